@@ -0,0 +1,72 @@
+//! Compares `List` and `ChunkedList`, and the ways of converting them
+//! from and to a `Vec`.
+//!
+//! Run with `cargo bench`. This is a plain timing loop rather than a
+//! statistical benchmark, so run it a few times and compare the
+//! numbers on the same machine.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use cons::{ChunkedList, List};
+
+const LEN: u64 = 1_000_000;
+const RUNS: u32 = 20;
+
+fn bench<R>(name: &str, mut f: impl FnMut() -> R) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        black_box(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{name:<24} {best:>12.2?}");
+}
+
+fn main() {
+    bench("List::cons", || {
+        (0..LEN).fold(List::new(), |xs, x| xs.cons(black_box(x)))
+    });
+    bench("ChunkedList::cons", || {
+        (0..LEN).fold(ChunkedList::new(), |xs, x| xs.cons(black_box(x)))
+    });
+
+    // each run converts a fresh copy of the same vector, so the clone
+    // is part of every timing below
+    let vec = (0..LEN).collect::<Vec<u64>>();
+    bench("List::from_iter", || {
+        vec.clone().into_iter().collect::<List<u64>>()
+    });
+    bench("List::from_vec", || List::from_vec(vec.clone()));
+    bench("ChunkedList::cons (rev)", || {
+        vec.clone()
+            .into_iter()
+            .rev()
+            .fold(ChunkedList::new(), |xs, x| xs.cons(x))
+    });
+    bench("ChunkedList::from_vec", || ChunkedList::from_vec(vec.clone()));
+    let list = (0..LEN).collect::<List<u64>>();
+    let chunked = (0..LEN).collect::<ChunkedList<u64>>();
+    // `list` is kept, so its nodes are shared and have to be cloned
+    bench("List::into_iter", || {
+        list.clone().into_iter().collect::<Vec<u64>>()
+    });
+    bench("List::into_vec", || list.clone().into_vec());
+    bench("List::iter", || list.iter().sum::<u64>());
+    bench("ChunkedList::iter", || chunked.iter().sum::<u64>());
+
+    bench("List::tail", || {
+        let mut xs = list.clone();
+        while let Some(tail) = xs.tail() {
+            xs = tail;
+        }
+        xs
+    });
+    bench("ChunkedList::tail", || {
+        let mut xs = chunked.clone();
+        while let Some(tail) = xs.tail() {
+            xs = tail;
+        }
+        xs
+    });
+}
@@ -0,0 +1,62 @@
+//! Expansion of pattern aliases declared with `cons::cons_pattern!`.
+//!
+//! An alias is a `macro_rules!` macro holding the tokens of the
+//! fragment, and those tokens pick up the alias's hygiene when it is
+//! expanded, so any bindings in them would be invisible to the code
+//! that used the alias. Re-spanning them here to resolve where the
+//! alias was used fixes that.
+
+use proc_macro::{Delimiter, Group, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+
+/// Expects `anchor [macro path] [before] [after] [fragment]` and
+/// expands to `macro path!(before fragment after)`, with the fragment
+/// resolved at the span of `anchor`.
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    let mut tokens = input.into_iter();
+    let anchor = tokens
+        .next()
+        .ok_or_else(|| Error::new(Span::call_site(), "expected an alias name"))?
+        .span();
+    let mut groups = tokens.map(|token| match token {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => Ok(g.stream()),
+        other => Err(Error::new(other.span(), "expected `[...]`")),
+    });
+    let mut next = || {
+        groups
+            .next()
+            .unwrap_or_else(|| Err(Error::new(Span::call_site(), "expected `[...]`")))
+    };
+    let (path, before, after, fragment) = (next()?, next()?, next()?, next()?);
+
+    let mut args = before;
+    args.extend(respan(fragment, anchor));
+    args.extend(after);
+    let mut expanded = path;
+    expanded.extend([
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, args)),
+    ]);
+    Ok(expanded)
+}
+
+fn respan(tokens: TokenStream, anchor: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| {
+            let span = token.span().resolved_at(anchor);
+            match token {
+                TokenTree::Group(g) => {
+                    let mut group = Group::new(g.delimiter(), respan(g.stream(), anchor));
+                    group.set_span(span);
+                    TokenTree::Group(group)
+                }
+                mut other => {
+                    other.set_span(span);
+                    other
+                }
+            }
+        })
+        .collect()
+}
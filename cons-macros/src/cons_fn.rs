@@ -0,0 +1,197 @@
+//! Expansion of `#[cons_fn]`, which allows cons patterns in the place
+//! of a function's parameter names.
+
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+use crate::pattern::{is_ident, is_path_sep, is_punct};
+
+/// What to do when an argument doesn't fit its pattern.
+enum Mode {
+    Panic,
+    Try,
+    Else(Group),
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
+    let mode = parse_mode(attr)?;
+    let mut tokens = item.into_iter().collect::<Vec<_>>();
+    let Some(fn_pos) = tokens.iter().position(|t| is_ident(t, "fn")) else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`#[cons_fn]` only applies to functions",
+        ));
+    };
+    let Some(params_pos) = find_params(&tokens, fn_pos) else {
+        return Err(Error::new(
+            tokens[fn_pos].span(),
+            "expected a parameter list",
+        ));
+    };
+    let body_pos = match tokens.last() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => tokens.len() - 1,
+        _ => {
+            return Err(Error::new(
+                tokens[fn_pos].span(),
+                "`#[cons_fn]` needs a function with a body",
+            ))
+        }
+    };
+
+    let TokenTree::Group(params) = &tokens[params_pos] else {
+        unreachable!()
+    };
+    let mut new_params = TokenStream::new();
+    let mut prelude = TokenStream::new();
+    for (i, param) in split_params(params.stream()).into_iter().enumerate() {
+        if i > 0 {
+            new_params.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        }
+        let Some(colon) = type_colon(&param) else {
+            new_params.extend(param);
+            continue;
+        };
+        let pattern = &param[..colon];
+        if !(0..pattern.len()).any(|i| is_path_sep(pattern, i)) {
+            new_params.extend(param);
+            continue;
+        }
+        // the argument gets a name that the body can't refer to, and is
+        // then taken apart by the pattern at the top of the body
+        let arg = Ident::new(&format!("__cons_arg{i}"), Span::mixed_site());
+        new_params.extend([TokenTree::Ident(arg.clone())]);
+        new_params.extend(param[colon..].iter().cloned());
+        prelude.extend(destructure(&mode, arg, pattern));
+    }
+    let mut group = Group::new(Delimiter::Parenthesis, new_params);
+    group.set_span(params.span());
+    tokens[params_pos] = TokenTree::Group(group);
+
+    let TokenTree::Group(body) = &tokens[body_pos] else {
+        unreachable!()
+    };
+    prelude.extend(body.stream());
+    let mut group = Group::new(Delimiter::Brace, prelude);
+    group.set_span(body.span());
+    tokens[body_pos] = TokenTree::Group(group);
+    Ok(tokens.into_iter().collect())
+}
+
+fn parse_mode(attr: TokenStream) -> Result<Mode> {
+    let tokens = attr.into_iter().collect::<Vec<_>>();
+    match tokens.as_slice() {
+        [] => Ok(Mode::Panic),
+        [kw] if is_ident(kw, "try") => Ok(Mode::Try),
+        [kw, TokenTree::Group(block)]
+            if is_ident(kw, "else") && block.delimiter() == Delimiter::Brace =>
+        {
+            Ok(Mode::Else(block.clone()))
+        }
+        [first, ..] => Err(Error::new(
+            first.span(),
+            "expected nothing, `try`, or `else { ... }`",
+        )),
+    }
+}
+
+/// Finds the parameter list after `fn name`, skipping over any
+/// generics.
+fn find_params(tokens: &[TokenTree], fn_pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(fn_pos + 2) {
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth = depth.saturating_sub(1),
+            TokenTree::Group(g) if depth == 0 && g.delimiter() == Delimiter::Parenthesis => {
+                return Some(i);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a parameter list at the commas that aren't inside generics.
+fn split_params(params: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut out = vec![Vec::new()];
+    let mut depth = 0usize;
+    for token in params {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            // `->` in a function pointer type isn't a closing bracket
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_arrow(out.last().unwrap()) => {
+                depth = depth.saturating_sub(1);
+            }
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                out.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        out.last_mut().unwrap().push(token);
+    }
+    if out.last().is_some_and(Vec::is_empty) {
+        out.pop();
+    }
+    out
+}
+
+fn is_arrow(before: &[TokenTree]) -> bool {
+    matches!(before.last(), Some(TokenTree::Punct(p)) if p.as_char() == '-' && p.spacing() == Spacing::Joint)
+}
+
+/// Finds the `:` between a parameter's pattern and its type, skipping
+/// the `::`s of a cons pattern.
+fn type_colon(param: &[TokenTree]) -> Option<usize> {
+    let mut i = 0;
+    while i < param.len() {
+        if is_path_sep(param, i) {
+            i += 2;
+        } else if is_punct(&param[i], ':') {
+            return Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Expands to a `cons!` or `try_cons!` statement that binds `pattern`
+/// from `arg`.
+fn destructure(mode: &Mode, arg: Ident, pattern: &[TokenTree]) -> TokenStream {
+    let mut invocation = TokenStream::new();
+    invocation.extend([
+        TokenTree::Ident(arg),
+        TokenTree::Ident(Ident::new("as", Span::call_site())),
+    ]);
+    // `_rest` stands in for `_`, so the tail isn't collected for nothing
+    invocation.extend(pattern.iter().map(|token| match token {
+        TokenTree::Ident(ident) if ident.to_string().starts_with('_') => {
+            TokenTree::Ident(Ident::new("_", ident.span()))
+        }
+        _ => token.clone(),
+    }));
+    if let Mode::Else(block) = mode {
+        invocation.extend([
+            TokenTree::Ident(Ident::new("else", Span::call_site())),
+            TokenTree::Group(block.clone()),
+        ]);
+    }
+    let name = match mode {
+        Mode::Try => "try_cons",
+        Mode::Panic | Mode::Else(_) => "cons",
+    };
+    let mut out = TokenStream::new();
+    out.extend([
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("cons", Span::call_site())),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new(name, Span::call_site())),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, invocation)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    out
+}
@@ -0,0 +1,79 @@
+//! Expansion of the printing half of `debug_cons!`.
+
+use std::str::FromStr;
+
+use proc_macro::{Delimiter, Group, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+use crate::pattern::{bindings, is_ident, is_path_sep, is_punct};
+
+/// Expands to an `eprintln!` for each name bound by the pattern of a
+/// `cons!` invocation, compiled only with debug assertions.
+pub fn print_bindings(input: TokenStream) -> Result<TokenStream> {
+    let tokens = input.into_iter().collect::<Vec<_>>();
+    let Some(start) = tokens
+        .iter()
+        .enumerate()
+        .position(|(i, t)| is_ident(t, "as") || is_arrow(&tokens, i))
+    else {
+        return Err(Error::new(
+            Span::call_site(),
+            "expected `<iterator> as <pattern>`",
+        ));
+    };
+    let start = if is_ident(&tokens[start], "as") {
+        start + 1
+    } else {
+        start + 2
+    };
+    let mut pattern = &tokens[start..];
+    // everything after the pattern: `else { ... }`, `; enumerate`,
+    // `; on_missing = ...`, or a message
+    if let Some(end) = pattern
+        .iter()
+        .position(|t| is_ident(t, "else") || is_punct(t, ';') || is_punct(t, ','))
+    {
+        pattern = &pattern[..end];
+    }
+    let mut names = bindings(pattern)?;
+    if is_lazy_tail(pattern) {
+        // an iterator left unconsumed may not implement `Debug`
+        names.pop();
+    }
+
+    let mut out = TokenStream::new();
+    for name in names {
+        let mut args = TokenStream::from_str(&format!(
+            "\"[{{}}:{{}}:{{}}] {name} = {{:#?}}\", \
+             ::core::file!(), ::core::line!(), ::core::column!(), &"
+        ))
+        .expect("eprintln arguments should parse");
+        args.extend([TokenTree::Ident(name)]);
+        out.extend(
+            TokenStream::from_str("#[cfg(debug_assertions)] ::std::eprintln!")
+                .expect("eprintln should parse"),
+        );
+        out.extend([
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, args)),
+            TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        ]);
+    }
+    Ok(out)
+}
+
+fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    matches!(
+        (tokens.get(i), tokens.get(i + 1)),
+        (Some(TokenTree::Punct(a)), Some(b)) if a.as_char() == '=' && is_punct(b, '>')
+    )
+}
+
+/// Returns `true` if the pattern ends in a tail bound with `@ iter` or
+/// `@ ..`.
+fn is_lazy_tail(pattern: &[TokenTree]) -> bool {
+    let last = (0..pattern.len())
+        .rev()
+        .find(|&i| is_path_sep(pattern, i))
+        .map_or(0, |i| i + 2);
+    pattern[last..].iter().any(|t| is_punct(t, '@'))
+}
@@ -0,0 +1,202 @@
+//! `#[derive(Uncons)]` for structs that wrap a single field, such as
+//! `struct Path(Vec<Segment>)`. The impl forwards to the field's own
+//! `Uncons` impl.
+
+use std::str::FromStr;
+
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+use crate::pattern::{is_ident, is_punct};
+
+pub fn derive_uncons(input: TokenStream) -> Result<TokenStream> {
+    let tokens = input.into_iter().collect::<Vec<_>>();
+    let mut i = skip_attrs_and_vis(&tokens);
+    match tokens.get(i) {
+        Some(t) if is_ident(t, "struct") => {}
+        Some(t) => {
+            return Err(Error::new(
+                t.span(),
+                "`Uncons` can only be derived for structs",
+            ))
+        }
+        None => return Err(Error::new(Span::call_site(), "expected a struct")),
+    }
+    let name = match tokens.get(i + 1) {
+        Some(TokenTree::Ident(name)) => name.clone(),
+        _ => return Err(Error::new(tokens[i].span(), "expected a struct name")),
+    };
+    i += 2;
+
+    let mut generics: &[TokenTree] = &[];
+    if tokens.get(i).is_some_and(|t| is_punct(t, '<')) {
+        let end = closing_angle(&tokens, i)
+            .ok_or_else(|| Error::new(tokens[i].span(), "unclosed `<`"))?;
+        generics = &tokens[i + 1..end];
+        i = end + 1;
+    }
+
+    let rest = &tokens[i..];
+    let Some(body) = rest.iter().position(|t| {
+        matches!(t, TokenTree::Group(g) if matches!(g.delimiter(), Delimiter::Parenthesis | Delimiter::Brace))
+    }) else {
+        return Err(Error::new(
+            name.span(),
+            "`Uncons` can only be derived for structs with exactly one field",
+        ));
+    };
+    let TokenTree::Group(fields) = &rest[body] else {
+        unreachable!()
+    };
+    let tuple = fields.delimiter() == Delimiter::Parenthesis;
+    let where_clause = if tuple {
+        &rest[body + 1..]
+    } else {
+        &rest[..body]
+    };
+    let predicates = match where_clause {
+        [kw, preds @ ..] if is_ident(kw, "where") => preds
+            .iter()
+            .take_while(|t| !is_punct(t, ';'))
+            .cloned()
+            .collect::<TokenStream>(),
+        _ => TokenStream::new(),
+    };
+
+    let fields = fields.stream().into_iter().collect::<Vec<_>>();
+    let fields = split_top_level(&fields);
+    let [field] = fields.as_slice() else {
+        return Err(Error::new(
+            name.span(),
+            "`Uncons` can only be derived for structs with exactly one field",
+        ));
+    };
+    let field = &field[skip_attrs_and_vis(field)..];
+    let (member, ty) = if tuple {
+        (String::from("0"), field)
+    } else {
+        match field {
+            [TokenTree::Ident(ident), colon, ty @ ..] if is_punct(colon, ':') => {
+                (ident.to_string(), ty)
+            }
+            _ => return Err(Error::new(name.span(), "expected a named field")),
+        }
+    };
+    let ty = ty.iter().cloned().collect::<TokenStream>().to_string();
+
+    let params = split_top_level(generics);
+    let impl_params = params
+        .iter()
+        .map(|param| {
+            strip_default(param)
+                .iter()
+                .cloned()
+                .collect::<TokenStream>()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let args = params
+        .iter()
+        .map(|param| generic_arg(param))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let expanded = format!(
+        "impl<{impl_params}> ::cons::Uncons for {name}<{args}> \
+         where {ty}: ::cons::Uncons, {predicates} {{ \
+             type Head = <{ty} as ::cons::Uncons>::Head; \
+             type Rest = <{ty} as ::cons::Uncons>::Rest; \
+             fn uncons(self) -> ::core::option::Option<(Self::Head, Self::Rest)> {{ \
+                 ::cons::Uncons::uncons(self.{member}) \
+             }} \
+         }}"
+    );
+    Ok(TokenStream::from_str(&expanded).expect("derived impl should parse"))
+}
+
+/// Returns the index of the first token after any outer attributes and
+/// a visibility.
+fn skip_attrs_and_vis(tokens: &[TokenTree]) -> usize {
+    let mut i = 0;
+    while tokens.get(i).is_some_and(|t| is_punct(t, '#')) {
+        i += 2;
+    }
+    if tokens.get(i).is_some_and(|t| is_ident(t, "pub")) {
+        i += 1;
+        if matches!(tokens.get(i), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis)
+        {
+            i += 1;
+        }
+    }
+    i
+}
+
+/// Finds the `>` that closes the `<` at `open`.
+fn closing_angle(tokens: &[TokenTree], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(tokens, i) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Splits a comma separated list, ignoring commas inside generic
+/// arguments, and drops a trailing comma.
+fn split_top_level(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(tokens, i) {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && is_punct(token, ',') {
+            parts.push(&tokens[start..i]);
+            start = i + 1;
+        }
+    }
+    if start < tokens.len() {
+        parts.push(&tokens[start..]);
+    }
+    parts
+}
+
+fn is_arrow(tokens: &[TokenTree], i: usize) -> bool {
+    i > 0 && is_punct(&tokens[i - 1], '-')
+}
+
+/// Drops the `= Default` from a generic parameter, which isn't allowed
+/// on impls.
+fn strip_default(param: &[TokenTree]) -> &[TokenTree] {
+    let mut depth = 0usize;
+    for (i, token) in param.iter().enumerate() {
+        if is_punct(token, '<') {
+            depth += 1;
+        } else if is_punct(token, '>') && !is_arrow(param, i) {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && is_punct(token, '=') {
+            return &param[..i];
+        }
+    }
+    param
+}
+
+/// The argument that names a generic parameter, e.g. `'a` for `'a: 'b`,
+/// `T` for `T: Clone`, or `N` for `const N: usize`.
+fn generic_arg(param: &[TokenTree]) -> String {
+    match param {
+        [quote, lifetime, ..] if is_punct(quote, '\'') => format!("'{lifetime}"),
+        [kw, name, ..] if is_ident(kw, "const") => name.to_string(),
+        [name, ..] => name.to_string(),
+        [] => String::new(),
+    }
+}
@@ -0,0 +1,45 @@
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// A compile error pointing at a specific token.
+pub struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Expands to `::core::compile_error!("...")`, with every token
+    /// spanned at the offending token so the error is reported there.
+    pub fn into_compile_error(self) -> TokenStream {
+        let span = self.span;
+        let punct = |c, spacing| {
+            let mut p = Punct::new(c, spacing);
+            p.set_span(span);
+            TokenTree::Punct(p)
+        };
+        let mut message = Literal::string(&self.message);
+        message.set_span(span);
+        let mut args = Group::new(Delimiter::Parenthesis, TokenTree::Literal(message).into());
+        args.set_span(span);
+        [
+            punct(':', Spacing::Joint),
+            punct(':', Spacing::Alone),
+            TokenTree::Ident(Ident::new("core", span)),
+            punct(':', Spacing::Joint),
+            punct(':', Spacing::Alone),
+            TokenTree::Ident(Ident::new("compile_error", span)),
+            punct('!', Spacing::Alone),
+            TokenTree::Group(args),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
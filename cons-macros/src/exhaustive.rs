@@ -0,0 +1,185 @@
+//! Exhaustiveness and reachability checks for `cons_match!` arms.
+//!
+//! Each arm is reduced to the lengths of iterator it can match: a
+//! pattern with `k` heads matches exactly `k` elements if it ends in
+//! `nil`, and at least `k` otherwise. Arms whose heads might not match
+//! (literals, guards, enum variants, ...) are still checked for
+//! reachability, but are never counted as covering anything, since
+//! there's no way to tell from the tokens alone whether they do.
+
+use std::str::FromStr;
+
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+use crate::pattern::{is_ident, is_path_sep, is_punct};
+
+/// The lengths an arm can match.
+#[derive(Clone, Copy)]
+enum Len {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+struct Arm {
+    span: Span,
+    len: Len,
+    /// Whether the arm matches every iterator of the right length.
+    irrefutable: bool,
+}
+
+/// Checks the arms of a `cons_match!`, given as one bracketed group of
+/// pattern tokens per arm.
+///
+/// Arms that can never match because of earlier arms produce a
+/// warning. If the irrefutable arms don't cover every length, that is
+/// an error, since the match could panic for those lengths; refutable
+/// arms don't help, as they might not match either.
+pub fn check_arms(input: TokenStream) -> Result<TokenStream> {
+    let Some(arms) = input
+        .into_iter()
+        .map(|arm| match arm {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => parse_arm(&g),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        // malformed patterns are reported by `cons_match!` itself
+        return Ok(TokenStream::new());
+    };
+
+    let mut covered = Coverage::default();
+    let mut warnings = TokenStream::new();
+    for arm in &arms {
+        if covered.contains(arm.len) {
+            warnings.extend(unreachable_warning(arm.span));
+        }
+        if arm.irrefutable {
+            covered.add(arm.len);
+        }
+    }
+
+    if let Some(n) = covered.first_missing() {
+        let span = arms.last().map_or(Span::call_site(), |arm| arm.span);
+        return Err(Error::new(
+            span,
+            format!(
+                "non-exhaustive `cons_match!`: no arm matches {n} element{}; \
+                 add a `_ => ...` arm to handle the rest",
+                if n == 1 { "" } else { "s" },
+            ),
+        ));
+    }
+    Ok(warnings)
+}
+
+fn parse_arm(group: &Group) -> Option<Arm> {
+    let tokens = group.stream().into_iter().collect::<Vec<_>>();
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_path_sep(&tokens, i) {
+            segments.push(&tokens[start..i]);
+            i += 2;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    segments.push(&tokens[start..]);
+
+    let (last, heads) = segments.split_last()?;
+    let exact = match last {
+        [t] if is_ident(t, "nil") || is_empty_brackets(t) => true,
+        [TokenTree::Ident(_)] => false,
+        _ => return None,
+    };
+    let k = heads.len();
+    Some(Arm {
+        span: tokens.first()?.span(),
+        len: if exact {
+            Len::Exactly(k)
+        } else {
+            Len::AtLeast(k)
+        },
+        irrefutable: heads.iter().all(|head| is_irrefutable(head)),
+    })
+}
+
+/// A conservative guess at whether a head pattern always matches.
+/// Bindings, `_`, and tuples of those do; anything else (literals,
+/// paths, variants, guards) might not.
+fn is_irrefutable(tokens: &[TokenTree]) -> bool {
+    tokens.iter().all(|token| match token {
+        TokenTree::Ident(ident) => {
+            let name = ident.to_string();
+            // upper case names are almost always unit structs, enum
+            // variants, or constants rather than bindings
+            !name.starts_with(|c: char| c.is_uppercase()) && name != "if"
+        }
+        TokenTree::Punct(p) => matches!(p.as_char(), ',' | '&'),
+        TokenTree::Group(g) => {
+            g.delimiter() == Delimiter::Parenthesis
+                && is_irrefutable(&g.stream().into_iter().collect::<Vec<_>>())
+        }
+        TokenTree::Literal(_) => false,
+    }) && !tokens.iter().any(|t| is_punct(t, '@'))
+}
+
+fn is_empty_brackets(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket && g.stream().is_empty())
+}
+
+/// The set of lengths matched by the irrefutable arms so far.
+#[derive(Default)]
+struct Coverage {
+    exact: Vec<usize>,
+    /// Every length from this one up is covered.
+    from: Option<usize>,
+}
+
+impl Coverage {
+    fn add(&mut self, len: Len) {
+        match len {
+            Len::Exactly(k) => self.exact.push(k),
+            Len::AtLeast(k) => self.from = Some(self.from.map_or(k, |from| from.min(k))),
+        }
+    }
+
+    fn has(&self, n: usize) -> bool {
+        self.from.is_some_and(|from| n >= from) || self.exact.contains(&n)
+    }
+
+    fn contains(&self, len: Len) -> bool {
+        match len {
+            Len::Exactly(k) => self.has(k),
+            Len::AtLeast(k) => self.from.is_some_and(|from| (k..from).all(|n| self.has(n))),
+        }
+    }
+
+    fn first_missing(&self) -> Option<usize> {
+        match self.from {
+            Some(from) => (0..from).find(|&n| !self.has(n)),
+            None => (0..).find(|&n| !self.has(n)),
+        }
+    }
+}
+
+/// There's no stable way for a proc macro to emit a warning, so this
+/// uses a deprecated constant, spanned at the arm, to get one.
+fn unreachable_warning(span: Span) -> TokenStream {
+    let mut block = TokenStream::from_str(
+        "#[deprecated(note = \"unreachable `cons_match!` arm: the arms before it \
+         already match everything it could\")] \
+         #[allow(non_upper_case_globals)] \
+         const unreachable_arm: () = (); \
+         let _ = ",
+    )
+    .unwrap();
+    block.extend([
+        TokenTree::Ident(Ident::new("unreachable_arm", span)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+    TokenStream::from(TokenTree::Group(Group::new(Delimiter::Brace, block)))
+}
@@ -0,0 +1,165 @@
+//! Expansion of `fun!`, which turns a function signature and a list of
+//! SML-style clauses into a function whose body is a `cons_match!`.
+
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+use crate::pattern::is_ident;
+
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    let mut tokens = input.into_iter().collect::<Vec<_>>();
+    // `cons::fun!` passes its own `$crate` followed by `;`
+    let krate = tokens.remove(0);
+    tokens.remove(0);
+
+    let clauses = match tokens.pop() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => g,
+        Some(other) => {
+            return Err(Error::new(
+                other.span(),
+                "expected a block of clauses at the end of the function",
+            ))
+        }
+        None => return Err(Error::new(Span::call_site(), "expected a function")),
+    };
+    let Some(fn_pos) = tokens.iter().position(|t| is_ident(t, "fn")) else {
+        return Err(Error::new(Span::call_site(), "expected a function"));
+    };
+    let name = match tokens.get(fn_pos + 1) {
+        Some(TokenTree::Ident(name)) => name.clone(),
+        Some(other) => return Err(Error::new(other.span(), "expected a function name")),
+        None => {
+            return Err(Error::new(
+                tokens[fn_pos].span(),
+                "expected a function name",
+            ))
+        }
+    };
+    let param = find_param(&tokens[fn_pos + 2..], &name)?;
+    let body = expand_clauses(clauses.stream(), &name)?;
+
+    let mut invocation = TokenStream::new();
+    invocation.extend([
+        TokenTree::Ident(param),
+        TokenTree::Group(Group::new(Delimiter::Brace, body)),
+    ]);
+    let mut cons_match = TokenStream::new();
+    cons_match.extend([
+        krate,
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("cons_match", Span::call_site())),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, invocation)),
+    ]);
+    let mut block = Group::new(Delimiter::Brace, cons_match);
+    block.set_span(clauses.span());
+    tokens.push(TokenTree::Group(block));
+    Ok(tokens.into_iter().collect())
+}
+
+/// Finds the name of the function's single parameter, skipping over
+/// any generics before the parameter list.
+fn find_param(tokens: &[TokenTree], name: &Ident) -> Result<Ident> {
+    let mut depth = 0usize;
+    for token in tokens {
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth = depth.saturating_sub(1),
+            TokenTree::Group(g) if depth == 0 && g.delimiter() == Delimiter::Parenthesis => {
+                return param_name(g);
+            }
+            _ => {}
+        }
+    }
+    Err(Error::new(
+        name.span(),
+        format!("expected a parameter list after `{name}`"),
+    ))
+}
+
+fn param_name(params: &Group) -> Result<Ident> {
+    let tokens = params.stream().into_iter().collect::<Vec<_>>();
+    let mut depth = 0usize;
+    for token in &tokens {
+        match token {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth = depth.saturating_sub(1),
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                return Err(Error::new(
+                    p.span(),
+                    "functions defined with `fun!` take exactly one parameter",
+                ));
+            }
+            _ => {}
+        }
+    }
+    match tokens.as_slice() {
+        [TokenTree::Ident(m), TokenTree::Ident(param), TokenTree::Punct(colon), ..]
+            if m.to_string() == "mut" && colon.as_char() == ':' =>
+        {
+            Ok(param.clone())
+        }
+        [TokenTree::Ident(param), TokenTree::Punct(colon), ..] if colon.as_char() == ':' => {
+            Ok(param.clone())
+        }
+        [] => Err(Error::new(
+            params.span(),
+            "functions defined with `fun!` take exactly one parameter",
+        )),
+        [first, ..] => Err(Error::new(first.span(), "expected a parameter name")),
+    }
+}
+
+/// Rewrites each `name(pattern) => body` clause into a `cons_match!`
+/// arm `pattern => body`.
+fn expand_clauses(clauses: TokenStream, name: &Ident) -> Result<TokenStream> {
+    let tokens = clauses.into_iter().collect::<Vec<_>>();
+    if tokens.is_empty() {
+        return Err(Error::new(
+            name.span(),
+            format!("expected at least one clause, e.g. `{name}(x::xs) => ...`"),
+        ));
+    }
+    let mut arms = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some(pattern) = clause_head(&tokens[i..], name) else {
+            return Err(Error::new(
+                tokens[i].span(),
+                format!("expected a clause, e.g. `{name}(x::xs) => ...`"),
+            ));
+        };
+        arms.extend(pattern.stream());
+        // skip `name(...)`, leaving `=>` to be copied as-is
+        i += 2;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            arms.extend([token.clone()]);
+            i += 1;
+            if matches!(token, TokenTree::Punct(p) if p.as_char() == ',')
+                && clause_head(&tokens[i..], name).is_some()
+            {
+                break;
+            }
+        }
+    }
+    Ok(arms)
+}
+
+/// If `tokens` starts with `name(...) =>`, returns the parenthesized
+/// group.
+fn clause_head<'a>(tokens: &'a [TokenTree], name: &Ident) -> Option<&'a Group> {
+    match tokens {
+        [TokenTree::Ident(ident), TokenTree::Group(g), TokenTree::Punct(eq), TokenTree::Punct(gt), ..]
+            if ident.to_string() == name.to_string()
+                && g.delimiter() == Delimiter::Parenthesis
+                && eq.as_char() == '='
+                && eq.spacing() == Spacing::Joint
+                && gt.as_char() == '>' =>
+        {
+            Some(g)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,116 @@
+/*!
+Procedural macros backing the [`cons`](https://docs.rs/cons) crate.
+
+You shouldn't need to depend on this crate directly; everything is
+re-exported from `cons`.
+*/
+
+use proc_macro::{Punct, Spacing, Span, TokenStream, TokenTree};
+
+mod alias;
+mod cons_fn;
+mod debug;
+mod derive;
+mod error;
+mod exhaustive;
+mod fun;
+mod pattern;
+
+use error::Error;
+
+/// Produces a readable, spanned compile error for an invocation of one
+/// of the `cons` macros that the `macro_rules` implementation couldn't
+/// match.
+///
+/// The first token selects what is being checked: `invocation` for a
+/// whole `<iterator> as <pattern>` invocation, or `pattern` for the
+/// remainder of a pattern.
+#[doc(hidden)]
+#[proc_macro]
+pub fn diagnose(input: TokenStream) -> TokenStream {
+    let mut tokens = input.into_iter();
+    let kind = tokens.next().map(|t| t.to_string());
+    let tokens = tokens.collect::<Vec<TokenTree>>();
+    let result = match kind.as_deref() {
+        Some("invocation") => pattern::check_invocation(tokens),
+        Some("pattern") => pattern::check_pattern(tokens),
+        _ => Err(Error::new(Span::call_site(), "unknown diagnostic")),
+    };
+    let error = match result {
+        Ok(()) => Error::new(Span::call_site(), "invalid cons pattern"),
+        Err(e) => e,
+    };
+    error.into_compile_error()
+}
+
+/// Checks the arms of a `cons_match!` for unreachable arms and, when
+/// that can be decided, missing lengths. Each arm's pattern is passed as
+/// a bracketed group.
+#[doc(hidden)]
+#[proc_macro]
+pub fn check_arms(input: TokenStream) -> TokenStream {
+    exhaustive::check_arms(input).unwrap_or_else(Error::into_compile_error)
+}
+
+/// Reports a name that is bound more than once in a cons pattern, and
+/// expands to nothing otherwise.
+#[doc(hidden)]
+#[proc_macro]
+pub fn check_bindings(input: TokenStream) -> TokenStream {
+    match pattern::check_bindings(input.into_iter().collect()) {
+        Ok(()) => TokenStream::new(),
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+/// Prints each name bound by the pattern of a `cons!` invocation, for
+/// `cons::debug_cons!`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn print_bindings(input: TokenStream) -> TokenStream {
+    debug::print_bindings(input).unwrap_or_else(Error::into_compile_error)
+}
+
+/// Splices the fragment of a `cons_pattern!` alias into the pattern it
+/// was used in, so that its bindings are visible where it was used.
+#[doc(hidden)]
+#[proc_macro]
+pub fn expand_alias(input: TokenStream) -> TokenStream {
+    alias::expand(input).unwrap_or_else(Error::into_compile_error)
+}
+
+/// Expands a function whose body is a list of SML-style clauses into a
+/// function whose body is a `cons_match!`. See `cons::fun!`.
+///
+/// The first tokens must be the path to the `cons` crate followed by
+/// `;`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn fun(input: TokenStream) -> TokenStream {
+    fun::expand(input).unwrap_or_else(Error::into_compile_error)
+}
+
+/// Replaces cons patterns in a function's parameter list with plain
+/// names, and takes the arguments apart with `cons!` at the top of the
+/// body. See `cons::cons_fn`.
+#[proc_macro_attribute]
+pub fn cons_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    cons_fn::expand(attr, item).unwrap_or_else(|e| {
+        let mut error = e.into_compile_error();
+        error.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        error
+    })
+}
+
+/// Implements `cons::Uncons` for a struct with a single field by
+/// forwarding to the field's implementation, so a newtype around a
+/// `Vec` or slice can be destructured like the collection it wraps.
+#[proc_macro_derive(Uncons)]
+pub fn derive_uncons(input: TokenStream) -> TokenStream {
+    derive::derive_uncons(input).unwrap_or_else(|e| {
+        // in item position, `compile_error!(...)` needs a semicolon
+        let mut error = e.into_compile_error();
+        error.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+        error
+    })
+}
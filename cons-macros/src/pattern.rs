@@ -0,0 +1,384 @@
+//! Diagnostics for malformed cons patterns.
+//!
+//! The `macro_rules` implementation of `cons!` forwards anything it
+//! can't match to [`check_invocation`] or [`check_pattern`], which
+//! walk the tokens and report the first thing that is wrong, pointing
+//! at the offending token.
+
+use proc_macro::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
+
+use crate::error::{Error, Result};
+
+/// Checks a whole `<iterator> as <pattern>` invocation.
+pub fn check_invocation(tokens: Vec<TokenTree>) -> Result<()> {
+    let Some(as_pos) = tokens.iter().position(|t| is_ident(t, "as")) else {
+        return Err(Error::new(
+            Span::call_site(),
+            "expected `<iterator> as <pattern>`, e.g. `cons!(v as x::xs)`",
+        ));
+    };
+    let (iter, pattern) = tokens.split_at(as_pos);
+    if iter.is_empty() {
+        return Err(Error::new(
+            tokens[as_pos].span(),
+            "expected an iterator before `as`",
+        ));
+    }
+    let mut pattern = &pattern[1..];
+    if let [rest @ .., else_kw, TokenTree::Group(block)] = pattern {
+        if is_ident(else_kw, "else") && block.delimiter() == Delimiter::Brace {
+            pattern = rest;
+        }
+    }
+    if let [rest @ .., semi, kw] = pattern {
+        if is_punct(semi, ';') && is_ident(kw, "enumerate") {
+            pattern = rest;
+        }
+    }
+    if pattern.is_empty() {
+        return Err(Error::new(
+            tokens[as_pos].span(),
+            "expected a cons pattern after `as`",
+        ));
+    }
+    check_pattern(pattern.to_vec())
+}
+
+/// Checks the pattern part of an invocation, i.e. everything after
+/// `as`.
+pub fn check_pattern(tokens: Vec<TokenTree>) -> Result<()> {
+    if is_path_sep(&tokens, 0) {
+        // the `macro_rules` side has already consumed whatever came
+        // before this `::`, so there must have been two in a row
+        return Err(Error::new(tokens[0].span(), "unexpected `::`"));
+    }
+    let segments = split_segments(&tokens)?;
+    let last = segments.len() - 1;
+    for (i, segment) in segments.iter().enumerate() {
+        check_segment(segment, i == last)?;
+    }
+    Ok(())
+}
+
+/// One element of a pattern, i.e. the tokens between two `::`.
+struct Segment<'a> {
+    tokens: &'a [TokenTree],
+    /// The `::` before this segment, if there is one.
+    sep: Option<Span>,
+}
+
+fn split_segments(tokens: &[TokenTree]) -> Result<Vec<Segment<'_>>> {
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut sep = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_path_sep(tokens, i) {
+            segments.push(Segment {
+                tokens: &tokens[start..i],
+                sep,
+            });
+            sep = Some(tokens[i].span());
+            i += 2;
+            start = i;
+        } else if is_punct(&tokens[i], ':') {
+            if let [TokenTree::Ident(_)] = &tokens[start..i] {
+                // a tail with a type, such as `xs: HashSet<_>`
+                segments.push(Segment {
+                    tokens: &tokens[start..i],
+                    sep,
+                });
+                return Ok(segments);
+            }
+            return Err(Error::new(tokens[i].span(), "expected `::`, found `:`"));
+        } else {
+            i += 1;
+        }
+    }
+    segments.push(Segment {
+        tokens: &tokens[start..],
+        sep,
+    });
+    Ok(segments)
+}
+
+fn check_segment(segment: &Segment<'_>, is_last: bool) -> Result<()> {
+    let mut tokens = segment.tokens;
+    // `mut x`, `ref x` and `ref mut x` are checked like `x`
+    while let [kw, TokenTree::Ident(_), ..] = tokens {
+        if !(is_ident(kw, "ref") || is_ident(kw, "mut")) {
+            break;
+        }
+        tokens = &tokens[1..];
+    }
+    let Some(first) = tokens.first() else {
+        return Err(match segment.sep {
+            Some(sep) => Error::new(sep, "expected identifier or pattern after `::`"),
+            None => Error::new(Span::call_site(), "expected a cons pattern"),
+        });
+    };
+    match first {
+        TokenTree::Ident(ident) if ident.to_string() == "nil" && !is_last => {
+            return Err(Error::new(
+                ident.span(),
+                "`nil` can only appear at the end of a cons pattern",
+            ));
+        }
+        TokenTree::Ident(_) => {}
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket && g.stream().is_empty() => {
+            if !is_last {
+                return Err(Error::new(
+                    g.span(),
+                    "`[]` can only appear at the end of a cons pattern",
+                ));
+            }
+        }
+        TokenTree::Literal(lit) if is_last => {
+            return Err(Error::new(
+                lit.span(),
+                "the end of a cons pattern must be an identifier, `_`, or `nil`",
+            ));
+        }
+        TokenTree::Literal(_) => {}
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+            if g.stream().is_empty() {
+                return Err(Error::new(
+                    g.span(),
+                    "expected a pattern inside parentheses",
+                ));
+            }
+            let with_count = matches!(
+                g.stream().into_iter().collect::<Vec<_>>().as_slice(),
+                [TokenTree::Ident(_), .., comma, TokenTree::Ident(_)] if is_punct(comma, ',')
+            );
+            if is_last && tokens.len() == 1 && !with_count {
+                return Err(Error::new(
+                    g.span(),
+                    "the end of a cons pattern must be an identifier, `_`, or `nil`",
+                ));
+            }
+        }
+        other => {
+            return Err(Error::new(
+                other.span(),
+                format!("expected identifier or parenthesized pattern, found `{other}`"),
+            ));
+        }
+    }
+    match &tokens[1..] {
+        [] => Ok(()),
+        [bang] if is_last && is_punct(bang, '!') => Ok(()),
+        [at, kw] if is_last && is_punct(at, '@') && (is_ident(kw, "iter") || is_ident(kw, "par")) => {
+            Ok(())
+        }
+        [at, a, b] if is_last && is_punct(at, '@') && is_punct(a, '.') && is_punct(b, '.') => {
+            Ok(())
+        }
+        [at, small, lt, _, gt]
+            if is_last
+                && is_punct(at, '@')
+                && is_ident(small, "small")
+                && is_punct(lt, '<')
+                && is_punct(gt, '>') =>
+        {
+            Ok(())
+        }
+        [TokenTree::Group(g)] if is_last && g.delimiter() == Delimiter::Bracket => Ok(()),
+        [at, rest @ ..] if is_last && is_punct(at, '@') => Err(Error::new(
+            rest.first().map_or(at.span(), TokenTree::span),
+            "expected `iter`, `..`, `par`, or `small<N>` after `@`",
+        )),
+        [TokenTree::Group(g), ..] if matches!(first, TokenTree::Ident(_)) => {
+            let pat = tokens.iter().cloned().collect::<TokenStream>();
+            Err(Error::new(
+                g.span(),
+                format!("destructuring patterns must be wrapped in parentheses: `({pat})`"),
+            ))
+        }
+        [next, ..] => Err(Error::new(
+            next.span(),
+            format!("expected `::` after `{first}`, found `{next}`"),
+        )),
+    }
+}
+
+/// Checks that no name is bound twice in a pattern, which would
+/// otherwise silently shadow the first binding. Malformed patterns are
+/// left alone, since `@__` reports them.
+pub fn check_bindings(tokens: Vec<TokenTree>) -> Result<()> {
+    bindings(&tokens).map(drop)
+}
+
+/// Returns the names a pattern binds, in order, or an error pointing at
+/// the first name that is bound twice.
+pub fn bindings(tokens: &[TokenTree]) -> Result<Vec<Ident>> {
+    let mut names = vec![];
+    let mut i = 0;
+    let mut start = 0;
+    while i <= tokens.len() {
+        if i == tokens.len() || is_path_sep(tokens, i) {
+            top_level_bindings(&tokens[start..i], &mut names)?;
+            i += 2;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    Ok(names)
+}
+
+/// Collects the bindings of one element of a pattern, i.e. the tokens
+/// between two `::`.
+fn top_level_bindings(tokens: &[TokenTree], names: &mut Vec<Ident>) -> Result<()> {
+    match tokens {
+        // an alias, whose bindings aren't known until it is expanded
+        [TokenTree::Ident(_), bang, TokenTree::Group(_), ..] if is_punct(bang, '!') => Ok(()),
+        // a plain name, a tail with `@ iter`, `@ ..`, `@ par`, `@ small<N>`,
+        // `in alloc`, `: Type` or a length such as `[>= 2]`, or the last
+        // element before a `!`
+        [TokenTree::Ident(ident), rest @ ..]
+            if rest.first().is_none_or(|t| {
+                is_punct(t, '@')
+                    || is_punct(t, ':')
+                    || is_punct(t, '!')
+                    || is_ident(t, "in")
+                    || matches!(t, TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket)
+            }) =>
+        {
+            if !is_reserved(ident) {
+                bind(ident, names)?;
+            }
+            Ok(())
+        }
+        // a head with a type, such as `(x: u64)`, or a default, such as
+        // `(x = 0)`
+        [TokenTree::Group(g)] if g.delimiter() == Delimiter::Parenthesis => {
+            let inner = g.stream().into_iter().collect::<Vec<_>>();
+            let typed = match inner.as_slice() {
+                [kw, TokenTree::Ident(ident), colon, ..]
+                    if is_ident(kw, "mut") && is_punct(colon, ':') && !is_path_sep(&inner, 2) =>
+                {
+                    Some(ident)
+                }
+                [TokenTree::Ident(ident), colon, ..]
+                    if is_punct(colon, ':') && !is_path_sep(&inner, 1) =>
+                {
+                    Some(ident)
+                }
+                [TokenTree::Ident(ident), eq, ..] if is_punct(eq, '=') => Some(ident),
+                _ => None,
+            };
+            if let (Some(_), [.., comma, TokenTree::Ident(count)]) = (typed, inner.as_slice()) {
+                // a tail with a type and a count, such as `(xs: Vec<_>, n)`
+                if is_punct(comma, ',') {
+                    bind(count, names)?;
+                }
+            }
+            match typed {
+                Some(ident) => bind(ident, names),
+                None => nested_bindings(&inner, names),
+            }
+        }
+        _ => nested_bindings(tokens, names),
+    }
+}
+
+/// Collects the bindings of an ordinary Rust pattern, which may contain
+/// a guard.
+///
+/// The alternatives of an or-pattern such as `Ok(x) | Err(x)` each bind
+/// the same names, so the names are collected once, from all of them.
+/// Rust itself reports an alternative that is missing one.
+fn nested_bindings(tokens: &[TokenTree], names: &mut Vec<Ident>) -> Result<()> {
+    // everything after `if` is a guard expression
+    let end = tokens
+        .iter()
+        .position(|t| is_ident(t, "if"))
+        .unwrap_or(tokens.len());
+    let mut union = vec![];
+    for alt in tokens[..end].split(|t| is_punct(t, '|')) {
+        for ident in alternative_bindings(alt)? {
+            if !is_bound(&ident, &union) {
+                union.push(ident);
+            }
+        }
+    }
+    union.iter().try_for_each(|ident| bind(ident, names))
+}
+
+/// Collects the bindings of one alternative of an or-pattern.
+fn alternative_bindings(tokens: &[TokenTree]) -> Result<Vec<Ident>> {
+    let mut names = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(g) => {
+                nested_bindings(&g.stream().into_iter().collect::<Vec<_>>(), &mut names)?;
+            }
+            TokenTree::Ident(ident) if is_binding(tokens, i) => bind(ident, &mut names)?,
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+/// Returns `true` if the identifier at `i` introduces a name, rather
+/// than being a keyword, a path, a constant, or a struct field.
+fn is_binding(tokens: &[TokenTree], i: usize) -> bool {
+    let TokenTree::Ident(ident) = &tokens[i] else {
+        return false;
+    };
+    if is_reserved(ident) {
+        return false;
+    }
+    let path_before = i >= 2 && is_path_sep(tokens, i - 2);
+    let field_name =
+        tokens.get(i + 1).is_some_and(|t| is_punct(t, ':')) && !is_path_sep(tokens, i + 1);
+    let followed_by = |c| tokens.get(i + 1).is_some_and(|t| is_punct(t, c));
+    let call = matches!(tokens.get(i + 1), Some(TokenTree::Group(g)) if g.delimiter() != Delimiter::Bracket);
+    !(path_before || field_name || is_path_sep(tokens, i + 1) || followed_by('!') || call)
+}
+
+/// Returns `true` for keywords, for the words that mean something in a
+/// cons pattern, and for names that look like constants or variants.
+fn is_reserved(ident: &Ident) -> bool {
+    let name = ident.to_string();
+    let name = name.trim_start_matches("r#");
+    matches!(
+        name,
+        "_" | "ref" | "mut" | "box" | "true" | "false" | "nil" | "iter"
+    ) || name.starts_with(|c: char| c.is_uppercase())
+}
+
+fn is_bound(ident: &Ident, names: &[Ident]) -> bool {
+    let name = ident.to_string();
+    names.iter().any(|n| n.to_string() == name)
+}
+
+fn bind(ident: &Ident, names: &mut Vec<Ident>) -> Result<()> {
+    if is_bound(ident, names) {
+        let name = ident.to_string();
+        return Err(Error::new(
+            ident.span(),
+            format!("identifier `{name}` is bound more than once in the same cons pattern"),
+        ));
+    }
+    names.push(ident.clone());
+    Ok(())
+}
+
+pub fn is_ident(token: &TokenTree, name: &str) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident.to_string() == name)
+}
+
+pub fn is_punct(token: &TokenTree, c: char) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == c)
+}
+
+pub fn is_path_sep(tokens: &[TokenTree], i: usize) -> bool {
+    match (tokens.get(i), tokens.get(i + 1)) {
+        (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b))) => {
+            a.as_char() == ':' && a.spacing() == Spacing::Joint && b.as_char() == ':'
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,110 @@
+//! Random lists for property tests, behind the `proptest` and
+//! `quickcheck` features.
+//!
+//! Both generate a list the same way as a `Vec` of the same elements,
+//! and shrink a failing list towards `nil` by dropping elements and
+//! towards smaller elements by shrinking each one.
+
+#[cfg(feature = "quickcheck")]
+use alloc::boxed::Box;
+#[cfg(feature = "proptest")]
+use alloc::vec::Vec;
+
+use crate::list::{GenericList, PointerKind};
+#[cfg(doc)]
+use crate::List;
+
+#[cfg(feature = "proptest")]
+impl<T: proptest::arbitrary::Arbitrary, P: PointerKind> proptest::arbitrary::Arbitrary
+    for GenericList<T, P>
+{
+    type Parameters = (proptest::collection::SizeRange, T::Parameters);
+    type Strategy = proptest::strategy::Map<
+        proptest::collection::VecStrategy<T::Strategy>,
+        fn(Vec<T>) -> Self,
+    >;
+
+    /// Generates lists whose lengths are in the size range and whose
+    /// elements are generated with `T`'s parameters, like a `Vec`.
+    ///
+    /// ```rust
+    /// use cons::List;
+    /// use proptest::prelude::*;
+    ///
+    /// proptest! {
+    ///     fn rev_twice(xs: List<u8>) {
+    ///         prop_assert_eq!(xs.rev().rev(), xs);
+    ///     }
+    /// }
+    /// # rev_twice();
+    /// ```
+    fn arbitrary_with((size, args): Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+
+        proptest::collection::vec(proptest::arbitrary::any_with::<T>(args), size)
+            .prop_map(Self::from_vec as fn(Vec<T>) -> Self)
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl<T: quickcheck::Arbitrary, P: PointerKind> quickcheck::Arbitrary for GenericList<T, P> {
+    /// Generates a list with up to [`Gen::size`](quickcheck::Gen::size)
+    /// elements.
+    ///
+    /// ```rust
+    /// use cons::List;
+    /// use quickcheck::QuickCheck;
+    ///
+    /// fn rev_twice(xs: List<u8>) -> bool {
+    ///     xs.rev().rev() == xs
+    /// }
+    ///
+    /// QuickCheck::new().quickcheck(rev_twice as fn(List<u8>) -> bool);
+    /// ```
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::from_vec(quickcheck::Arbitrary::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.clone().into_vec().shrink().map(Self::from_vec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{list, List};
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn test_proptest_shrinks() {
+        use proptest::prelude::*;
+        use proptest::test_runner::{Config, TestError, TestRunner};
+
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let result = runner.run(&any::<List<u8>>(), |xs| {
+            prop_assert!(xs.iter().all(|&x| x < 100));
+            Ok(())
+        });
+        match result {
+            Err(TestError::Fail(_, xs)) => assert_eq!(xs, list![100]),
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn test_quickcheck_shrinks() {
+        use quickcheck::{Arbitrary, Gen};
+
+        let xs: List<u8> = list![7, 0, 3];
+        let shrunk = xs.shrink().collect::<Vec<_>>();
+        assert!(shrunk.contains(&List::new()));
+        assert!(shrunk.iter().all(|ys| ys.len() < 3 || ys.iter().lt(xs.iter())));
+
+        let mut g = Gen::new(10);
+        assert!((0..100).all(|_| List::<u8>::arbitrary(&mut g).len() <= 10));
+    }
+}
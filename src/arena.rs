@@ -0,0 +1,312 @@
+//! Cons cells that are allocated together and freed all at once.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// Storage for the cons cells of many lists, for workloads such as
+/// interpreters that create and throw away lots of small lists.
+///
+/// Each [`List`](crate::List) node is its own reference-counted heap
+/// allocation. A `ConsArena` instead keeps all of its cells in one
+/// growing buffer, and a list is just a [`ArenaList`] handle to a cell
+/// in it, which is `Copy` and doesn't touch a reference count. Cells
+/// are never freed one by one; they all go away together when the
+/// arena is dropped or [`clear`](ConsArena::clear)ed:
+///
+/// ```rust
+/// # use cons::{cons, ConsArena};
+/// let mut arena = ConsArena::new();
+/// let xs = arena.list([2, 3]);
+/// let ys = arena.cons(1, xs);
+/// let zs = arena.cons(10, xs); // shares `xs` with `ys`
+///
+/// assert_eq!(arena.head(ys), Some(&1));
+/// assert_eq!(arena.tail(zs), Some(xs));
+/// assert_eq!(arena.len(ys), 3);
+///
+/// cons!((arena.iter(ys)) as a::b::rest);
+/// assert_eq!((*a, *b, rest), (1, 2, vec![&3]));
+/// ```
+///
+/// Handles are only meaningful for the arena that made them. Using one
+/// with another arena, or after `clear`, gives unrelated elements or
+/// panics.
+pub struct ConsArena<T> {
+    cells: Vec<Cell<T>>,
+}
+
+struct Cell<T> {
+    elem: T,
+    next: Option<usize>,
+    len: usize,
+}
+
+/// A list whose cells live in a [`ConsArena`].
+///
+/// This is a handle rather than the list itself, so the arena has to be
+/// passed to anything that looks at the elements.
+pub struct ArenaList<T> {
+    head: Option<usize>,
+    elem: PhantomData<fn() -> T>,
+}
+
+impl<T> ArenaList<T> {
+    /// The empty list.
+    pub const NIL: Self = Self {
+        head: None,
+        elem: PhantomData,
+    };
+
+    /// Returns `true` if the list has no elements. Unlike the rest of
+    /// the list operations, this doesn't need the arena.
+    pub fn is_empty(self) -> bool {
+        self.head.is_none()
+    }
+}
+
+impl<T> ConsArena<T> {
+    /// Creates a new, empty arena.
+    pub const fn new() -> Self {
+        Self { cells: Vec::new() }
+    }
+
+    /// Creates an arena with room for `capacity` cells before it has to
+    /// grow.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            cells: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of cells in the arena, across all lists.
+    pub fn cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Frees every cell at once. Every list from this arena becomes
+    /// invalid.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Returns a new list with `elem` at the front and `tail` as the
+    /// rest. This is amortized O(1).
+    pub fn cons(&mut self, elem: T, tail: ArenaList<T>) -> ArenaList<T> {
+        let len = self.len(tail) + 1;
+        self.cells.push(Cell {
+            elem,
+            next: tail.head,
+            len,
+        });
+        ArenaList {
+            head: Some(self.cells.len() - 1),
+            elem: PhantomData,
+        }
+    }
+
+    /// Allocates a list with the elements of `iter`, in order.
+    pub fn list<I>(&mut self, iter: I) -> ArenaList<T>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: DoubleEndedIterator,
+    {
+        iter.into_iter()
+            .rev()
+            .fold(ArenaList::NIL, |list, x| self.cons(x, list))
+    }
+
+    /// Returns a reference to the first element of the list, or `None`
+    /// if it is empty.
+    pub fn head(&self, list: ArenaList<T>) -> Option<&T> {
+        list.head.map(|i| &self.cells[i].elem)
+    }
+
+    /// Returns a mutable reference to the first element of the list.
+    /// The cell is shared by every list that has it as a tail.
+    pub fn head_mut(&mut self, list: ArenaList<T>) -> Option<&mut T> {
+        list.head.map(|i| &mut self.cells[i].elem)
+    }
+
+    /// Returns everything but the first element of the list, or `None`
+    /// if it is empty.
+    pub fn tail(&self, list: ArenaList<T>) -> Option<ArenaList<T>> {
+        list.head.map(|i| ArenaList {
+            head: self.cells[i].next,
+            elem: PhantomData,
+        })
+    }
+
+    /// Splits the list into its head and tail, or returns `None` if it
+    /// is empty.
+    pub fn uncons(&self, list: ArenaList<T>) -> Option<(&T, ArenaList<T>)> {
+        list.head.map(|i| {
+            let cell = &self.cells[i];
+            (
+                &cell.elem,
+                ArenaList {
+                    head: cell.next,
+                    elem: PhantomData,
+                },
+            )
+        })
+    }
+
+    /// Returns the number of elements in the list. This is O(1).
+    pub fn len(&self, list: ArenaList<T>) -> usize {
+        list.head.map_or(0, |i| self.cells[i].len)
+    }
+
+    /// Returns an iterator over references to the elements of the list.
+    pub fn iter(&self, list: ArenaList<T>) -> Iter<'_, T> {
+        Iter {
+            cells: &self.cells,
+            next: list.head,
+        }
+    }
+}
+
+impl<T> Default for ConsArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ConsArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsArena")
+            .field("cells", &self.cells.len())
+            .finish()
+    }
+}
+
+impl<T> Clone for ArenaList<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaList<T> {}
+
+impl<T> Default for ArenaList<T> {
+    fn default() -> Self {
+        Self::NIL
+    }
+}
+
+/// Compares the handles, so two lists are equal if they are the same
+/// cells, not just the same elements.
+impl<T> PartialEq for ArenaList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.head == other.head
+    }
+}
+
+impl<T> Eq for ArenaList<T> {}
+
+impl<T> Hash for ArenaList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.head.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for ArenaList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.head {
+            Some(i) => write!(f, "ArenaList({i})"),
+            None => f.write_str("ArenaList::NIL"),
+        }
+    }
+}
+
+/// An iterator over references to the elements of an [`ArenaList`].
+///
+/// Created by [`ConsArena::iter`].
+pub struct Iter<'a, T> {
+    cells: &'a [Cell<T>],
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|i| {
+            let cell = &self.cells[i];
+            self.next = cell.next;
+            &cell.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.next.map_or(0, |i| self.cells[i].len);
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            cells: self.cells,
+            next: self.next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tiny Lisp-style value whose lists live in an arena.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Value {
+        Int(i64),
+        List(ArenaList<Value>),
+    }
+
+    fn sum(arena: &ConsArena<Value>, list: ArenaList<Value>) -> i64 {
+        arena
+            .iter(list)
+            .map(|v| match *v {
+                Value::Int(n) => n,
+                Value::List(xs) => sum(arena, xs),
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut arena = ConsArena::new();
+        let inner = arena.list([Value::Int(2), Value::Int(3)]);
+        let outer = arena.list([Value::Int(1), Value::List(inner), Value::Int(4)]);
+        assert_eq!(sum(&arena, outer), 10);
+        assert_eq!(arena.cells(), 5);
+
+        *arena.head_mut(inner).unwrap() = Value::Int(20);
+        assert_eq!(sum(&arena, outer), 28);
+
+        arena.clear();
+        assert_eq!(arena.cells(), 0);
+    }
+
+    #[test]
+    fn test_many_lists() {
+        let mut arena = ConsArena::with_capacity(1_000_000);
+        let mut xs = ArenaList::NIL;
+        for i in 0..1_000_000u32 {
+            xs = arena.cons(i, xs);
+        }
+        assert_eq!(arena.len(xs), 1_000_000);
+        assert_eq!(arena.iter(xs).len(), 1_000_000);
+        let (x, rest) = arena.uncons(xs).unwrap();
+        assert_eq!((*x, arena.head(rest)), (999_999, Some(&999_998)));
+        assert!(ArenaList::<u32>::NIL.is_empty());
+        assert_eq!(arena.tail(ArenaList::NIL), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Consing onto and splitting arrays in `const` contexts.
+//!
+//! [`cons_slice!`](crate::cons_slice) can already take arrays apart in
+//! a `const fn`. These functions do the same for whole arrays, so that
+//! tables built at compile time can be put together and taken apart one
+//! element at a time. The length of the result is given by its type,
+//! and is checked at compile time:
+//!
+//! ```rust
+//! use cons::array;
+//!
+//! const DIGITS: [u8; 4] = array::cons(b'0', *b"123");
+//! const SPLIT: (u8, [u8; 3]) = array::uncons(DIGITS);
+//! assert_eq!(&DIGITS, b"0123");
+//! assert_eq!(SPLIT, (b'0', *b"123"));
+//! ```
+//!
+//! ```rust,compile_fail
+//! # use cons::array;
+//! const TOO_LONG: [u8; 5] = array::cons(b'0', *b"123");
+//! ```
+//!
+//! The elements have to be [`Copy`], since a `const fn` can't move them
+//! out of an array one at a time.
+
+/// Returns an array with `head` first, followed by the elements of
+/// `tail`. `M` must be `N + 1`.
+pub const fn cons<T: Copy, const N: usize, const M: usize>(head: T, tail: [T; N]) -> [T; M] {
+    const {
+        assert!(M == N + 1, "the result of `cons` must be one element longer than the tail");
+    }
+    let mut array = [head; M];
+    let mut i = 0;
+    while i < N {
+        array[i + 1] = tail[i];
+        i += 1;
+    }
+    array
+}
+
+/// Splits an array into its first element and the rest. `M` must be
+/// `N - 1`, so an empty array can't be split.
+pub const fn uncons<T: Copy, const N: usize, const M: usize>(array: [T; N]) -> (T, [T; M]) {
+    const {
+        assert!(N == M + 1, "the tail from `uncons` must be one element shorter than the array");
+    }
+    let mut tail = [array[0]; M];
+    let mut i = 0;
+    while i < M {
+        tail[i] = array[i + 1];
+        i += 1;
+    }
+    (array[0], tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARES: [u32; 3] = {
+        let (_, rest) = uncons::<u32, 4, 3>([0, 1, 4, 9]);
+        rest
+    };
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(SQUARES, [1, 4, 9]);
+        let one: [u32; 1] = cons(7, []);
+        assert_eq!(one, [7]);
+        assert_eq!(uncons::<_, 1, 0>(one), (7, []));
+        assert_eq!(cons::<_, 3, 4>(0, SQUARES), [0, 1, 4, 9]);
+    }
+}
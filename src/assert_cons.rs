@@ -0,0 +1,203 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+
+/// Asserts that an iterator matches a cons pattern, and binds the
+/// pattern's variables like [`cons!`](crate::cons) does.
+///
+/// This is meant for tests, where the usual `cons!` followed by
+/// `assert_eq!` can be written as one step:
+///
+/// ```rust
+/// # use cons::assert_cons;
+/// let result = vec![1, 2, 3];
+/// assert_cons!(result as 1::2::rest);
+/// assert_eq!(rest, vec![3]);
+/// ```
+///
+/// When the iterator doesn't match, the panic message shows the
+/// pattern and every element that was taken from the iterator before
+/// the match failed, so the elements have to implement [`Debug`]:
+///
+/// ```rust,should_panic
+/// # use cons::assert_cons;
+/// let result = vec![1, 3, 5];
+/// // assertion `result as 1::2::rest` failed
+/// //  consumed: [1, 3]
+/// assert_cons!(result as 1::2::rest);
+/// ```
+///
+/// Like [`assert!`], a custom message can be given after the pattern:
+///
+/// ```rust,should_panic
+/// # use cons::assert_cons;
+/// let tokens = ["let", "x"];
+/// assert_cons!(tokens as "let"::_::"="::_, "bad statement: {:?}", tokens);
+/// ```
+///
+/// A tail bound with `@ iter` is the recording iterator rather than the
+/// original one, since it borrows the list of consumed elements.
+#[macro_export]
+macro_rules! assert_cons {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::assert_cons!(@__split [$iter] [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::assert_cons!(@__split [$iter] [] $($rest)+);
+    };
+    (@__split [$iter:expr] [$($pat:tt)+] , $($arg:tt)+) => {
+        $crate::assert_cons!(@__ $iter => [$($pat)+] (
+            ::core::format_args!($($arg)+)
+        ));
+    };
+    (@__split [$iter:expr] [$($pat:tt)+]) => {
+        $crate::assert_cons!(@__ $iter => [$($pat)+] (
+            ::core::format_args!(
+                "assertion `{} as {}` failed",
+                ::core::stringify!($iter),
+                ::core::stringify!($($pat)+),
+            )
+        ));
+    };
+    (@__split [$iter:expr] [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::assert_cons!(@__split [$iter] [$($pat)* $next] $($rest)*);
+    };
+    (@__ $iter:expr => [$($pat:tt)+] ($msg:expr)) => {
+        let consumed = $crate::__private::Consumed::new();
+        let recorded = consumed.record($crate::cons!(@__dispatch __cons_iter $iter));
+        $crate::cons!(@__start [else {
+            ::core::panic!("{}\n consumed: {}", $msg, consumed)
+        }] recorded => $($pat)+);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front assert_cons [] $($invocation)*);
+    };
+}
+
+/// The elements that [`assert_cons!`] has taken from the iterator so
+/// far, formatted with [`Debug`].
+#[doc(hidden)]
+pub struct Consumed(RefCell<Vec<String>>);
+
+impl Consumed {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(RefCell::new(Vec::new()))
+    }
+
+    pub fn record<I>(&self, iter: I) -> Recorded<'_, I>
+    where
+        I: Iterator,
+        I::Item: Debug,
+    {
+        Recorded {
+            iter,
+            consumed: self,
+        }
+    }
+}
+
+impl core::fmt::Display for Consumed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[{}]", self.0.borrow().join(", "))
+    }
+}
+
+/// An iterator that writes down each element it yields.
+#[doc(hidden)]
+pub struct Recorded<'a, I> {
+    iter: I,
+    consumed: &'a Consumed,
+}
+
+impl<I> Iterator for Recorded<'_, I>
+where
+    I: Iterator,
+    I::Item: Debug,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let x = self.iter.next()?;
+        self.consumed.0.borrow_mut().push(format!("{x:?}"));
+        Some(x)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // hide the exact length from `cons!`, so that a failed match
+        // shows the elements that were actually looked at instead of
+        // being rejected up front
+        (0, self.iter.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use crate::cons_pattern;
+
+    fn panic_message(f: impl FnOnce() + panic::UnwindSafe) -> String {
+        let payload = panic::catch_unwind(f).unwrap_err();
+        match payload.downcast::<String>() {
+            Ok(s) => *s,
+            Err(payload) => String::from(*payload.downcast::<&str>().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_binds() {
+        let v = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        assert_cons!(v as (1, a)::((n, b) if b > a)::rest);
+        assert_eq!((a, n, b), ('a', 2, 'b'));
+        assert_eq!(rest, vec![(3, 'c')]);
+
+        assert_cons!((1..=3) as x::rest @ iter);
+        assert_eq!(x, 1);
+        assert_eq!(rest.collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_message() {
+        let message = panic_message(|| {
+            let result = vec![1, 3, 5];
+            assert_cons!(result as 1::2::rest);
+            let _ = rest;
+        });
+        assert_eq!(
+            message,
+            "assertion `result as 1::2::rest` failed\n consumed: [1, 3]"
+        );
+
+        let message = panic_message(|| {
+            assert_cons!((vec!["a"]) as x::y::_);
+            let _ = (x, y);
+        });
+        assert!(message.ends_with(" consumed: [\"a\"]"), "{message}");
+
+        let message = panic_message(|| {
+            assert_cons!((0..4) as 0::1::nil);
+        });
+        assert!(message.ends_with(" consumed: [0, 1, 2, 3]"), "{message}");
+    }
+
+    #[test]
+    fn test_custom_message() {
+        let message = panic_message(|| {
+            let n = 7;
+            assert_cons!((vec![n]) as x::y::nil, "expected two elements, got {}", n + 1);
+            let _ = (x, y);
+        });
+        assert_eq!(message, "expected two elements, got 8\n consumed: [7]");
+    }
+
+    cons_pattern!(pair = a::b);
+
+    #[test]
+    fn test_alias() {
+        assert_cons!((vec![4, 5]) as pair!()::nil);
+        assert_eq!((a, b), (4, 5));
+    }
+}
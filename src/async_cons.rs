@@ -0,0 +1,252 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// The head/tail split for a [`Stream`], behind the `futures` feature.
+///
+/// This is the async counterpart of
+/// [`IterConsExt::uncons`](crate::IterConsExt::uncons): it waits for the
+/// first item and hands it back together with the rest of the stream,
+/// or `None` if the stream ended:
+///
+/// ```rust
+/// use cons::StreamConsExt;
+/// use futures::stream;
+///
+/// # futures::executor::block_on(async {
+/// let (x, rest) = stream::iter([1, 2, 3]).uncons().await.unwrap();
+/// assert_eq!(x, 1);
+/// let (y, _) = rest.uncons().await.unwrap();
+/// assert_eq!(y, 2);
+/// # });
+/// ```
+///
+/// The stream has to be [`Unpin`] so it can be moved into the result.
+/// Others can be pinned first with `Box::pin` or
+/// [`core::pin::pin!`], or passed as `&mut stream`.
+///
+/// See [`async_cons!`](crate::async_cons) for whole patterns.
+pub trait StreamConsExt: Stream + Sized {
+    /// Splits off the first item. This is `x::xs`.
+    fn uncons(self) -> UnconsFuture<Self>
+    where
+        Self: Unpin,
+    {
+        UnconsFuture { stream: Some(self) }
+    }
+}
+
+impl<S: Stream> StreamConsExt for S {}
+
+/// The future returned by [`StreamConsExt::uncons`].
+#[must_use = "futures do nothing unless polled"]
+pub struct UnconsFuture<S> {
+    stream: Option<S>,
+}
+
+impl<S: Stream + Unpin> Future for UnconsFuture<S> {
+    type Output = Option<(S::Item, S)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let stream = self
+            .stream
+            .as_mut()
+            .expect("`UnconsFuture` polled after completion");
+        let Poll::Ready(item) = Pin::new(stream).poll_next(cx) else {
+            return Poll::Pending;
+        };
+        let stream = self.stream.take().unwrap();
+        Poll::Ready(item.map(|x| (x, stream)))
+    }
+}
+
+/// [`cons!`](crate::cons) for a [`Stream`], behind the `futures`
+/// feature. It can only be used in an `async` block or function, since
+/// each head is awaited in turn.
+///
+/// Heads can be names, `_`, literals, or patterns in parentheses with
+/// an optional guard, as with `cons!`. The tail is the rest of the
+/// stream, so nothing after the heads is waited for; `_` drops it and
+/// `nil` checks that the stream ends:
+///
+/// ```rust
+/// use cons::async_cons;
+/// use futures::stream::{self, StreamExt};
+///
+/// # futures::executor::block_on(async {
+/// let lines = stream::iter(["HELLO v2", "a", "b"]);
+/// async_cons!(lines as (greeting if greeting.starts_with("HELLO"))::rest);
+/// assert_eq!(greeting, "HELLO v2");
+/// assert_eq!(rest.collect::<Vec<_>>().await, ["a", "b"]);
+///
+/// async_cons!((stream::iter(1..=2)) as 1::two::nil);
+/// assert_eq!(two, 2);
+/// # });
+/// ```
+///
+/// The stream has to be [`Unpin`], as for [`StreamConsExt::uncons`].
+///
+/// # Panics
+///
+/// Like `cons!`, the macro panics if the stream ends too early, if a
+/// head doesn't match, or if the pattern ends in `nil` and there are
+/// items left. An `else` block, which has to diverge, runs instead:
+///
+/// ```rust
+/// # use cons::async_cons;
+/// # use futures::stream;
+/// async fn second(s: impl futures::Stream<Item = u8> + Unpin) -> Option<u8> {
+///     async_cons!(s as _::x::_ else { return None });
+///     Some(x)
+/// }
+///
+/// # futures::executor::block_on(async {
+/// assert_eq!(second(stream::iter([1, 2, 3])).await, Some(2));
+/// assert_eq!(second(stream::iter([1])).await, None);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! async_cons {
+    ($stream:ident as $($rest:tt)+) => {
+        $crate::async_cons!(@__else $stream => [] $($rest)+);
+    };
+    (($stream:expr) as $($rest:tt)+) => {
+        $crate::async_cons!(@__else $stream => [] $($rest)+);
+    };
+    (@__else $stream:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::async_cons!(@__start [else $else] $stream => $($pat)+);
+    };
+    (@__else $stream:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::async_cons!(@__else $stream => [$($pat)* $next] $($rest)*);
+    };
+    (@__else $stream:expr => [$($pat:tt)+]) => {
+        $crate::async_cons!(@__start [panic] $stream => $($pat)+);
+    };
+    (@__start $mode:tt $stream:expr => $($pat:tt)+) => {
+        $crate::__private::check_bindings!($($pat)+);
+        let stream = $stream;
+        $crate::async_cons!(@__ $mode stream => $($pat)+);
+    };
+    (@__ $mode:tt $stream:ident => nil) => {
+        let mut rest = 0usize;
+        let mut stream = $stream;
+        while let ::core::option::Option::Some((_, tl)) = $crate::StreamConsExt::uncons(stream).await {
+            rest += 1;
+            stream = tl;
+        }
+        let 0 = rest else {
+            $crate::cons!(@__fail $mode not_empty rest)
+        };
+    };
+    (@__ $mode:tt $stream:ident => _) => {
+        let _ = $stream;
+    };
+    (@__ $mode:tt $stream:ident => $tl:ident) => {
+        #[allow(unused_mut)]
+        let mut $tl = $stream;
+    };
+    (@__ $mode:tt $stream:ident => _ :: $($rest:tt)+) => {
+        let ::core::option::Option::Some((_, stream)) = $crate::StreamConsExt::uncons($stream).await else {
+            $crate::cons!(@__fail $mode exhausted _)
+        };
+        $crate::async_cons!(@__ $mode stream => $($rest)+);
+    };
+    (@__ $mode:tt $stream:ident => $hd:ident :: $($rest:tt)+) => {
+        let ::core::option::Option::Some(($hd, stream)) = $crate::StreamConsExt::uncons($stream).await else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        $crate::async_cons!(@__ $mode stream => $($rest)+);
+    };
+    (@__ $mode:tt $stream:ident => $hd:literal :: $($rest:tt)+) => {
+        $crate::async_cons!(@__ $mode $stream => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $stream:ident => ($hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        let ::core::option::Option::Some((hd, stream)) = $crate::StreamConsExt::uncons($stream).await else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens)]
+        let ($hd) = hd else {
+            $crate::cons!(@__fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [$hd if $guard])
+        };)?
+        $crate::async_cons!(@__ $mode stream => $($rest)+);
+    };
+    (@__ $mode:tt $stream:ident => $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front async_cons [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    use super::StreamConsExt;
+
+    #[test]
+    fn test_uncons() {
+        block_on(async {
+            let (x, rest) = stream::iter(["a", "b"]).uncons().await.unwrap();
+            assert_eq!(x, "a");
+            let (y, rest) = rest.uncons().await.unwrap();
+            assert_eq!(y, "b");
+            assert!(rest.uncons().await.is_none());
+
+            let mut s = Box::pin(stream::once(async { 7 }));
+            assert_eq!((&mut s).uncons().await.map(|(x, _)| x), Some(7));
+        });
+    }
+
+    #[test]
+    fn test_tail_is_lazy() {
+        let pulled = Cell::new(0);
+        block_on(async {
+            let s = stream::iter(1..).inspect(|_| pulled.set(pulled.get() + 1));
+            async_cons!(s as a::(b if b > a)::3::rest);
+            assert_eq!((a, b, pulled.get()), (1, 2, 3));
+            assert_eq!(rest.take(2).collect::<Vec<_>>().await, [4, 5]);
+        });
+    }
+
+    #[test]
+    fn test_else() {
+        async fn pair(v: Vec<i32>) -> Result<(i32, i32), usize> {
+            let len = v.len();
+            async_cons!((stream::iter(v)) as (x @ 0..=9)::y::nil else { return Err(len) });
+            Ok((x, y))
+        }
+        block_on(async {
+            assert_eq!(pair(vec![1, 2]).await, Ok((1, 2)));
+            assert_eq!(pair(vec![1]).await, Err(1));
+            assert_eq!(pair(vec![1, 2, 3]).await, Err(3));
+            assert_eq!(pair(vec![10, 2]).await, Err(2));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (2 elements left)")]
+    fn test_nil_panics() {
+        block_on(async {
+            async_cons!((stream::iter(0..3)) as _::nil);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable y")]
+    fn test_exhausted_panics() {
+        block_on(async {
+            async_cons!((stream::iter(0..1)) as _x::y::_);
+            let _ = y;
+        });
+    }
+}
@@ -0,0 +1,595 @@
+//! A persistent list that stores its elements in chunks.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::array;
+use core::cell::OnceCell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+
+/// How many elements each node holds.
+const CHUNK: usize = 32;
+
+/// A persistent list like [`List`](crate::List), but with up to 32
+/// elements in each node instead of one.
+///
+/// With one heap allocation per element, walking a `List` jumps around
+/// memory. A `ChunkedList` keeps neighbouring elements next to each
+/// other, so iterating over it is much friendlier to the cache, and
+/// consing only allocates once every 32 elements:
+///
+/// ```rust
+/// # use cons::ChunkedList;
+/// let xs = (1..=100).collect::<ChunkedList<u32>>();
+/// assert_eq!(xs.len(), 100);
+/// assert_eq!(xs.iter().sum::<u32>(), 5050);
+///
+/// let ys = xs.cons(0);
+/// assert_eq!(ys.head(), Some(&0));
+/// assert_eq!(ys.tail().unwrap(), xs);
+/// ```
+///
+/// It is still persistent: consing and taking the tail are O(1) and
+/// share the existing nodes. Consing onto a list fills the next free
+/// slot of its first node, and only allocates a new node when the
+/// node is full or the slot was already taken by consing something
+/// else onto the same list:
+///
+/// ```rust
+/// # use cons::ChunkedList;
+/// let xs = ChunkedList::new().cons(2);
+/// let a = xs.cons(1); // shares a node with `xs`
+/// let b = xs.cons(10); // the slot is taken, so this allocates
+/// assert_eq!(a.iter().copied().collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(b.iter().copied().collect::<Vec<_>>(), [10, 2]);
+/// ```
+///
+/// An element in a slot lives as long as its node does, even if the
+/// list that put it there is dropped.
+pub struct ChunkedList<T> {
+    head: Option<Cursor<T>>,
+}
+
+/// A node, and how many of its slots belong to the list.
+struct Cursor<T> {
+    node: Rc<Node<T>>,
+    used: usize,
+}
+
+struct Node<T> {
+    // filled from the back of the list to the front, so the first
+    // element is `slots[used - 1]`
+    slots: [OnceCell<T>; CHUNK],
+    next: Option<Cursor<T>>,
+    // the length of `next`
+    base: usize,
+}
+
+impl<T> ChunkedList<T> {
+    /// Creates a new, empty list.
+    pub const fn new() -> Self {
+        Self { head: None }
+    }
+
+    /// Returns a new list with `elem` at the front and `self` as the
+    /// tail. This is O(1); `self` is shared, not copied.
+    #[must_use]
+    pub fn cons(&self, elem: T) -> Self {
+        let mut elem = elem;
+        if let Some(Cursor { node, used }) = &self.head {
+            if *used < CHUNK {
+                match node.slots[*used].set(elem) {
+                    Ok(()) => {
+                        return Self {
+                            head: Some(Cursor {
+                                node: node.clone(),
+                                used: used + 1,
+                            }),
+                        };
+                    }
+                    Err(e) => elem = e,
+                }
+            }
+        }
+        let node = Node {
+            slots: array::from_fn(|_| OnceCell::new()),
+            next: self.head.clone(),
+            base: self.len(),
+        };
+        let _ = node.slots[0].set(elem);
+        Self {
+            head: Some(Cursor {
+                node: Rc::new(node),
+                used: 1,
+            }),
+        }
+    }
+
+    /// Moves the elements of a vector into a list, in order.
+    ///
+    /// The nodes are filled 32 elements at a time, from the back, so
+    /// this allocates once per node rather than going through
+    /// [`cons`](ChunkedList::cons) for every element. Only the first
+    /// node can be partly full, which leaves room to cons onto it.
+    ///
+    /// ```rust
+    /// # use cons::ChunkedList;
+    /// let xs = ChunkedList::from_vec((0..100).collect());
+    /// assert_eq!(xs.len(), 100);
+    /// assert!(xs.iter().copied().eq(0..100));
+    /// ```
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        let mut head = None;
+        let mut base = 0;
+        let mut elems = vec.into_iter().rev();
+        while elems.len() > 0 {
+            let used = elems.len().min(CHUNK);
+            let node = Node {
+                slots: array::from_fn(|_| OnceCell::new()),
+                next: head.take(),
+                base,
+            };
+            for (slot, elem) in node.slots.iter().zip(elems.by_ref().take(used)) {
+                let _ = slot.set(elem);
+            }
+            base += used;
+            head = Some(Cursor {
+                node: Rc::new(node),
+                used,
+            });
+        }
+        Self { head }
+    }
+
+    /// Returns a reference to the first element of the list, or `None`
+    /// if it is empty.
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(Cursor::elem)
+    }
+
+    /// Returns everything but the first element of the list, or `None`
+    /// if it is empty. This is O(1).
+    pub fn tail(&self) -> Option<Self> {
+        self.head.as_ref().map(|cursor| Self {
+            head: cursor.next(),
+        })
+    }
+
+    /// Splits the list into its head and tail, or returns `None` if it
+    /// is empty.
+    pub fn uncons(&self) -> Option<(&T, Self)> {
+        self.head.as_ref().map(|cursor| {
+            (
+                cursor.elem(),
+                Self {
+                    head: cursor.next(),
+                },
+            )
+        })
+    }
+
+    /// Returns `true` if the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns the number of elements in the list. This is O(1).
+    pub fn len(&self) -> usize {
+        self.head
+            .as_ref()
+            .map_or(0, |cursor| cursor.node.base + cursor.used)
+    }
+
+    /// Returns an iterator over references to the elements.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_ref().map(|cursor| (&*cursor.node, cursor.used)),
+            len: self.len(),
+        }
+    }
+
+    /// Combines the elements from first to last, starting with `init`,
+    /// like [`List::fold_left`](crate::List::fold_left).
+    pub fn fold_left<B>(&self, init: B, f: impl FnMut(B, &T) -> B) -> B {
+        self.iter().fold(init, f)
+    }
+
+    /// Combines the elements from last to first, starting with `init`,
+    /// like [`List::fold_right`](crate::List::fold_right).
+    ///
+    /// ```rust
+    /// # use cons::ChunkedList;
+    /// let xs = (1..=3).collect::<ChunkedList<u32>>();
+    /// assert_eq!(xs.fold_right(String::new(), |x, s| format!("{s}{x}")), "321");
+    /// ```
+    pub fn fold_right<B>(&self, init: B, mut f: impl FnMut(&T, B) -> B) -> B {
+        let elems = self.iter().collect::<Vec<_>>();
+        elems.into_iter().rev().fold(init, |acc, x| f(x, acc))
+    }
+
+    /// Returns a new list with `f` applied to each element, in order.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> ChunkedList<U> {
+        self.iter().map(f).collect()
+    }
+}
+
+impl<T: Clone> ChunkedList<T> {
+    /// Returns a new list with the elements of `self` followed by the
+    /// elements of `other`.
+    ///
+    /// As with [`List::append`](crate::List::append), the elements of
+    /// `self` are cloned and `other` is shared. They are consed onto
+    /// `other` from the back, so they fill its first node before
+    /// allocating new ones.
+    ///
+    /// ```rust
+    /// # use cons::ChunkedList;
+    /// let xs = (1..=2).collect::<ChunkedList<u32>>();
+    /// let ys = (3..=4).collect::<ChunkedList<u32>>();
+    /// assert!(xs.append(&ys).iter().copied().eq(1..=4));
+    /// ```
+    #[must_use]
+    pub fn append(&self, other: &Self) -> Self {
+        if other.is_empty() {
+            return self.clone();
+        }
+        let elems = self.iter().collect::<Vec<_>>();
+        elems
+            .into_iter()
+            .rev()
+            .fold(other.clone(), |list, x| list.cons(x.clone()))
+    }
+
+    /// Returns a new list with the elements in reverse order.
+    #[must_use]
+    pub fn rev(&self) -> Self {
+        self.fold_left(Self::new(), |list, x| list.cons(x.clone()))
+    }
+}
+
+impl<T> Cursor<T> {
+    fn elem(&self) -> &T {
+        self.node.slots[self.used - 1].get().unwrap()
+    }
+
+    fn next(&self) -> Option<Self> {
+        if self.used > 1 {
+            Some(Self {
+                node: self.node.clone(),
+                used: self.used - 1,
+            })
+        } else {
+            self.node.next.clone()
+        }
+    }
+}
+
+impl<T> Clone for Cursor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            node: self.node.clone(),
+            used: self.used,
+        }
+    }
+}
+
+impl<T> Clone for ChunkedList<T> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T> Drop for ChunkedList<T> {
+    /// Drops the nodes one at a time rather than recursively, so long
+    /// lists can't overflow the stack. Stops at the first node that is
+    /// still shared with another list.
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(Ok(mut node)) = next.map(|cursor| Rc::try_unwrap(cursor.node)) {
+            next = node.next.take();
+        }
+    }
+}
+
+impl<T> Default for ChunkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChunkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for ChunkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for ChunkedList<T> {}
+
+impl<T: PartialOrd> PartialOrd for ChunkedList<T> {
+    /// Compares the lists lexicographically.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for ChunkedList<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for ChunkedList<T> {
+    /// Hashes the elements and then the length, the same way as a
+    /// [`List`](crate::List) with the same elements.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for x in self {
+            x.hash(state);
+        }
+        state.write_usize(self.len());
+    }
+}
+
+impl<T> FromIterator<T> for ChunkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect())
+    }
+}
+
+impl<T: Clone> Extend<T> for ChunkedList<T> {
+    /// Appends the elements to the end of the list.
+    ///
+    /// The existing elements are cloned, as with
+    /// [`append`](ChunkedList::append), so other lists are unaffected.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        *self = self.append(&Self::from_iter(iter));
+    }
+}
+
+impl<T: Clone> IntoIterator for ChunkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Turns the list into an iterator over its elements.
+    ///
+    /// Elements are moved out of nodes that aren't shared with another
+    /// list, and cloned out of those that are. This also makes an owned
+    /// list work with [`cons!`](crate::cons) and the other macros:
+    ///
+    /// ```rust
+    /// # use cons::{cons, ChunkedList};
+    /// let xs = (1..=40).collect::<ChunkedList<u32>>();
+    /// cons!(xs as first::second::rest @ iter);
+    /// assert_eq!((first, second, rest.len()), (1, 2, 38));
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ChunkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over references to the elements of a [`ChunkedList`].
+///
+/// Created by [`ChunkedList::iter`].
+pub struct Iter<'a, T> {
+    next: Option<(&'a Node<T>, usize)>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, used) = self.next?;
+        self.next = if used > 1 {
+            Some((node, used - 1))
+        } else {
+            node.next.as_ref().map(|cursor| (&*cursor.node, cursor.used))
+        };
+        self.len -= 1;
+        node.slots[used - 1].get()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            len: self.len,
+        }
+    }
+}
+
+/// An owning iterator over the elements of a [`ChunkedList`].
+///
+/// Created by [`ChunkedList::into_iter`](IntoIterator::into_iter).
+pub struct IntoIter<T> {
+    list: ChunkedList<T>,
+}
+
+impl<T: Clone> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Cursor { mut node, used } = self.list.head.take()?;
+        let elem = match Rc::get_mut(&mut node) {
+            // nobody else can see the slot, so the element can be moved
+            Some(node) => node.slots[used - 1].take(),
+            None => node.slots[used - 1].get().cloned(),
+        };
+        self.list.head = if used > 1 {
+            Some(Cursor {
+                node,
+                used: used - 1,
+            })
+        } else {
+            match Rc::try_unwrap(node) {
+                Ok(node) => node.next,
+                Err(node) => node.next.clone(),
+            }
+        };
+        elem
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.list.len();
+        (len, Some(len))
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for IntoIter<T> {}
+
+impl<T: Clone> FusedIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::List;
+
+    #[test]
+    fn test_chunk_boundaries() {
+        for n in [0, 1, 31, 32, 33, 64, 100] {
+            let xs = (0..n).collect::<ChunkedList<usize>>();
+            assert_eq!(xs.len(), n);
+            assert_eq!(xs.iter().len(), n);
+            assert!(xs.iter().copied().eq(0..n));
+            let mut tail = xs.clone();
+            for i in 0..n {
+                let (x, rest) = tail.uncons().unwrap();
+                assert_eq!((*x, rest.len()), (i, n - i - 1));
+                tail = rest;
+            }
+            assert!(tail.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_siblings_are_independent() {
+        let base = (0..40).collect::<ChunkedList<u32>>();
+        let a = base.cons(100);
+        let b = base.cons(200);
+        let c = a.tail().unwrap().cons(300);
+        assert_eq!(a.head(), Some(&100));
+        assert_eq!(b.head(), Some(&200));
+        assert_eq!(c.head(), Some(&300));
+        for xs in [&a, &b, &c] {
+            assert_eq!(xs.tail().unwrap(), base);
+        }
+        // `a` filled the free slot, so it shares the first node
+        assert!(Rc::ptr_eq(&a.head.as_ref().unwrap().node, &base.head.as_ref().unwrap().node));
+        assert!(!Rc::ptr_eq(&b.head.as_ref().unwrap().node, &base.head.as_ref().unwrap().node));
+    }
+
+    #[test]
+    fn test_cons_macro() {
+        let xs = (1..=3).collect::<ChunkedList<i32>>();
+        crate::cons!((&xs) as x::rest);
+        assert_eq!((*x, rest), (1, vec![&2, &3]));
+        assert_eq!(format!("{xs:?}"), "[1, 2, 3]");
+        assert!(xs.iter().eq(&(1..=3).collect::<List<_>>()));
+    }
+
+    #[test]
+    fn test_from_vec_fills_nodes() {
+        let xs = ChunkedList::from_vec((0..70).collect());
+        let mut nodes = Vec::new();
+        let mut cur = xs.head.as_ref();
+        while let Some(cursor) = cur {
+            nodes.push((cursor.used, cursor.node.base));
+            cur = cursor.node.next.as_ref();
+        }
+        assert_eq!(nodes, [(6, 64), (32, 32), (32, 0)]);
+        assert!(xs.iter().copied().eq(0..70));
+        // the first node has room, so consing fills it in place
+        let ys = xs.cons(-1);
+        assert!(Rc::ptr_eq(&ys.head.as_ref().unwrap().node, &xs.head.as_ref().unwrap().node));
+        assert_eq!(ChunkedList::<i32>::from_vec(Vec::new()), ChunkedList::new());
+    }
+
+    #[test]
+    fn test_list_api() {
+        let xs = (0..40).collect::<ChunkedList<u32>>();
+        assert!(xs.map(|x| x * 2).iter().copied().eq((0..40).map(|x| x * 2)));
+        assert_eq!(xs.fold_left(0, |acc, x| acc + x), 780);
+        assert!(xs.rev().iter().copied().eq((0..40).rev()));
+        let ys = (40..50).collect::<ChunkedList<u32>>();
+        let zs = xs.append(&ys);
+        assert!(zs.iter().copied().eq(0..50));
+        // `ys` is shared, and its first node is filled before allocating
+        let mut rest = zs.clone();
+        for _ in 0..40 {
+            rest = rest.tail().unwrap();
+        }
+        assert!(Rc::ptr_eq(&rest.head.as_ref().unwrap().node, &ys.head.as_ref().unwrap().node));
+        let mut ws = xs.clone();
+        ws.extend(40..50);
+        assert_eq!((ws, xs.len()), (zs.clone(), 40));
+
+        assert!(xs < zs && zs < ys.cons(0) && xs.cmp(&xs.clone()) == Ordering::Equal);
+        let hash = |h: &dyn Fn(&mut std::hash::DefaultHasher)| {
+            let mut state = std::hash::DefaultHasher::new();
+            h(&mut state);
+            state.finish()
+        };
+        let list = zs.iter().copied().collect::<List<_>>();
+        assert_eq!(hash(&|s| zs.hash(s)), hash(&|s| list.hash(s)));
+    }
+
+    #[test]
+    fn test_into_iter_moves_unshared() {
+        let s = Rc::new(());
+        let xs = (0..40).map(|_| Rc::clone(&s)).collect::<ChunkedList<_>>();
+        let shared = xs.tail().unwrap().tail().unwrap();
+        let mut iter = xs.into_iter();
+        assert_eq!(iter.len(), 40);
+        // the first node is shared with `shared`, so this clones
+        drop(iter.next());
+        assert_eq!(Rc::strong_count(&s), 41);
+        drop(shared);
+        drop(iter.next());
+        assert_eq!(Rc::strong_count(&s), 40);
+        assert_eq!(iter.by_ref().count(), 38);
+        assert_eq!(Rc::strong_count(&s), 1);
+
+        let xs = (1..=3).collect::<ChunkedList<i32>>();
+        let ys = xs.clone();
+        crate::cons!(ys as 1::rest @ iter);
+        assert_eq!(rest.collect::<Vec<_>>(), [2, 3]);
+        assert!(xs.into_iter().eq(1..=3));
+    }
+
+    #[test]
+    fn test_long_list_drop() {
+        let mut xs = ChunkedList::new();
+        for i in 0..1_000_000 {
+            xs = xs.cons(i);
+        }
+        assert_eq!(xs.len(), 1_000_000);
+        drop(xs);
+    }
+}
@@ -0,0 +1,160 @@
+/// A version of [`cons!`](crate::cons) for fixed-size arrays that
+/// checks the pattern against the array length at compile time.
+///
+/// The pattern syntax is the same as [`cons!`](crate::cons), and the
+/// elements are moved out of the array just like `cons!` would. The
+/// difference is that a pattern which can't fit the array is a
+/// compile error rather than a panic.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons_array;
+/// cons_array!([1, 2, 3] as x::y::z::nil);
+/// assert_eq!((x, y, z), (1, 2, 3));
+///
+/// let words = [String::from("a"), String::from("b"), String::from("c")];
+/// cons_array!(words as first::rest);
+/// assert_eq!(first, "a");
+/// assert_eq!(rest, vec!["b", "c"]);
+/// # }
+/// ```
+///
+/// If the array is too short for the pattern, or the pattern ends in
+/// `nil` and the lengths don't match exactly, compilation fails:
+///
+/// ```rust,compile_fail
+/// # use cons::cons_array;
+/// cons_array!([1, 2] as x::y::z::nil);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::cons_array;
+/// cons_array!([1, 2, 3] as x::nil);
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::cons_array;
+/// cons_array!([1] as x::mid::y!);
+/// ```
+///
+/// Only arrays and [`Vect`](crate::Vect)s are accepted; for anything
+/// else use [`cons!`](crate::cons) or [`cons_slice!`](crate::cons_slice).
+#[macro_export]
+macro_rules! cons_array {
+    ($array:ident as $($pat:tt)+) => {
+        $crate::cons_array!(@__count $array => [$($pat)+] [] $($pat)+);
+    };
+    (($array:expr) as $($pat:tt)+) => {
+        $crate::cons_array!(@__count $array => [$($pat)+] [] $($pat)+);
+    };
+    ([$($elem:expr),* $(,)?] as $($pat:tt)+) => {
+        $crate::cons_array!(([$($elem),*]) as $($pat)+);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] nil) => {
+        let array = $array;
+        $crate::__private::ArrayLen::<{ 0 $(+ $n)* }, true>::check(&array);
+        $crate::cons!(@__start [panic] array => $($pat)+);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] []) => {
+        $crate::cons_array!(@__count $array => [$($pat)+] [$($n)*] nil);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] $tl:tt $(@ iter)? $(@ ..)?) => {
+        let array = $array;
+        $crate::__private::ArrayLen::<{ 0 $(+ $n)* }, false>::check(&array);
+        $crate::cons!(@__start [panic] array => $($pat)+);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] $mid:tt $(@ iter)? $(@ ..)? :: $last:tt !) => {
+        $crate::cons_array!(@__count $array => [$($pat)+] [$($n)* 1] _);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] $hd:tt :: $($rest:tt)+) => {
+        $crate::cons_array!(@__count $array => [$($pat)+] [$($n)* 1] $($rest)+);
+    };
+    (@__count $array:expr => [$($pat:tt)+] [$($n:tt)*] $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_array [] $($invocation)*);
+    };
+}
+
+/// Compile-time length check used by [`cons_array!`]. `HEADS` is the
+/// number of head bindings in the pattern and `EXACT` is whether the
+/// pattern ends in `nil`.
+#[doc(hidden)]
+pub struct ArrayLen<const HEADS: usize, const EXACT: bool>;
+
+impl<const HEADS: usize, const EXACT: bool> ArrayLen<HEADS, EXACT> {
+    pub const fn check<A: FixedLen>(_: &A) {
+        const {
+            assert!(
+                A::LEN >= HEADS,
+                "cons pattern has more elements than the array it is matched against"
+            );
+            assert!(
+                !EXACT || A::LEN == HEADS,
+                "cons pattern ends in `nil` but the array has more elements than the pattern"
+            );
+        }
+    }
+}
+
+/// A collection whose length is known from its type, which
+/// [`cons_array!`] can check a pattern against.
+#[doc(hidden)]
+pub trait FixedLen {
+    const LEN: usize;
+}
+
+impl<T, const N: usize> FixedLen for [T; N] {
+    const LEN: usize = N;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_exact() {
+        let v = [(1, 2), (3, 4)];
+        cons_array!(v as ((a, b))::((c, d))::nil);
+        assert_eq!((a, b, c, d), (1, 2, 3, 4));
+    }
+
+    #[test]
+    fn test_empty_alias_and_literals() {
+        cons_array!([1, 2, 3] as 1::x::3::[]);
+        assert_eq!(x, 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_first_mid_last() {
+        cons_array!([1, 2, 3] as x::mid::y!);
+        assert_eq!((x, y), (1, 3));
+        assert_eq!(mid, vec![2]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_tail() {
+        cons_array!([1, 2, 3, 4] as x::_::rest);
+        assert_eq!(x, 1);
+        assert_eq!(rest, vec![3, 4]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_empty_tail() {
+        cons_array!([1, 2] as x::y::rest);
+        assert_eq!((x, y), (1, 2));
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_expression_input() {
+        let v = [1, 2, 3];
+        cons_array!((v.map(|x| x * 2)) as x::rest @ iter);
+        assert_eq!(x, 2);
+        assert_eq!(rest.next(), Some(4));
+    }
+}
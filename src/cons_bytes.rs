@@ -0,0 +1,197 @@
+/// A version of [`cons_slice!`](crate::cons_slice) for parsing binary
+/// data. Heads are bytes, bound by value, and the tail is the rest of
+/// the input as a `&[u8]` subslice, so nothing is allocated or copied.
+///
+/// The input can be anything that can be indexed with `[..]` to get a
+/// `&[u8]`, such as a `Vec<u8>`, a byte array, or a byte slice.
+///
+/// Besides the usual single-element segments, a segment can be an array
+/// pattern such as `[hi, lo]`, which takes that many bytes at once. It
+/// can be given a name with `name @ [..]` to bind the bytes as an array.
+///
+/// # Examples
+///
+/// ```rust
+/// # use cons::cons_bytes;
+/// let packet = [0xCA, 0xFE, 2, 0x01, 0x00, b'h', b'i'];
+///
+/// cons_bytes!(packet as [0xCA, 0xFE]::version::len @ [_, _]::payload);
+/// assert_eq!(version, 2);
+/// assert_eq!(u16::from_le_bytes(len), 1);
+/// assert_eq!(payload, b"hi");
+/// ```
+///
+/// As with [`cons!`](crate::cons), `nil` checks that there is nothing
+/// left, heads can be literals or guarded patterns, and an `else`
+/// block can be given to run instead of panicking:
+///
+/// ```rust
+/// # use cons::cons_bytes;
+/// fn opcode(input: &[u8]) -> Option<(u8, &[u8])> {
+///     cons_bytes!(input as (op if op < 0x80)::rest else { return None });
+///     Some((op, rest))
+/// }
+///
+/// assert_eq!(opcode(&[0x10, 1, 2]), Some((0x10, &[1, 2][..])));
+/// assert_eq!(opcode(&[0x90]), None);
+/// assert_eq!(opcode(&[]), None);
+/// ```
+///
+/// This makes it easy to write small parsers that take a `&[u8]` and
+/// return what they read along with the rest of the input.
+///
+/// # Panics
+///
+/// The macro panics if the input is too short for the pattern, if the
+/// pattern ends in `nil` and there are bytes left over, or if a head
+/// doesn't match.
+///
+/// ```rust,should_panic
+/// # use cons::cons_bytes;
+/// cons_bytes!(b"ab" as [a, b, c]::_); // panics, needs 3 bytes
+/// ```
+#[macro_export]
+macro_rules! cons_bytes {
+    ($bytes:ident as $($rest:tt)+) => {
+        $crate::cons_bytes!(@__else (&$bytes[..]) => [] $($rest)+);
+    };
+    (($bytes:expr) as $($rest:tt)+) => {
+        $crate::cons_bytes!(@__else (&($bytes)[..]) => [] $($rest)+);
+    };
+    ($bytes:literal as $($rest:tt)+) => {
+        $crate::cons_bytes!(@__else (&$bytes[..]) => [] $($rest)+);
+    };
+    (@__else $bytes:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::cons_bytes!(@__start [else $else] $bytes => $($pat)+);
+    };
+    (@__else $bytes:expr => [$($pat:tt)+]) => {
+        $crate::cons_bytes!(@__start [panic] $bytes => $($pat)+);
+    };
+    (@__else $bytes:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons_bytes!(@__else $bytes => [$($pat)* $next] $($rest)*);
+    };
+    (@__start $mode:tt $bytes:expr => $($pat:tt)+) => {
+        let bytes: &[u8] = $bytes;
+        $crate::cons_bytes!(@__ $mode bytes => $($pat)+);
+    };
+    (@__ $mode:tt $bytes:expr => nil) => {
+        let rest = <[u8]>::len($bytes);
+        let 0 = rest else {
+            $crate::cons!(@__fail $mode not_empty rest)
+        };
+    };
+    (@__ $mode:tt $bytes:expr => []) => {
+        $crate::cons_bytes!(@__ $mode $bytes => nil);
+    };
+    (@__ $mode:tt $bytes:expr => $tl:ident) => {
+        let $tl: &[u8] = $bytes;
+    };
+    (@__ $mode:tt $bytes:expr => _) => {
+        let _ = $bytes;
+    };
+    (@__ $mode:tt $bytes:expr => nil :: $($rest:tt)+) => {
+        $crate::__private::diagnose!(pattern nil :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => [$($hd:pat),+] :: $($rest:tt)+) => {
+        $crate::cons_bytes!(@__chunk $mode $bytes => [] [$($hd),+] :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => $name:ident @ [$($hd:pat),+] :: $($rest:tt)+) => {
+        $crate::cons_bytes!(@__chunk $mode $bytes => [$name] [$($hd),+] :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => $hd:literal :: $($rest:tt)+) => {
+        $crate::cons_bytes!(@__ $mode $bytes => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => $hd:ident :: $($rest:tt)+) => {
+        $crate::cons_bytes!(@__ $mode $bytes => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => _ :: $($rest:tt)+) => {
+        $crate::cons_bytes!(@__ $mode $bytes => (_) :: $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => ($hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        #[allow(clippy::question_mark)]
+        let ::core::option::Option::Some((&hd, bytes)) = <[u8]>::split_first($bytes) else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens)]
+        let ($hd) = hd else {
+            $crate::cons!(@__fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [$hd if $guard])
+        };)?
+        $crate::cons_bytes!(@__ $mode bytes => $($rest)+);
+    };
+    (@__chunk $mode:tt $bytes:expr => [$($name:ident)?] [$($hd:pat),+] :: $($rest:tt)+) => {
+        #[allow(clippy::question_mark)]
+        let ::core::option::Option::Some((&chunk, bytes)) = <[u8]>::split_first_chunk::<
+            { [$(::core::stringify!($hd)),+].len() },
+        >($bytes) else {
+            $crate::cons!(@__fail $mode exhausted [$($hd),+])
+        };
+        #[allow(irrefutable_let_patterns)]
+        let $($name @)? [$($hd),+] = chunk else {
+            $crate::cons!(@__fail $mode mismatch [[$($hd),+]])
+        };
+        $crate::cons_bytes!(@__ $mode bytes => $($rest)+);
+    };
+    (@__ $mode:tt $bytes:expr => $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_bytes [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    fn read_u16(input: &[u8]) -> Option<(u16, &[u8])> {
+        cons_bytes!(input as n @ [_, _]::rest else { return None });
+        Some((u16::from_be_bytes(n), rest))
+    }
+
+    #[test]
+    fn test_tail_is_subslice() {
+        let data = Vec::from([1, 2, 3, 4]);
+        cons_bytes!(data as a::b::rest);
+        assert_eq!((a, b), (1, 2));
+        assert!(std::ptr::eq(rest, &data[2..]));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        let (a, rest) = read_u16(&data).unwrap();
+        let (b, rest) = read_u16(rest).unwrap();
+        assert_eq!((a, b), (0x1234, 0x5678));
+        assert_eq!(read_u16(rest), None);
+
+        cons_bytes!(data as [0x12, x]::[y, 0x78, z]::nil);
+        assert_eq!((x, y, z), (0x34, 0x56, 0x9A));
+    }
+
+    #[test]
+    fn test_literal_input() {
+        cons_bytes!(b"GET /" as b'G'::b'E'::b'T'::path);
+        assert_eq!(path, b" /");
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable [a, b, c]")]
+    fn test_chunk_too_short() {
+        cons_bytes!(b"ab" as _::[a, b, c]::_);
+        let _ = (a, b, c);
+    }
+
+    #[test]
+    #[should_panic(expected = "Element did not match pattern `[1, 2]`")]
+    fn test_chunk_mismatch() {
+        cons_bytes!(b"\x01\x03" as [1, 2]::_);
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (1 elements left)")]
+    fn test_too_long() {
+        cons_bytes!((vec![1, 2]) as _x::nil);
+    }
+}
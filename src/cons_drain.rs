@@ -0,0 +1,267 @@
+use alloc::collections::VecDeque;
+use alloc::vec::{self, Vec};
+
+/// Moves the first few elements out of a `Vec` or `VecDeque` and binds
+/// them, leaving the rest of the elements where they are.
+///
+/// Unlike [`cons!`](crate::cons), which consumes the whole collection
+/// and collects the tail into a new `Vec`, this takes the `Vec` by
+/// mutable reference and only removes the elements that the pattern
+/// binds, so the tail is the original `Vec` and its allocation is kept.
+/// The pattern is just the heads:
+///
+/// ```rust
+/// # use cons::cons_drain;
+/// let mut v = vec![String::from("cp"), String::from("a"), String::from("b")];
+/// let capacity = v.capacity();
+///
+/// cons_drain!(v as cmd);
+/// assert_eq!(cmd, "cp");
+/// assert_eq!(v, ["a", "b"]);
+/// assert_eq!(v.capacity(), capacity);
+///
+/// cons_drain!(v as src::dst);
+/// assert_eq!((src.as_str(), dst.as_str()), ("a", "b"));
+/// assert!(v.is_empty());
+/// ```
+///
+/// Heads can use the same patterns as in `cons!`, including literals,
+/// destructuring, and guards.
+///
+/// On a `VecDeque`, the heads are taken with `pop_front`, so each step
+/// of consing a queue in a loop is O(1) no matter how long the queue
+/// is:
+///
+/// ```rust
+/// # use cons::cons_drain;
+/// # use std::collections::VecDeque;
+/// let mut queue = VecDeque::from([1, 2, 3, 4]);
+/// let mut sum = 0;
+/// while !queue.is_empty() {
+///     cons_drain!(queue as x);
+///     if x % 2 == 0 {
+///         queue.push_back(x / 2);
+///     }
+///     sum += x;
+/// }
+/// assert_eq!(sum, 1 + 2 + 3 + 4 + 1 + 2 + 1);
+/// ```
+///
+/// # Panics
+///
+/// If the `Vec` is shorter than the pattern, the macro panics before
+/// removing anything. If a refutable head pattern or a guard doesn't
+/// match, it panics after the heads have been removed. As with `cons!`,
+/// an `else` block can be given to run instead:
+///
+/// ```rust
+/// # use cons::cons_drain;
+/// let mut queue = vec![1, 2, 3];
+/// let mut pairs = vec![];
+/// loop {
+///     cons_drain!(queue as x::y else { break });
+///     pairs.push((x, y));
+/// }
+/// assert_eq!(pairs, vec![(1, 2)]);
+/// assert_eq!(queue, vec![3]);
+/// ```
+#[macro_export]
+macro_rules! cons_drain {
+    ($v:ident as $($rest:tt)+) => {
+        $crate::cons_drain!(@__else $v => [] $($rest)+);
+    };
+    (($v:expr) as $($rest:tt)+) => {
+        $crate::cons_drain!(@__else $v => [] $($rest)+);
+    };
+    (@__else $v:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::cons_drain!(@__ [else $else] $v => $($pat)+);
+    };
+    (@__else $v:expr => [$($pat:tt)+]) => {
+        $crate::cons_drain!(@__ [panic] $v => $($pat)+);
+    };
+    (@__else $v:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons_drain!(@__else $v => [$($pat)* $next] $($rest)*);
+    };
+    (@__ $mode:tt $v:expr => $($hd:tt)::+) => {
+        let v = &mut $v;
+        $crate::cons_drain!(@__len $mode v [] $($hd)+);
+        let mut drain = $crate::__private::DrainFront::drain_front(
+            v,
+            [$(::core::stringify!($hd)),+].len(),
+        );
+        $($crate::cons_drain!(@__bind $mode drain $hd);)+
+        ::core::mem::drop(drain);
+    };
+    (@__len $mode:tt $v:ident [$($n:tt)*] $hd:tt $($rest:tt)*) => {
+        let true = $crate::__private::DrainFront::len($v) > 0 $(+ $n)* else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        $crate::cons_drain!(@__len $mode $v [$($n)* 1] $($rest)*);
+    };
+    (@__len $mode:tt $v:ident [$($n:tt)*]) => {};
+    (@__bind $mode:tt $drain:ident ($hd:pat $(if $guard:expr)?)) => {
+        let ::core::option::Option::Some(hd) = $drain.next() else {
+            ::core::unreachable!()
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens)]
+        let ($hd) = hd else {
+            $crate::cons!(@__fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [$hd if $guard])
+        };)?
+    };
+    (@__bind $mode:tt $drain:ident $hd:ident) => {
+        $crate::cons_drain!(@__bind $mode $drain ($hd));
+    };
+    (@__bind $mode:tt $drain:ident $hd:literal) => {
+        $crate::cons_drain!(@__bind $mode $drain ($hd));
+    };
+    (@__bind $mode:tt $drain:ident _) => {
+        $crate::cons_drain!(@__bind $mode $drain (_));
+    };
+    (@__bind $mode:tt $drain:ident $($pat:tt)*) => {
+        $crate::__private::diagnose!(pattern $($pat)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_drain [] $($invocation)*);
+    };
+}
+
+/// The collections [`cons_drain!`] works on, and how to take elements
+/// off their fronts.
+#[doc(hidden)]
+#[allow(clippy::len_without_is_empty)]
+pub trait DrainFront {
+    type Item;
+    type Drain<'a>: Iterator<Item = Self::Item>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+
+    /// Removes the first `n` elements, which must exist.
+    fn drain_front(&mut self, n: usize) -> Self::Drain<'_>;
+}
+
+impl<T> DrainFront for Vec<T> {
+    type Item = T;
+    type Drain<'a>
+        = vec::Drain<'a, T>
+    where
+        T: 'a;
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn drain_front(&mut self, n: usize) -> Self::Drain<'_> {
+        self.drain(..n)
+    }
+}
+
+impl<T> DrainFront for VecDeque<T> {
+    type Item = T;
+    type Drain<'a>
+        = PopFront<'a, T>
+    where
+        T: 'a;
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn drain_front(&mut self, n: usize) -> Self::Drain<'_> {
+        PopFront { deque: self, n }
+    }
+}
+
+/// Pops up to `n` elements off the front of a deque, one at a time.
+#[doc(hidden)]
+pub struct PopFront<'a, T> {
+    deque: &'a mut VecDeque<T>,
+    n: usize,
+}
+
+impl<T> Iterator for PopFront<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.n = self.n.checked_sub(1)?;
+        self.deque.pop_front()
+    }
+}
+
+impl<T> Drop for PopFront<'_, T> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_keeps_allocation() {
+        let mut v = Vec::with_capacity(16);
+        v.extend(["a", "b", "c"].map(String::from));
+        let ptr = v.as_ptr();
+        cons_drain!(v as x::_);
+        assert_eq!(x, "a");
+        assert_eq!(v, vec!["c"]);
+        assert_eq!(v.as_ptr(), ptr);
+        assert_eq!(v.capacity(), 16);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut v = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        cons_drain!(v as ((n, c))::((2, d) if d > c));
+        assert_eq!((n, c, d), (1, 'a', 'b'));
+        assert_eq!(v, vec![(3, 'c')]);
+    }
+
+    #[test]
+    fn test_deque_pops_front() {
+        let mut queue = VecDeque::with_capacity(4);
+        queue.extend([1, 2, 3]);
+        queue.push_front(0);
+        cons_drain!(queue as _::x::_);
+        assert_eq!(x, 1);
+        assert_eq!(queue, [3]);
+        assert_eq!(queue.capacity(), 4);
+    }
+
+    #[test]
+    fn test_expression_and_deque() {
+        let mut queues = [VecDeque::from([1, 2, 3])];
+        cons_drain!((queues[0]) as x);
+        assert_eq!(x, 1);
+        assert_eq!(queues[0], [2, 3]);
+    }
+
+    fn third(v: &mut Vec<i32>) -> Option<i32> {
+        cons_drain!((*v) as _::_::z else { return None });
+        Some(z)
+    }
+
+    #[test]
+    fn test_too_short_leaves_vec_alone() {
+        let mut v = vec![1, 2];
+        assert_eq!(third(&mut v), None);
+        assert_eq!(v, vec![1, 2]);
+        v.extend([3, 4]);
+        assert_eq!(third(&mut v), Some(3));
+        assert_eq!(v, vec![4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable y")]
+    fn test_too_short_panics() {
+        let mut v = vec![1];
+        cons_drain!(v as x::y);
+        let _ = (x, y);
+    }
+}
@@ -0,0 +1,194 @@
+/// Splits the lines of a [`BufRead`](https://doc.rust-lang.org/std/io/trait.BufRead.html) into the first
+/// few lines and the rest, for line-oriented formats that start with a
+/// header.
+///
+/// Heads are bound as `String`s, and the tail is the
+/// [`Lines`](https://doc.rust-lang.org/std/io/struct.Lines.html) iterator positioned after them, so the
+/// rest of the input is only read as it is used. Like
+/// [`try_cons!`](crate::try_cons), the macro returns early from the
+/// enclosing function: an [`io::Error`](https://doc.rust-lang.org/std/io/struct.Error.html) from reading a
+/// head is returned as is, and a [`ConsError`](crate::ConsError) if
+/// there aren't enough lines or a head doesn't match. Both are passed
+/// through [`From::from`], so `Box<dyn Error>` works as the error type:
+///
+/// ```rust
+/// # use cons::cons_lines;
+/// # use std::error::Error;
+/// # use std::io::BufRead;
+/// fn total(input: impl BufRead) -> Result<u32, Box<dyn Error>> {
+///     cons_lines!(input as (header if header == "name,amount")::records);
+///     let mut total = 0;
+///     for record in records {
+///         let record = record?;
+///         let (_, amount) = record.split_once(',').ok_or("missing amount")?;
+///         total += amount.parse::<u32>()?;
+///     }
+///     Ok(total)
+/// }
+///
+/// let input = "name,amount\nrent,800\nfood,150\n";
+/// assert_eq!(total(input.as_bytes()).unwrap(), 950);
+/// assert!(total("name\n".as_bytes()).is_err());
+/// ```
+///
+/// With the `std` feature, a [`ConsError`](crate::ConsError) also
+/// converts into an `io::Error` of kind `InvalidData`, so the macro can
+/// be used in a function that returns `io::Result`:
+///
+/// ```rust
+/// # #[cfg(feature = "std")] {
+/// # use cons::cons_lines;
+/// use std::io::{self, BufRead};
+///
+/// fn title(input: impl BufRead) -> io::Result<String> {
+///     cons_lines!(input as title::_);
+///     Ok(title)
+/// }
+///
+/// assert_eq!(title("Title\nbody".as_bytes()).unwrap(), "Title");
+/// let err = title("".as_bytes()).unwrap_err();
+/// assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+/// # }
+/// ```
+///
+/// The rest of the pattern syntax is the same as
+/// [`cons!`](crate::cons), except that string literals can't be used
+/// as heads, since the lines are `String`s. Ending the pattern in `nil` checks that
+/// there are no lines after the heads, and `_` ignores them:
+///
+/// ```rust
+/// # use cons::cons_lines;
+/// # use std::io::BufRead;
+/// fn version(input: impl BufRead) -> Result<u32, Box<dyn std::error::Error>> {
+///     cons_lines!(input as (magic if magic == "P3")::version::_);
+///     Ok(version.parse()?)
+/// }
+///
+/// assert_eq!(version("P3\n2\nrest".as_bytes()).unwrap(), 2);
+/// assert!(version("P6\n2".as_bytes()).is_err());
+/// ```
+///
+/// The macro expands to `std::io`, so the calling crate needs `std`.
+#[macro_export]
+macro_rules! cons_lines {
+    ($reader:ident as $($rest:tt)+) => {
+        $crate::cons_lines!(@__tail $reader => [] $($rest)+);
+    };
+    (($reader:expr) as $($rest:tt)+) => {
+        $crate::cons_lines!(@__tail $reader => [] $($rest)+);
+    };
+    (@__tail $reader:expr => [$($pat:tt)*] nil) => {
+        $crate::cons_lines!(@__ $reader => $($pat)* nil);
+    };
+    (@__tail $reader:expr => [$($pat:tt)*] $tl:ident) => {
+        // the tail is the rest of the lines rather than a `Vec` of them
+        $crate::cons_lines!(@__ $reader => $($pat)* $tl @ iter);
+    };
+    (@__tail $reader:expr => [$($pat:tt)*] $alias:ident ! () :: $($rest:tt)+) => {
+        $crate::cons_lines!(@__tail $reader => [$($pat)* $alias!() ::] $($rest)+);
+    };
+    (@__tail $reader:expr => [$($pat:tt)*] $hd:tt :: $($rest:tt)+) => {
+        $crate::cons_lines!(@__tail $reader => [$($pat)* $hd ::] $($rest)+);
+    };
+    (@__tail $reader:expr => [$($pat:tt)*] $($rest:tt)+) => {
+        $crate::cons_lines!(@__ $reader => $($pat)* $($rest)+);
+    };
+    (@__ $reader:expr => $($pat:tt)+) => {
+        $crate::cons!(@__try_ok (::std::io::BufRead::lines($reader)) => [] $($pat)+);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_lines [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufRead, Read};
+
+    use crate::ConsError;
+
+    #[derive(Debug)]
+    enum Error {
+        Io(io::Error),
+        Cons(ConsError),
+    }
+
+    impl From<io::Error> for Error {
+        fn from(e: io::Error) -> Self {
+            Self::Io(e)
+        }
+    }
+
+    impl From<ConsError> for Error {
+        fn from(e: ConsError) -> Self {
+            Self::Cons(e)
+        }
+    }
+
+    fn header(input: impl BufRead) -> Result<(String, String, Vec<String>), Error> {
+        cons_lines!(input as title::(sep if matches!(sep.as_str(), "" | "---"))::rest);
+        let first = rest.next().transpose()?.unwrap_or_default();
+        Ok((title, first, rest.collect::<Result<_, _>>()?))
+    }
+
+    #[test]
+    fn test_tail_is_lines() {
+        let (title, first, rest) = header("Title\n\nbody\nmore\n".as_bytes()).unwrap();
+        assert_eq!((title.as_str(), first.as_str()), ("Title", "body"));
+        assert_eq!(rest, ["more"]);
+
+        let (_, first, rest) = header("Title\n---".as_bytes()).unwrap();
+        assert!(first.is_empty() && rest.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            header("Title".as_bytes()),
+            Err(Error::Cons(ConsError::Exhausted { missing_binding: "sep", got: 1 }))
+        ));
+        assert!(matches!(
+            header("Title\nbody".as_bytes()),
+            Err(Error::Cons(ConsError::Mismatch { .. }))
+        ));
+        // invalid UTF-8 on the second line
+        assert!(matches!(
+            header(&b"Title\n\xFF\n"[..]),
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::InvalidData
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io_result() {
+        fn pair(input: impl BufRead) -> io::Result<(String, String)> {
+            cons_lines!(input as a::b::_);
+            Ok((a, b))
+        }
+        assert_eq!(pair("x\ny".as_bytes()).unwrap(), (String::from("x"), String::from("y")));
+        let err = pair("x".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref()),
+            Some(ConsError::Exhausted { missing_binding: "b", got: 1 })
+        ));
+        // read errors come through untouched
+        let err = pair(&b"\xFF"[..]).unwrap_err();
+        assert!(err.get_ref().is_none_or(|e| !e.is::<ConsError>()));
+    }
+
+    #[test]
+    fn test_nil() {
+        fn pair(input: impl Read) -> Result<(String, String), Error> {
+            cons_lines!((io::BufReader::new(input)) as a::b::nil);
+            Ok((a, b))
+        }
+        let (a, b) = pair("x\ny\n".as_bytes()).unwrap();
+        assert_eq!((a.as_str(), b.as_str()), ("x", "y"));
+        assert!(matches!(
+            pair("x\ny\nz".as_bytes()),
+            Err(Error::Cons(ConsError::TrailingElements { remaining: 1 }))
+        ));
+        assert!(matches!(pair(&b"x\ny\n\xFF"[..]), Err(Error::Io(_))));
+    }
+}
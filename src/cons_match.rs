@@ -0,0 +1,483 @@
+use core::ops::Deref;
+
+/// SML's `case` expression for cons patterns. Each arm is a cons
+/// pattern followed by `=>` and an expression; the arms are tried in
+/// order and the expression of the first one that matches is
+/// evaluated.
+///
+/// # Examples
+///
+/// ```rust
+/// # use cons::cons_match;
+/// fn describe(v: Vec<i32>) -> String {
+///     cons_match!(v {
+///         nil => String::from("empty"),
+///         x::nil => format!("just {x}"),
+///         x::y::nil => format!("{x} and {y}"),
+///         x::rest => format!("{x} and {} more", rest.len()),
+///     })
+/// }
+///
+/// assert_eq!(describe(vec![]), "empty");
+/// assert_eq!(describe(vec![1]), "just 1");
+/// assert_eq!(describe(vec![1, 2]), "1 and 2");
+/// assert_eq!(describe(vec![1, 2, 3, 4]), "1 and 3 more");
+/// ```
+///
+/// Unlike [`cons!`](crate::cons), any expression other than a plain
+/// variable must be wrapped in parentheses:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// let sum = cons_match!((1..=3) {
+///     x::y::_ => x + y,
+///     _ => 0,
+/// });
+/// assert_eq!(sum, 3);
+/// ```
+///
+/// The patterns are the same as those accepted by [`cons!`](crate::cons),
+/// with a few additions:
+///
+/// - `nil` (or `[]`) on its own only matches an empty iterator.
+/// - `_` on its own matches anything.
+/// - A single identifier on its own binds every element as a `Vec`.
+///
+/// Since the arms are actually tried against the elements, head
+/// patterns in parentheses may be refutable:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// let v = [Some(1), None, Some(3)];
+/// let first = cons_match!(v {
+///     (None)::_ => 0,
+///     (Some(x))::_ => x,
+///     _ => -1,
+/// });
+/// assert_eq!(first, 1);
+/// ```
+///
+/// Literal heads work the same way, so an arm can check for specific
+/// values:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// fn route(parts: Vec<&str>) -> String {
+///     cons_match!(parts {
+///         []  => String::from("index"),
+///         "users"::id::[] => format!("user {id}"),
+///         "users"::_ => String::from("not found"),
+///         page::_ => format!("page {page}"),
+///     })
+/// }
+///
+/// assert_eq!(route(vec![]), "index");
+/// assert_eq!(route(vec!["users", "7"]), "user 7");
+/// assert_eq!(route(vec!["users", "7", "posts"]), "not found");
+/// assert_eq!(route(vec!["about"]), "page about");
+/// ```
+///
+/// A literal head is compared with `==` rather than matched as a
+/// pattern, so a string literal also matches a `String`, a byte string
+/// matches a `Vec<u8>`, and references to elements are compared with
+/// what they point to. That makes it easy to dispatch on the first
+/// token of a command, falling through to the next arm when it doesn't
+/// match:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// fn handle(line: &str) -> String {
+///     let tokens: Vec<String> = line.split_whitespace().map(String::from).collect();
+///     cons_match!(tokens {
+///         "GET"::key::nil => format!("get {key}"),
+///         "PUT"::key::value::nil => format!("put {key}={value}"),
+///         "DEL"::keys => format!("delete {}", keys.join(",")),
+///         cmd::_ => format!("unknown command {cmd}"),
+///         nil => String::from("empty"),
+///     })
+/// }
+///
+/// assert_eq!(handle("GET a"), "get a");
+/// assert_eq!(handle("PUT a 1"), "put a=1");
+/// assert_eq!(handle("DEL a b"), "delete a,b");
+/// assert_eq!(handle("GET a b"), "unknown command GET");
+/// assert_eq!(handle(""), "empty");
+/// ```
+///
+/// A head can be an or-pattern, so one arm can cover several values
+/// or constructors without repeating its body:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// fn kind(tokens: Vec<Option<char>>) -> &'static str {
+///     cons_match!(tokens {
+///         (Some('+' | '-') | None)::_ => "sign",
+///         (Some('0'..='9'))::_ => "digit",
+///         _ => "other",
+///     })
+/// }
+///
+/// assert_eq!(kind(vec![Some('-'), Some('1')]), "sign");
+/// assert_eq!(kind(vec![None]), "sign");
+/// assert_eq!(kind(vec![Some('7')]), "digit");
+/// assert_eq!(kind(vec![]), "other");
+/// ```
+///
+/// Head patterns can also have guards. An arm only matches if all of
+/// its guards hold; the guards can refer to any of the arm's head
+/// bindings:
+///
+/// ```rust
+/// # use cons::cons_match;
+/// fn sign(v: &[i32]) -> &'static str {
+///     cons_match!((v.iter().copied()) {
+///         (x if x > 0)::_ => "positive",
+///         (x if x < 0)::_ => "negative",
+///         x::(y if y > x)::_ => "zero, then increasing",
+///         _ => "other",
+///     })
+/// }
+///
+/// assert_eq!(sign(&[1]), "positive");
+/// assert_eq!(sign(&[-1, 5]), "negative");
+/// assert_eq!(sign(&[0, 1]), "zero, then increasing");
+/// assert_eq!(sign(&[]), "other");
+/// ```
+///
+/// # Errors
+///
+/// The arms are checked at compile time. Arms whose heads only bind
+/// names match based on the number of elements alone, and together
+/// they must cover every length:
+///
+/// ```rust,compile_fail
+/// # use cons::cons_match;
+/// let v = [1, 2, 3];
+/// cons_match!(v {
+///     nil => 0,
+///     x::nil => x,
+/// }); // error: no arm matches 2 elements
+/// ```
+///
+/// Arms with literals, guards, or other refutable head patterns might
+/// not match, so they don't count towards covering a length, and a
+/// match that relies on them needs a `_` or `xs` arm at the end:
+///
+/// ```rust,compile_fail
+/// # use cons::cons_match;
+/// let v = [5];
+/// cons_match!(v {
+///     nil => 0,
+///     1::_ => 1,
+/// }); // error: no arm matches 1 element
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::cons_match;
+/// let v = [-1];
+/// cons_match!(v {
+///     (x if x > 0)::_ => x,
+///     nil => 0,
+/// }); // error: no arm matches 1 element
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::cons_match;
+/// let v = [Some(1)];
+/// cons_match!(v {
+///     (None)::_ => 1,
+///     nil => 0,
+/// }); // error: no arm matches 1 element
+/// ```
+///
+/// An arm that can never be reached because the arms before it already
+/// match every length it could match produces a warning.
+///
+/// # Performance
+///
+/// The iterator is collected into a buffer up front so that arms can
+/// be retried. Elements are only moved out of the buffer once an arm
+/// has matched.
+#[macro_export]
+macro_rules! cons_match {
+    ($iter:ident { $($arms:tt)* }) => {
+        $crate::cons_match!(@__aliases $iter => [] $($arms)*)
+    };
+    (($iter:expr) { $($arms:tt)* }) => {
+        $crate::cons_match!(@__aliases $iter => [] $($arms)*)
+    };
+    (
+        @__aliases $iter:expr => [$($done:tt)*]
+        $($pat:tt)::+ => $body:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::cons_match!(@__aliases $iter => [$($done)* $($pat)::+ => $body,] $($($rest)*)?)
+    };
+    (@__aliases $iter:expr => [$($done:tt)*] $alias:ident ! () $($rest:tt)*) => {
+        $alias!($alias [$crate::cons_match] [@__aliases $iter => [$($done)*]] [$($rest)*])
+    };
+    (@__aliases $iter:expr => [$($done:tt)*] => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::cons_match!(@__aliases $iter => [$($done)* => $body,] $($($rest)*)?)
+    };
+    (@__aliases $iter:expr => [$($done:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons_match!(@__aliases $iter => [$($done)* $next] $($rest)*)
+    };
+    (@__aliases $iter:expr => [$($arms:tt)*]) => {
+        $crate::cons_match!(@__check $iter => [$($arms)*] [] $($arms)*)
+    };
+    (
+        @__check $iter:expr => [$($arms:tt)*] [$($checked:tt)*]
+        $($pat:tt)::+ => $body:expr $(, $($rest:tt)*)?
+    ) => {
+        $crate::cons_match!(@__check $iter => [$($arms)*] [$($checked)* [$($pat)::+]] $($($rest)*)?)
+    };
+    (@__check $iter:expr => [$($arms:tt)*] [$($checked:tt)*]) => {{
+        $crate::__private::check_arms!($($checked)*);
+        #[allow(unused_mut)]
+        let mut buf: $crate::__private::VecDeque<_> = $crate::cons!(@__dispatch __cons_iter $iter).collect();
+        $crate::cons_match!(@__arms buf => $($arms)*)
+    }};
+    (@__arms $buf:ident =>) => {
+        ::core::panic!("No arm of cons_match! matched the iterator")
+    };
+    (@__arms $buf:ident => $($pat:tt)::+ => $body:expr $(, $($arms:tt)*)?) => {
+        $crate::cons_match!(@__arm $buf [$($pat)::+] $body => $($($arms)*)?)
+    };
+    (@__arm $buf:ident [nil] $body:expr => $($arms:tt)*) => {
+        if $buf.is_empty() {
+            $body
+        } else {
+            $crate::cons_match!(@__arms $buf => $($arms)*)
+        }
+    };
+    (@__arm $buf:ident [[]] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__arm $buf [nil] $body => $($arms)*)
+    };
+    (@__arm $buf:ident [_] $body:expr => $($arms:tt)*) => {{
+        drop($buf);
+        $body
+    }};
+    (@__arm $buf:ident [$xs:ident] $body:expr => $($arms:tt)*) => {{
+        let $xs = $crate::__private::Vec::from($buf);
+        $body
+    }};
+    (@__arm $buf:ident [$($pat:tt)::+] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__heads $buf [] [$($pat)::+] $body => $($arms)*)
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [nil] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__try $buf [$($acc)*] [$buf.is_empty()] [] $body => $($arms)*)
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [[]] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__heads $buf [$($acc)*] [nil] $body => $($arms)*)
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [_] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__try $buf [$($acc)*] [true] [] $body => $($arms)*)
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [$tl:ident] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(
+            @__try $buf [$($acc)*] [true]
+            [let $tl = $crate::__private::Vec::from($buf);]
+            $body => $($arms)*
+        )
+    };
+    (
+        @__heads $buf:ident [$($acc:tt)*] [($hd:pat if $guard:expr) :: $($pat:tt)::+]
+        $body:expr => $($arms:tt)*
+    ) => {
+        $crate::cons_match!(
+            @__heads $buf [$($acc)* (hd, $hd, $guard)] [$($pat)::+] $body => $($arms)*
+        )
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [($hd:pat) :: $($pat:tt)::+] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__heads $buf [$($acc)* (hd, $hd, true)] [$($pat)::+] $body => $($arms)*)
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [$lit:literal :: $($pat:tt)::+] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(
+            @__heads $buf [$($acc)* (hd, elem, {
+                #[allow(unused_imports)]
+                use $crate::__private::{DerefEqLit as _, EqLit as _};
+                (&$crate::__private::Lit(&elem, $lit)).__cons_eq()
+            })]
+            [$($pat)::+] $body => $($arms)*
+        )
+    };
+    (@__heads $buf:ident [$($acc:tt)*] [$hd:tt :: $($pat:tt)::+] $body:expr => $($arms:tt)*) => {
+        $crate::cons_match!(@__heads $buf [$($acc)* (hd, $hd, true)] [$($pat)::+] $body => $($arms)*)
+    };
+    (
+        @__try $buf:ident [$(($name:ident, $hd:pat, $guard:expr))*] [$cond:expr]
+        [$($bind_tail:tt)*] $body:expr => $($arms:tt)*
+    ) => {{
+        $(let $name = $buf.pop_front();)*
+        match [$($name),*] {
+            [$(::core::option::Option::Some($hd)),*] if $cond $(&& $guard)* => {
+                $($bind_tail)*
+                $body
+            }
+            heads => {
+                for hd in heads.into_iter().flatten().rev() {
+                    $buf.push_front(hd);
+                }
+                $crate::cons_match!(@__arms $buf => $($arms)*)
+            }
+        }
+    }};
+}
+
+/// Compares an element with a literal head, using `==` rather than a
+/// literal pattern so that a `String` can match a string literal.
+/// Elements that are references to something comparable are compared
+/// through the reference.
+#[doc(hidden)]
+pub struct Lit<'a, T, L>(pub &'a T, pub L);
+
+#[doc(hidden)]
+pub trait EqLit {
+    fn __cons_eq(&self) -> bool;
+}
+
+impl<T: PartialEq<L>, L> EqLit for &Lit<'_, T, L> {
+    fn __cons_eq(&self) -> bool {
+        *self.0 == self.1
+    }
+}
+
+#[doc(hidden)]
+pub trait DerefEqLit {
+    fn __cons_eq(&self) -> bool;
+}
+
+impl<T, L> DerefEqLit for Lit<'_, T, L>
+where
+    T: Deref,
+    T::Target: PartialEq<L>,
+{
+    fn __cons_eq(&self) -> bool {
+        **self.0 == self.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_arms_in_order() {
+        let classify = |v: Vec<i32>| {
+            cons_match!(v {
+                nil => 0,
+                _x::nil => 1,
+                _x::_y::_ => 2,
+            })
+        };
+        assert_eq!(classify(vec![]), 0);
+        assert_eq!(classify(vec![1]), 1);
+        assert_eq!(classify(vec![1, 2]), 2);
+        assert_eq!(classify(vec![1, 2, 3]), 2);
+    }
+
+    #[test]
+    fn test_failed_arm_restores_elements() {
+        let v = vec![(1, 2), (3, 4), (5, 6)];
+        let result = cons_match!(v {
+            a::b::nil => vec![a, b],
+            a::b::c::d::rest => { let mut v = vec![a, b, c, d]; v.extend(rest); v },
+            a::((x, y))::rest => vec![a, (x, y), (rest.len() as i32, 0)],
+            _ => vec![],
+        });
+        assert_eq!(result, vec![(1, 2), (3, 4), (1, 0)]);
+    }
+
+    #[test]
+    fn test_refutable_heads() {
+        let v = vec![Some(String::from("a")), None];
+        let result = cons_match!(v {
+            (None)::_ => String::from("none"),
+            (Some(s))::(Some(t))::nil => s + &t,
+            (Some(s))::(None)::nil => s,
+            _ => String::new(),
+        });
+        assert_eq!(result, "a");
+    }
+
+    #[test]
+    fn test_guards() {
+        let v = vec![String::from("ab"), String::from("c")];
+        let result = cons_match!(v {
+            (s if s.len() > 2)::_ => 0,
+            s::(t if t.len() > s.len())::nil => 1,
+            (s if s.starts_with('a'))::t::nil => s.len() + t.len(),
+            _ => 3,
+        });
+        assert_eq!(result, 3);
+    }
+
+    #[test]
+    fn test_literals_and_empty_alias() {
+        let count = |v: Vec<i32>| {
+            cons_match!(v {
+                [] => 0,
+                0::rest => 10 + rest.len(),
+                1::2::[] => 12,
+                _ => 99,
+            })
+        };
+        assert_eq!(count(vec![]), 0);
+        assert_eq!(count(vec![0, 5]), 11);
+        assert_eq!(count(vec![1, 2]), 12);
+        assert_eq!(count(vec![1, 2, 3]), 99);
+    }
+
+    #[test]
+    fn test_literals_compare_with_eq() {
+        let frames = vec![b"PING".to_vec(), b"1".to_vec()];
+        let kind = cons_match!(frames {
+            b"PONG"::_ => 0,
+            b"PING"::n::nil => n.len(),
+            _ => 9,
+        });
+        assert_eq!(kind, 1);
+
+        let bytes = [2u8, 0];
+        let kind = cons_match!((bytes.iter()) {
+            1::_ => "one",
+            2::0::nil => "two",
+            _ => "other",
+        });
+        assert_eq!(kind, "two");
+    }
+
+    #[test]
+    fn test_or_patterns() {
+        let classify = |v: Vec<(i32, char)>| {
+            cons_match!(v {
+                ((0, _) | (_, 'z'))::rest => rest.len() as i32,
+                ((1 | 3 | 5, _) | (_, 'a'))::((n @ (4 | 7), _))::_ => n,
+                _ => -1,
+            })
+        };
+        assert_eq!(classify(vec![(0, 'a'), (9, 'b')]), 1);
+        assert_eq!(classify(vec![(7, 'z')]), 0);
+        assert_eq!(classify(vec![(2, 'a'), (7, 'b')]), 7);
+        assert_eq!(classify(vec![(2, 'a'), (6, 'b')]), -1);
+    }
+
+    #[test]
+    fn test_whole_binding() {
+        let result = cons_match!((1..=4) {
+            nil => vec![],
+            xs => xs,
+        });
+        assert_eq!(result, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_control_flow_in_body() {
+        let mut seen = vec![];
+        for v in [vec![1], vec![], vec![2, 3]] {
+            let x = cons_match!(v {
+                nil => continue,
+                x::_ => x,
+            });
+            seen.push(x);
+        }
+        assert_eq!(seen, vec![1, 2]);
+    }
+}
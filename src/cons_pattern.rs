@@ -0,0 +1,99 @@
+/// Gives a name to a fragment of a cons pattern, so it can be reused
+/// in later invocations of [`cons!`](crate::cons) and
+/// [`cons_match!`](crate::cons_match).
+///
+/// The fragment is written as `name!()` wherever it is used, and is
+/// replaced by the segments it stands for before the pattern is
+/// matched. It can be any number of segments, and can include the
+/// tail:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::{cons, cons_match, cons_pattern};
+/// enum Frame {
+///     Header(u8),
+///     Point(i32, i32),
+/// }
+///
+/// cons_pattern!(header = (Frame::Header(version))::(Frame::Point(x, y)));
+///
+/// fn origin(frames: Vec<Frame>) -> Option<(i32, i32)> {
+///     cons!(frames as header!()::_ else { return None });
+///     Some((x, y))
+/// }
+///
+/// fn describe(frames: Vec<Frame>) -> String {
+///     cons_match!(frames {
+///         header!()::nil => format!("v{version} at ({x}, {y})"),
+///         header!()::rest => format!("v{version} with {} more", rest.len()),
+///         _ => String::from("invalid"),
+///     })
+/// }
+///
+/// let frames = vec![Frame::Header(2), Frame::Point(1, 3)];
+/// assert_eq!(origin(frames), Some((1, 3)));
+/// assert_eq!(describe(vec![Frame::Header(1), Frame::Point(0, 0)]), "v1 at (0, 0)");
+/// assert_eq!(describe(vec![Frame::Point(0, 0)]), "invalid");
+/// # }
+/// ```
+///
+/// An alias is an ordinary `macro_rules!` macro, so it can be used
+/// anywhere after its declaration in the same module, and in child
+/// modules declared after it.
+#[macro_export]
+macro_rules! cons_pattern {
+    ($name:ident = $($frag:tt)+) => {
+        $crate::cons_pattern!(@__define ($) $name [$($frag)+]);
+    };
+    (@__define ($d:tt) $name:ident [$($frag:tt)+]) => {
+        macro_rules! $name {
+            ($d alias:ident $d($d args:tt)*) => {
+                $crate::__private::expand_alias!($d alias $d($d args)* [$($frag)+])
+            };
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cons;
+    #[cfg(feature = "alloc")]
+    use crate::cons_match;
+
+    cons_pattern!(pair = a::b);
+    #[cfg(feature = "alloc")]
+    cons_pattern!(rest_of_pair = ((a, b))::rest);
+
+    #[test]
+    fn test_alias_in_middle() {
+        let v = vec![0, 1, 2];
+        cons!(v as _::pair!()::nil);
+        assert_eq!((a, b), (1, 2));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_alias_with_tail() {
+        let v = [(1, 'a'), (2, 'b')];
+        cons!(v as rest_of_pair!());
+        assert_eq!((a, b), (1, 'a'));
+        assert_eq!(rest, vec![(2, 'b')]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_alias_in_cons_match() {
+        let f = |v: Vec<i32>| {
+            cons_match!(v {
+                nil => 0,
+                pair!()::nil => a + b,
+                pair!()::_ => a * b,
+                _ => -1,
+            })
+        };
+        assert_eq!(f(vec![]), 0);
+        assert_eq!(f(vec![2, 3]), 5);
+        assert_eq!(f(vec![2, 3, 4]), 6);
+        assert_eq!(f(vec![2]), -1);
+    }
+}
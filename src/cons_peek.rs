@@ -0,0 +1,98 @@
+/// Looks at the first few elements of an iterator without consuming
+/// them, for lookahead in parsers.
+///
+/// The pattern is just the heads, as in
+/// [`cons_drain!`](crate::cons_drain). They are matched against a
+/// clone of the iterator, so the iterator itself is left where it was
+/// and the heads are bound to the cloned elements:
+///
+/// ```rust
+/// # use cons::{cons, cons_peek};
+/// let mut chars = "->x".chars();
+/// cons_peek!(chars as a::b);
+/// assert_eq!((a, b), ('-', '>'));
+///
+/// // nothing was consumed
+/// cons!(chars as '-'::'>'::rest: String);
+/// assert_eq!(rest, "x");
+/// ```
+///
+/// This works with any iterator that implements [`Clone`], which
+/// includes a [`Peekable`](core::iter::Peekable) over one. Most
+/// iterators over collections are cheap to clone, since they only hold
+/// a position.
+///
+/// As with [`cons!`](crate::cons), heads can be literals or guarded
+/// patterns, and an `else` block can be given to run instead of
+/// panicking when they don't match, which makes it easy to decide what
+/// to parse next:
+///
+/// ```rust
+/// # use cons::cons_peek;
+/// fn is_arrow(tokens: &std::slice::Iter<'_, &str>) -> bool {
+///     cons_peek!((tokens.clone()) as (&"-")::(&">") else { return false });
+///     true
+/// }
+///
+/// let tokens = ["-", ">", "x"];
+/// assert!(is_arrow(&tokens.iter()));
+/// assert!(!is_arrow(&tokens[1..].iter()));
+/// ```
+///
+/// # Panics
+///
+/// Without an `else` block, the macro panics if the iterator is too
+/// short for the pattern or a head doesn't match.
+#[macro_export]
+macro_rules! cons_peek {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::cons_peek!(@__else $iter => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::cons_peek!(@__else $iter => [] $($rest)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::cons_peek!(@__ [else $else] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+]) => {
+        $crate::cons_peek!(@__ [panic] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons_peek!(@__else $iter => [$($pat)* $next] $($rest)*);
+    };
+    (@__ $mode:tt $iter:expr => $($hd:tt)::+) => {
+        $crate::cons!(@__start $mode (::core::clone::Clone::clone(&$iter)) => $($hd ::)+ _);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_peek [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_peekable() {
+        let mut iter = [1, 2, 3].into_iter().peekable();
+        cons_peek!(iter as x::(y if y > x));
+        assert_eq!((x, y), (1, 2));
+        assert_eq!(iter.next(), Some(1));
+    }
+
+    #[test]
+    fn test_else() {
+        let iter = "ab".chars();
+        let has3 = || {
+            cons_peek!(iter as _::_::_ else { return false });
+            true
+        };
+        assert!(!has3());
+        assert_eq!(iter.as_str(), "ab");
+    }
+
+    #[test]
+    #[should_panic(expected = "Element did not match pattern `'c'`")]
+    fn test_mismatch() {
+        let iter = "ab".chars();
+        cons_peek!(iter as 'a'::'c');
+    }
+}
@@ -0,0 +1,210 @@
+/// A version of [`cons!`](crate::cons) for slices that never
+/// allocates. Heads are bound as references into the slice and the
+/// tail is bound as a subslice rather than a `Vec`.
+///
+/// Anything that coerces to a slice works, including arrays, `Vec`s,
+/// and slices themselves. The input is only borrowed, so it can still
+/// be used afterwards.
+///
+/// # Examples
+///
+/// ```rust
+/// # use cons::cons_slice;
+/// let v = vec![1, 2, 3, 4];
+/// cons_slice!(v as x::y::zs);
+/// assert_eq!(*x, 1);
+/// assert_eq!(*y, 2);
+/// assert_eq!(zs, &[3, 4]);
+/// // `v` was only borrowed
+/// assert_eq!(v.len(), 4);
+/// ```
+///
+/// The rest of the pattern syntax is the same as [`cons!`](crate::cons),
+/// including `nil`, `_`, literals, destructuring, guards, and an
+/// `else` block:
+///
+/// ```rust
+/// # use cons::cons_slice;
+/// let points = [(1, 2), (3, 4)];
+/// cons_slice!(points as _::((x, y))::nil);
+/// assert_eq!((*x, *y), (3, 4));
+///
+/// fn first(s: &[u8]) -> Option<u8> {
+///     cons_slice!(s as b::_ else { return None });
+///     Some(*b)
+/// }
+/// assert_eq!(first(b"hi"), Some(b'h'));
+/// assert_eq!(first(b""), None);
+/// ```
+///
+/// The macro can also be used in a `const fn` or a `const` item, for
+/// tables computed at compile time:
+///
+/// ```rust
+/// # use cons::cons_slice;
+/// const fn opcode(insn: &[u8]) -> u8 {
+///     cons_slice!(insn as op::_ else { return 0 });
+///     *op
+/// }
+/// const NOP: u8 = opcode(&[0x90, 0x00]);
+/// assert_eq!(NOP, 0x90);
+/// ```
+///
+/// Guards there have to be `const` too, and a pattern that ends in
+/// `nil` needs an `else` block, since the message it panics with isn't
+/// available at compile time.
+///
+/// # Panics
+///
+/// Like [`cons!`](crate::cons), the macro panics if the slice is too
+/// short for the pattern, or if the pattern ends in `nil` and there
+/// are elements left over.
+///
+/// ```rust,should_panic
+/// # use cons::cons_slice;
+/// let v = [1];
+/// cons_slice!(v as x::y::zs); // panics, slice not long enough
+/// ```
+#[macro_export]
+macro_rules! cons_slice {
+    ($slice:ident as $($rest:tt)+) => {
+        $crate::cons_slice!(@__else ($crate::cons_slice!(@__as_slice $slice)) => [] $($rest)+);
+    };
+    (($slice:expr) as $($rest:tt)+) => {
+        $crate::cons_slice!(@__else ($crate::cons_slice!(@__as_slice $slice)) => [] $($rest)+);
+    };
+    (@__as_slice $slice:expr) => {{
+        // a coercion rather than `[..]`, which can't be used in a `const fn`
+        let slice: &[_] = &$slice;
+        slice
+    }};
+    (@__else $slice:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::cons_slice!(@__ [else $else] $slice => $($pat)+);
+    };
+    (@__else $slice:expr => [$($pat:tt)+]) => {
+        $crate::cons_slice!(@__ [panic] $slice => $($pat)+);
+    };
+    (@__else $slice:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons_slice!(@__else $slice => [$($pat)* $next] $($rest)*);
+    };
+    (@__ $mode:tt $slice:expr => nil) => {
+        let rest = <[_]>::len($slice);
+        let 0 = rest else {
+            $crate::cons!(@__fail $mode not_empty rest)
+        };
+    };
+    (@__ $mode:tt $slice:expr => []) => {
+        $crate::cons_slice!(@__ $mode $slice => nil);
+    };
+    (@__ $mode:tt $slice:expr => $hd:ident) => {
+        let $hd: &[_] = $slice;
+    };
+    (@__ $mode:tt $slice:expr => _) => {
+        let _ = $slice;
+    };
+    (@__ $mode:tt $slice:expr => $hd:literal :: $($rest:tt)+) => {
+        $crate::cons_slice!(@__ $mode $slice => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $slice:expr => nil :: $($rest:tt)+) => {
+        $crate::__private::diagnose!(pattern nil :: $($rest)+);
+    };
+    (@__ $mode:tt $slice:expr => $hd:ident :: $($rest:tt)+) => {
+        $crate::cons_slice!(@__ $mode $slice => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $slice:expr => _ :: $($rest:tt)+) => {
+        $crate::cons_slice!(@__ $mode $slice => (_) :: $($rest)+);
+    };
+    (@__ $mode:tt $slice:expr => ($hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        let ::core::option::Option::Some((hd, slice)) = <[_]>::split_first($slice) else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens)]
+        let ($hd) = hd else {
+            $crate::cons!(@__fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [$hd if $guard])
+        };)?
+        $crate::cons_slice!(@__ $mode slice => $($rest)+);
+    };
+    (@__ $mode:tt $slice:expr => $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_slice [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_tail_is_subslice() {
+        let v = [String::from("a"), String::from("b"), String::from("c")];
+        cons_slice!(v as x::xs);
+        assert_eq!(x, "a");
+        assert_eq!(xs, ["b", "c"]);
+        assert!(std::ptr::eq(xs, &v[1..]));
+    }
+
+    #[test]
+    fn test_expression_input() {
+        let v = [1, 2, 3, 4, 5];
+        cons_slice!((v[1..].iter().as_slice()) as a::b::_::rest);
+        assert_eq!((*a, *b), (2, 3));
+        assert_eq!(rest, &[5]);
+    }
+
+    #[test]
+    fn test_guard() {
+        let v = [1, 2, 3];
+        cons_slice!(v as (x if *x == 1)::rest);
+        assert_eq!(*x, 1);
+        assert_eq!(rest, &[2, 3]);
+    }
+
+    #[test]
+    fn test_literal_heads() {
+        let v = *b"GET /";
+        cons_slice!(v as b'G'::b'E'::b'T'::rest);
+        assert_eq!(rest, b" /");
+    }
+
+    #[test]
+    fn test_nil() {
+        let v = [1, 2];
+        cons_slice!(v as x::y::nil);
+        assert_eq!((*x, *y), (1, 2));
+    }
+
+    const fn sum2(xs: &[u32]) -> u32 {
+        cons_slice!(xs as a::b::_ else { return 0 });
+        *a + *b
+    }
+
+    #[test]
+    fn test_const() {
+        const SUM: u32 = sum2(&[1, 2, 3]);
+        const EMPTY: u32 = sum2(&[]);
+        const REST: usize = {
+            cons_slice!([7, 1, 3] as 7::x::rest);
+            *x as usize + rest.len()
+        };
+        assert_eq!((SUM, EMPTY, REST), (3, 0, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable y")]
+    #[allow(unused_variables)]
+    fn test_too_short() {
+        let v = [1];
+        cons_slice!(v as _x::y::_);
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (2 elements left)")]
+    fn test_too_long() {
+        let v = [1, 2, 3];
+        cons_slice!(v as _x::nil);
+    }
+}
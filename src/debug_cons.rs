@@ -0,0 +1,48 @@
+/// Binds a cons pattern like [`cons!`](crate::cons), then prints every
+/// name it bound to stderr, in the format of [`dbg!`](https://doc.rust-lang.org/std/macro.dbg.html).
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::debug_cons;
+/// let v = vec![1, 2, 3];
+/// debug_cons!(v as x::y::rest);
+/// // [src/main.rs:4:1] x = 1
+/// // [src/main.rs:4:1] y = 2
+/// // [src/main.rs:4:1] rest = [
+/// //     3,
+/// // ]
+/// # assert_eq!((x, y, rest), (1, 2, vec![3]));
+/// # }
+/// ```
+///
+/// Everything `cons!` accepts works here too. The bound values have to
+/// implement `Debug`, except for a tail bound with `@ iter`, which isn't
+/// printed. The printing is compiled only with debug assertions, so
+/// in release builds this is just `cons!`.
+///
+/// The macro expands to `std::eprintln!`, so the calling crate needs
+/// `std`.
+#[macro_export]
+macro_rules! debug_cons {
+    ($($invocation:tt)+) => {
+        $crate::cons!($($invocation)+);
+        $crate::__private::print_bindings!($($invocation)+);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_same_bindings_as_cons() {
+        let v = vec!["a", "b", "c"];
+        debug_cons!(v as x::(y: &str)::rest @ iter);
+        assert_eq!((x, y, rest.collect::<Vec<_>>()), ("a", "b", vec!["c"]));
+
+        let (first, second);
+        debug_cons!((1..=2) => first::second::nil);
+        assert_eq!((first, second), (1, 2));
+
+        debug_cons!((vec![1]) as a::(b = 0)::_, "context {}", 1);
+        assert_eq!((a, b), (1, 0));
+    }
+}
@@ -0,0 +1,209 @@
+//! A builder for [`List`]s that appends in O(1).
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::list::{GenericList, PointerKind, RcKind};
+#[cfg(doc)]
+use crate::List;
+
+/// A difference list: a list that is built by adding to the back, then
+/// turned into a [`List`] once it is done.
+///
+/// Consing onto the front of a persistent list is O(1), but appending
+/// to the back has to copy the whole list, so building a list front to
+/// back in a loop is O(n²). A `DList` instead keeps the pieces that were
+/// added in order, so [`push_back`](DList::push_back) and
+/// [`append_list`](DList::append_list) are O(1), and puts them
+/// together once in [`into_list`](DList::into_list):
+///
+/// ```rust
+/// # use cons::{list, DList};
+/// let mut out = DList::new();
+/// for word in "a b c".split(' ') {
+///     out.push_back(word);
+/// }
+/// out.append_list(&list!["x", "y"]);
+/// assert_eq!(out.into_list(), list!["a", "b", "c", "x", "y"]);
+/// ```
+///
+/// Appended lists are not copied until the end, and the last one is
+/// shared with the result, like with [`List::append`].
+pub struct DList<T, P: PointerKind = RcKind> {
+    segments: Vec<Segment<T, P>>,
+    len: usize,
+}
+
+enum Segment<T, P: PointerKind> {
+    Elems(Vec<T>),
+    List(GenericList<T, P>),
+}
+
+impl<T, P: PointerKind> DList<T, P> {
+    /// Creates a new, empty `DList`.
+    pub const fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements that have been added.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no elements have been added.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds an element to the back. This is amortized O(1).
+    pub fn push_back(&mut self, elem: T) {
+        self.len += 1;
+        if let Some(Segment::Elems(elems)) = self.segments.last_mut() {
+            elems.push(elem);
+        } else {
+            self.segments.push(Segment::Elems(Vec::from([elem])));
+        }
+    }
+
+    /// Adds the elements of a list to the back. This is O(1), since
+    /// the list is shared rather than copied.
+    pub fn append_list(&mut self, list: &GenericList<T, P>) {
+        if !list.is_empty() {
+            self.len += list.len();
+            self.segments.push(Segment::List(list.clone()));
+        }
+    }
+
+    /// Adds the elements of another `DList` to the back. This is O(1)
+    /// for each piece `other` was built from, rather than for each
+    /// element.
+    pub fn append(&mut self, mut other: Self) {
+        self.len += other.len;
+        self.segments.append(&mut other.segments);
+    }
+}
+
+impl<T: Clone, P: PointerKind> DList<T, P> {
+    /// Puts the pieces together into a list. This is O(n), except that
+    /// a list appended last is shared rather than copied.
+    ///
+    /// ```rust
+    /// # use cons::{list, DList};
+    /// let tail = list![3, 4];
+    /// let mut d = DList::new();
+    /// d.push_back(1);
+    /// d.push_back(2);
+    /// d.append_list(&tail);
+    /// let xs = d.into_list();
+    /// assert_eq!(xs, list![1, 2, 3, 4]);
+    /// assert!(std::ptr::eq(xs.get(2).unwrap(), tail.head().unwrap()));
+    /// ```
+    pub fn into_list(self) -> GenericList<T, P> {
+        let mut list = GenericList::new();
+        for segment in self.segments.into_iter().rev() {
+            match segment {
+                Segment::Elems(elems) => {
+                    for x in elems.into_iter().rev() {
+                        list = list.cons(x);
+                    }
+                }
+                Segment::List(xs) => list = xs.append(&list),
+            }
+        }
+        list
+    }
+}
+
+impl<T, P: PointerKind> Default for DList<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, P: PointerKind> Clone for DList<T, P> {
+    fn clone(&self) -> Self {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Elems(elems) => Segment::Elems(elems.clone()),
+                Segment::List(xs) => Segment::List(xs.clone()),
+            })
+            .collect();
+        Self {
+            segments,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for DList<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        for segment in &self.segments {
+            match segment {
+                Segment::Elems(elems) => list.entries(elems),
+                Segment::List(xs) => list.entries(xs),
+            };
+        }
+        list.finish()
+    }
+}
+
+impl<T, P: PointerKind> Extend<T> for DList<T, P> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.push_back(x);
+        }
+    }
+}
+
+impl<T, P: PointerKind> FromIterator<T> for DList<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut d = Self::new();
+        d.extend(iter);
+        d
+    }
+}
+
+impl<T: Clone, P: PointerKind> From<DList<T, P>> for GenericList<T, P> {
+    fn from(d: DList<T, P>) -> Self {
+        d.into_list()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list, List};
+
+    #[test]
+    fn test_pieces_in_order() {
+        let mut d: DList<i32> = (1..=2).collect();
+        d.append_list(&list![3, 4]);
+        d.push_back(5);
+        d.append_list(&List::new());
+        let mut e = DList::new();
+        e.append_list(&list![6]);
+        e.push_back(7);
+        d.append(e);
+        assert_eq!(d.len(), 7);
+        assert_eq!(format!("{d:?}"), "[1, 2, 3, 4, 5, 6, 7]");
+        assert_eq!(List::from(d), (1..=7).collect::<List<_>>());
+        assert_eq!(DList::<i32>::new().into_list(), List::new());
+    }
+
+    #[test]
+    fn test_long() {
+        let mut d = DList::new();
+        for i in 0..1_000_000u32 {
+            d.push_back(i);
+        }
+        let xs: List<_> = d.into_list();
+        assert_eq!(xs.len(), 1_000_000);
+        assert_eq!(xs.last(), Some(&999_999));
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+
+/// The error produced by [`try_cons!`](crate::try_cons) when the
+/// iterator does not fit the cons pattern.
+///
+/// ```rust
+/// # use cons::{try_cons, ConsError};
+/// fn parse(v: &[i32]) -> Result<i32, ConsError> {
+///     try_cons!((v.iter().copied()) as x::y::z::nil);
+///     Ok(x + y + z)
+/// }
+///
+/// match parse(&[1, 2]) {
+///     Err(ConsError::Exhausted { missing_binding, got }) => {
+///         assert_eq!((missing_binding, got), ("z", 2));
+///     }
+///     other => panic!("unexpected {other:?}"),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsError {
+    /// The iterator ran out of elements before the named variable
+    /// could be bound.
+    Exhausted {
+        /// The name of the variable that could not be bound.
+        missing_binding: &'static str,
+        /// How many elements were bound before the iterator ran out.
+        got: usize,
+    },
+    /// The pattern ended in `nil` but the iterator still had elements.
+    TrailingElements {
+        /// How many elements were left in the iterator.
+        remaining: usize,
+    },
+    /// The tail was collected but its length didn't satisfy the
+    /// requirement written after it, as in `xs[>= 2]`.
+    TailLength {
+        /// The name of the tail.
+        binding: &'static str,
+        /// What the pattern asked for, such as `"at least 2"`.
+        expected: &'static str,
+        /// How many elements the tail actually had.
+        got: usize,
+    },
+    /// An element was bound but did not match a literal or refutable
+    /// head pattern, or did not satisfy its guard.
+    Mismatch {
+        /// The pattern (including its guard) that was not satisfied.
+        pattern: &'static str,
+        /// How many elements were bound before the one that didn't
+        /// match, i.e. its index.
+        position: usize,
+    },
+}
+
+impl fmt::Display for ConsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exhausted {
+                missing_binding,
+                got,
+            } => write!(
+                f,
+                "Iterator exhausted after {got} elements, before reaching variable {missing_binding}"
+            ),
+            Self::TrailingElements { remaining } => write!(
+                f,
+                "Found `nil` in cons but iterator is not empty ({remaining} elements left)"
+            ),
+            Self::TailLength {
+                binding,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Tail {binding} has {got} elements, but the pattern expects {expected}"
+            ),
+            Self::Mismatch { pattern, position } => {
+                write!(f, "Element {position} did not match pattern `{pattern}`")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ConsError {}
+
+/// A [`ConsError`] together with a message saying what was being
+/// matched, produced by [`try_cons!`](crate::try_cons) when a message
+/// is given after the pattern.
+///
+/// It displays as just the message, and its
+/// [`source`](core::error::Error::source) is the `ConsError`, so error
+/// reporters that walk the chain of sources, such as `anyhow`'s, show
+/// each of them once:
+///
+/// ```rust
+/// # use cons::{try_cons, ConsError, ContextError};
+/// use std::error::Error;
+///
+/// fn header(lines: Vec<&str>, path: &str) -> Result<(), ContextError> {
+///     try_cons!(lines as "[package]"::_, "reading the header of {}", path);
+///     Ok(())
+/// }
+///
+/// let err = header(vec!["[lib]"], "Cargo.toml").unwrap_err();
+/// assert_eq!(err.to_string(), "reading the header of Cargo.toml");
+/// assert_eq!(
+///     err.source().unwrap().to_string(),
+///     "Element 0 did not match pattern `\"[package]\"`"
+/// );
+/// assert!(matches!(err.error(), ConsError::Mismatch { position: 0, .. }));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextError {
+    message: String,
+    error: ConsError,
+}
+
+#[cfg(feature = "alloc")]
+impl ContextError {
+    /// Attaches a message to an error.
+    pub fn new(message: impl Into<String>, error: ConsError) -> Self {
+        Self {
+            message: message.into(),
+            error,
+        }
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the error the message is attached to.
+    pub fn error(&self) -> ConsError {
+        self.error
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the error itself is the source, so reporters print it next
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<ContextError> for ConsError {
+    /// Drops the message, for callers that only deal in `ConsError`.
+    fn from(e: ContextError) -> Self {
+        e.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ConsError> for std::io::Error {
+    /// Wraps the error with [`std::io::ErrorKind::InvalidData`], since
+    /// it means the input didn't have the expected shape. This lets
+    /// [`cons_lines!`](crate::cons_lines) and
+    /// [`try_cons!`](crate::try_cons) be used in functions that return
+    /// `io::Result`.
+    fn from(e: ConsError) -> Self {
+        Self::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
@@ -0,0 +1,70 @@
+/// Splits a string into a `Vec` of its characters, like SML's
+/// `String.explode`.
+///
+/// ```rust
+/// # use cons::{cons, explode};
+/// let chars = explode!("abc");
+/// assert_eq!(chars, vec!['a', 'b', 'c']);
+///
+/// cons!(chars as first::_);
+/// assert_eq!(first, 'a');
+/// ```
+///
+/// To cons over a string without building the `Vec`, match its
+/// [`chars`](str::chars) directly. The tail can be collected back into
+/// a `String`:
+///
+/// ```rust
+/// # use cons::cons;
+/// cons!(("hello".chars()) as 'h'::rest: String);
+/// assert_eq!(rest, "ello");
+/// ```
+#[macro_export]
+macro_rules! explode {
+    ($s:expr) => {
+        <$crate::__private::Vec<char> as ::core::iter::FromIterator<char>>::from_iter(
+            ::core::primitive::str::chars(::core::convert::AsRef::<str>::as_ref(&$s)),
+        )
+    };
+}
+
+/// Joins characters into a `String`, like SML's `String.implode`. It
+/// takes anything that iterates over `char`s, such as the `Vec` from
+/// [`explode!`] or the tail of a cons pattern.
+///
+/// ```rust
+/// # use cons::{cons, explode, implode};
+/// let chars = explode!("cons");
+/// cons!(chars as c::rest);
+/// assert_eq!(implode!(rest), "ons");
+/// assert_eq!(implode!([c, 'o']), "co");
+/// ```
+#[macro_export]
+macro_rules! implode {
+    ($chars:expr) => {
+        <$crate::__private::String as ::core::iter::FromIterator<char>>::from_iter($chars)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cons, list};
+
+    #[test]
+    fn test_round_trip() {
+        let s = String::from("héllo");
+        let chars = explode!(s);
+        assert_eq!(chars.len(), 5);
+        assert_eq!(implode!(chars), s);
+        assert_eq!(explode!(""), Vec::<char>::new());
+        assert_eq!(implode!(list!['o', 'k']), "ok");
+    }
+
+    #[test]
+    fn test_string_tail() {
+        cons!(("sml".chars()) as _::rest: String else { unreachable!() });
+        assert_eq!(rest, "ml");
+        cons!((explode!("ab")) as a::b::rest: String);
+        assert_eq!((a, b, rest.as_str()), ('a', 'b', ""));
+    }
+}
@@ -0,0 +1,86 @@
+/// Method-chaining versions of the simplest cons patterns, for when a
+/// macro would be overkill.
+///
+/// The methods are available on anything that implements
+/// [`IntoIterator`] once the trait is in scope. Each one takes the
+/// first few elements and returns them together with the rest of the
+/// iterator, or `None` if there weren't enough elements:
+///
+/// ```rust
+/// use cons::IterConsExt;
+///
+/// let (x, rest) = vec![1, 2, 3].uncons().unwrap();
+/// assert_eq!(x, 1);
+/// assert_eq!(rest.collect::<Vec<_>>(), vec![2, 3]);
+///
+/// let (x, y, _) = (1..).uncons2().unwrap();
+/// assert_eq!((x, y), (1, 2));
+///
+/// assert!([1, 2].uncons3().is_none());
+/// ```
+///
+/// These are equivalent to `cons!(v as x::rest @ iter)` and friends,
+/// so nothing past the bound elements is consumed.
+pub trait IterConsExt: IntoIterator + Sized {
+    /// Splits off the first element. This is `x::xs`.
+    fn uncons(self) -> Option<(Self::Item, Self::IntoIter)> {
+        let mut iter = self.into_iter();
+        let x = iter.next()?;
+        Some((x, iter))
+    }
+
+    /// Splits off the first two elements. This is `x::y::xs`.
+    fn uncons2(self) -> Option<(Self::Item, Self::Item, Self::IntoIter)> {
+        let mut iter = self.into_iter();
+        let x = iter.next()?;
+        let y = iter.next()?;
+        Some((x, y, iter))
+    }
+
+    /// Splits off the first three elements. This is `x::y::z::xs`.
+    #[allow(clippy::type_complexity)]
+    fn uncons3(self) -> Option<(Self::Item, Self::Item, Self::Item, Self::IntoIter)> {
+        let mut iter = self.into_iter();
+        let x = iter.next()?;
+        let y = iter.next()?;
+        let z = iter.next()?;
+        Some((x, y, z, iter))
+    }
+}
+
+impl<I: IntoIterator> IterConsExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::IterConsExt;
+
+    #[test]
+    fn test_uncons() {
+        let (x, rest) = vec![String::from("a"), String::from("b")].uncons().unwrap();
+        assert_eq!(x, "a");
+        assert_eq!(rest.collect::<Vec<_>>(), vec!["b"]);
+        assert!(Vec::<i32>::new().uncons().is_none());
+    }
+
+    #[test]
+    fn test_uncons_is_lazy() {
+        let mut pulled = 0;
+        let (x, y, mut rest) = (1..)
+            .inspect(|_| pulled += 1)
+            .uncons2()
+            .unwrap();
+        assert_eq!((x, y), (1, 2));
+        assert_eq!(rest.next(), Some(3));
+        drop(rest);
+        assert_eq!(pulled, 3);
+    }
+
+    #[test]
+    fn test_uncons3() {
+        let v = [1, 2, 3];
+        let (x, y, z, mut rest) = v.iter().uncons3().unwrap();
+        assert_eq!((x, y, z), (&1, &2, &3));
+        assert_eq!(rest.next(), None);
+        assert!(v[..2].iter().uncons3().is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use core::cell::Cell;
+use core::iter::FusedIterator;
+
+/// Yields the `Ok` values of an iterator over `Result`s, and stops at
+/// the first `Err`, which is kept for [`try_cons!`](crate::try_cons) to
+/// return once the pattern has been matched.
+#[doc(hidden)]
+pub struct TryItems<'a, I, E> {
+    iter: I,
+    error: &'a Cell<Option<E>>,
+    failed: bool,
+}
+
+impl<'a, I, T, E> TryItems<'a, I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    pub fn new(iter: I, error: &'a Cell<Option<E>>) -> Self {
+        Self {
+            iter,
+            error,
+            failed: false,
+        }
+    }
+
+    /// Gives back the underlying iterator, so that a tail bound with
+    /// `@ iter` still sees the errors after the heads.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<I, T, E> Iterator for TryItems<'_, I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.failed {
+            return None;
+        }
+        match self.iter.next()? {
+            Ok(x) => Some(x),
+            Err(e) => {
+                self.failed = true;
+                self.error.set(Some(e));
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            return (0, Some(0));
+        }
+        // any element could be an error, which ends the iterator early
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<I, T, E> FusedIterator for TryItems<'_, I, E> where I: Iterator<Item = Result<T, E>> {}
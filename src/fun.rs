@@ -0,0 +1,92 @@
+/// Defines a function by a list of clauses over cons patterns, like
+/// SML's `fun`.
+///
+/// The function is written as usual, except that it takes exactly one
+/// parameter and its body is a list of clauses of the form
+/// `name(pattern) => expression`. The clauses are tried in order, as
+/// if by [`cons_match!`](crate::cons_match), against the parameter.
+///
+/// # Examples
+///
+/// Compare the SML definition
+///
+/// ```sml
+/// fun len [] = 0
+///   | len (_::xs) = 1 + len xs
+/// ```
+///
+/// with
+///
+/// ```rust
+/// # use cons::fun;
+/// fun! {
+///     fn len<T>(list: Vec<T>) -> usize {
+///         len([]) => 0,
+///         len(_::xs) => 1 + len(xs),
+///     }
+/// }
+///
+/// assert_eq!(len(vec![1, 2, 3]), 3);
+/// assert_eq!(len::<i32>(vec![]), 0);
+/// ```
+///
+/// `[]` is SML's spelling of `nil`; both are accepted. Everything else
+/// that [`cons_match!`](crate::cons_match) supports, such as guards and
+/// refutable head patterns, works in the clauses too:
+///
+/// ```rust
+/// # use cons::fun;
+/// fun! {
+///     /// Sums the leading positive numbers.
+///     pub fn sum_positive(v: Vec<i32>) -> i32 {
+///         sum_positive((x if x > 0)::xs) => x + sum_positive(xs),
+///         sum_positive(_) => 0,
+///     }
+/// }
+///
+/// assert_eq!(sum_positive(vec![1, 2, -3, 4]), 3);
+/// ```
+#[macro_export]
+macro_rules! fun {
+    ($($function:tt)*) => {
+        $crate::__private::fun!($crate; $($function)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    fun! {
+        fn sum(v: Vec<i32>) -> i32 {
+            sum(nil) => 0,
+            sum(x::xs) => x + sum(xs),
+        }
+    }
+
+    fun! {
+        fn pairs<T>(v: Vec<T>) -> Vec<(String, String)>
+        where
+            T: Into<String>,
+        {
+            pairs(a::b::rest) => {
+                let mut out = vec![(a.into(), b.into())];
+                out.extend(pairs(rest));
+                out
+            },
+            pairs(_) => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recursive() {
+        assert_eq!(sum(vec![1, 2, 3, 4]), 10);
+        assert_eq!(sum(vec![]), 0);
+    }
+
+    #[test]
+    fn test_generic_signature() {
+        assert_eq!(
+            pairs(vec!["a", "b", "c"]),
+            vec![(String::from("a"), String::from("b"))]
+        );
+    }
+}
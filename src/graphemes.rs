@@ -0,0 +1,101 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::Uncons;
+
+/// A `&str` that is taken apart one grapheme cluster at a time, behind
+/// the `unicode` feature.
+///
+/// Splitting a string into `char`s breaks up what a reader sees as a
+/// single character, such as a letter with a combining accent or a
+/// flag. A grapheme cluster keeps those together, so that is usually
+/// what text processing wants. Each head is the cluster as a `&str`,
+/// and with `@ iter` the tail is the rest of the string, still as a
+/// `Graphemes`:
+///
+/// ```rust
+/// # use cons::{cons, Graphemes};
+/// let s = "e\u{301}te\u{301}";
+/// cons!((Graphemes::new(s)) as first::rest @ iter);
+/// assert_eq!(first, "e\u{301}");
+/// assert_eq!(rest.as_str(), "te\u{301}");
+///
+/// // `chars` would have split the accent off
+/// assert_eq!(s.chars().next(), Some('e'));
+/// ```
+///
+/// Like any other [`Uncons`] type, it works with the other macros and
+/// tails too:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::{cons, cons_match, Graphemes};
+/// cons!((Graphemes::new("🇫🇷🇩🇪!")) as flag::rest: String);
+/// assert_eq!((flag, rest.as_str()), ("🇫🇷", "🇩🇪!"));
+///
+/// let kind = cons_match!((Graphemes::new("👍🏽")) {
+///     "👍🏽"::nil => "thumbs up",
+///     _ => "something else",
+/// });
+/// assert_eq!(kind, "thumbs up");
+/// # }
+/// ```
+///
+/// The clusters are extended grapheme clusters, as defined by
+/// [Unicode Standard Annex #29](https://www.unicode.org/reports/tr29/).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Graphemes<'a> {
+    /// Wraps a string so that it is split into grapheme clusters.
+    pub fn new(s: &'a str) -> Self {
+        Self { rest: s }
+    }
+
+    /// Returns the part of the string that hasn't been split off yet.
+    pub fn as_str(&self) -> &'a str {
+        self.rest
+    }
+}
+
+impl<'a> From<&'a str> for Graphemes<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl<'a> Uncons for Graphemes<'a> {
+    type Head = &'a str;
+    type Rest = Self;
+
+    fn uncons(self) -> Option<(&'a str, Self)> {
+        let first = self.rest.graphemes(true).next()?;
+        Some((first, Self::new(&self.rest[first.len()..])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cons, try_cons, ConsError};
+
+    #[test]
+    fn test_clusters() {
+        let s = "a\r\nb\u{308}🏳️‍🌈";
+        cons!((Graphemes::new(s)) as a::crlf::b::flag::nil);
+        assert_eq!([a, crlf, b, flag], ["a", "\r\n", "b\u{308}", "🏳️‍🌈"]);
+
+        cons!((Graphemes::from(s)) as "a"::_::rest @ iter);
+        assert_eq!(rest.as_str(), "b\u{308}🏳️‍🌈");
+    }
+
+    #[test]
+    fn test_empty() {
+        let first = || -> Result<&str, ConsError> {
+            try_cons!((Graphemes::new("")) as g::_);
+            Ok(g)
+        };
+        assert!(matches!(first(), Err(ConsError::Exhausted { got: 0, .. })));
+    }
+}
@@ -0,0 +1,188 @@
+/// Tuples that can be split into their first field and a tuple of the
+/// rest, like a heterogeneous cons list.
+///
+/// It is implemented for tuples with up to 12 fields. The tail of a
+/// one-field tuple is `()`, so a tuple can be taken apart one field at
+/// a time, each with its own type:
+///
+/// ```rust
+/// use cons::TupleCons;
+///
+/// let (x, rest) = (1, "two", 3.0).uncons();
+/// assert_eq!(x, 1);
+/// assert_eq!(rest, ("two", 3.0));
+///
+/// let t = <(char, bool)>::cons('a', (true,));
+/// assert_eq!(t, ('a', true));
+/// ```
+///
+/// Since the head and tail are associated types, generic code can ask
+/// for a tuple that starts with a particular type, whatever comes after
+/// it:
+///
+/// ```rust
+/// use cons::TupleCons;
+///
+/// struct Config {
+///     verbose: bool,
+/// }
+///
+/// fn extract<T: TupleCons<Head = Config>>(args: T) -> (bool, T::Tail) {
+///     let (config, rest) = args.uncons();
+///     (config.verbose, rest)
+/// }
+///
+/// let (verbose, rest) = extract((Config { verbose: true }, "input.txt", 3));
+/// assert!(verbose);
+/// assert_eq!(rest, ("input.txt", 3));
+/// ```
+///
+/// [`hcons!`](crate::hcons) does the same with the pattern syntax of
+/// [`cons!`](crate::cons).
+pub trait TupleCons: Sized {
+    /// The type of the first field.
+    type Head;
+
+    /// A tuple of the rest of the fields.
+    type Tail;
+
+    /// Splits the tuple into its first field and the rest.
+    fn uncons(self) -> (Self::Head, Self::Tail);
+
+    /// Builds the tuple from its first field and the rest.
+    fn cons(head: Self::Head, tail: Self::Tail) -> Self;
+}
+
+macro_rules! impl_tuple_cons {
+    ($h:ident $hx:ident $(, $t:ident $tx:ident)*) => {
+        impl<$h, $($t),*> TupleCons for ($h, $($t,)*) {
+            type Head = $h;
+            type Tail = ($($t,)*);
+
+            fn uncons(self) -> (Self::Head, Self::Tail) {
+                let ($hx, $($tx,)*) = self;
+                ($hx, ($($tx,)*))
+            }
+
+            fn cons($hx: Self::Head, ($($tx,)*): Self::Tail) -> Self {
+                ($hx, $($tx,)*)
+            }
+        }
+    };
+}
+
+impl_tuple_cons!(A a);
+impl_tuple_cons!(A a, B b);
+impl_tuple_cons!(A a, B b, C c);
+impl_tuple_cons!(A a, B b, C c, D d);
+impl_tuple_cons!(A a, B b, C c, D d, E e);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g, H h);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g, H h, I i);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+impl_tuple_cons!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+
+/// A version of [`cons!`](crate::cons) for tuples, which splits off
+/// fields with [`TupleCons`] instead of elements of an iterator.
+///
+/// Each head can have a different type, and the tail is bound as a
+/// tuple of the remaining fields:
+///
+/// ```rust
+/// # use cons::hcons;
+/// let t = (1, "two", 3.0, '4');
+/// hcons!(t as x::y::rest);
+/// assert_eq!((x, y), (1, "two"));
+/// assert_eq!(rest, (3.0, '4'));
+/// ```
+///
+/// Heads can be destructured, and the pattern can end in `nil` or `_`
+/// like in `cons!`. Because the length of a tuple is part of its type,
+/// a pattern that doesn't fit the tuple is a type error rather than a
+/// panic:
+///
+/// ```rust
+/// # use cons::hcons;
+/// hcons!(((1, 'a'), "b") as ((n, c))::s::nil);
+/// assert_eq!((n, c, s), (1, 'a', "b"));
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::hcons;
+/// hcons!((1, 2, 3) as x::y::nil); // `nil` but there is a field left
+/// ```
+#[macro_export]
+macro_rules! hcons {
+    ($tuple:ident as $($pat:tt)+) => {
+        $crate::hcons!(@__ $tuple => $($pat)+);
+    };
+    (($tuple:expr) as $($pat:tt)+) => {
+        $crate::hcons!(@__ $tuple => $($pat)+);
+    };
+    (@__ $tuple:expr => nil) => {
+        let () = $tuple;
+    };
+    (@__ $tuple:expr => []) => {
+        let () = $tuple;
+    };
+    (@__ $tuple:expr => _) => {
+        let _ = $tuple;
+    };
+    (@__ $tuple:expr => $tl:ident) => {
+        let $tl = $tuple;
+    };
+    (@__ $tuple:expr => nil :: $($rest:tt)+) => {
+        $crate::__private::diagnose!(pattern nil :: $($rest)+);
+    };
+    (@__ $tuple:expr => $hd:tt :: $($rest:tt)+) => {
+        let (hd, tl) = $crate::TupleCons::uncons($tuple);
+        #[allow(unused_parens)]
+        let $hd = hd;
+        $crate::hcons!(@__ tl => $($rest)+);
+    };
+    (@__ $tuple:expr => $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front hcons [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TupleCons;
+
+    #[test]
+    fn test_round_trip() {
+        let t = (1, 'a', "b", 2.5, [0u8; 2], (), Some(3), 4u64, '5', 6i8, "7", 8);
+        let (h, rest) = t.uncons();
+        let u: (i32, _, _, _, _, _, _, _, _, _, _, _) = TupleCons::cons(h, rest);
+        assert_eq!(u, t);
+        assert_eq!((1,).uncons(), (1, ()));
+    }
+
+    #[test]
+    fn test_hcons() {
+        let t = (String::from("name"), 42, vec![1, 2]);
+        hcons!(t as name::_::rest);
+        assert_eq!(name, "name");
+        assert_eq!(rest, (vec![1, 2],));
+
+        hcons!((('x', 1), true) as ((c, _))::b::[]);
+        assert_eq!((c, b), ('x', true));
+    }
+
+    /// Builds up a tuple field by field, then reads it back.
+    fn push<T: TupleCons>(head: T::Head, tail: T::Tail) -> T {
+        T::cons(head, tail)
+    }
+
+    #[test]
+    fn test_builder() {
+        let t: (u8, &str, bool) = push(1, push("a", push(true, ())));
+        hcons!(t as n::s::b::nil);
+        assert_eq!((n, s, b), (1, "a", true));
+    }
+}
@@ -1,7 +1,398 @@
 /*!
 This crate attempts to bring the "cons" feature from SML into Rust.
-See the [`cons!`] macro for more information.
+See the [`cons!`] macro for more information, or [`IterConsExt`] for
+the same idea as plain methods.
+
+The crate also provides [`List`], a persistent SML-style cons list
+with O(1) `cons`, `head`, and `tail`, [`Stream`], its lazy
+counterpart for infinite lists, and [`Vect`], which tracks the length
+of a list in its type.
+
+# `no_std`
+
+The crate is `#![no_std]`. Binding a tail as a `Vec`, [`cons_match!`],
+[`cons_drain!`], [`assert_cons!`], [`explode!`], [`fun!`], [`List`],
+[`DList`], [`ChunkedList`], [`VecList`], [`MutList`], [`ConsArena`],
+[`Stream`], and [`Vect`] need an allocator and are only available with
+the `alloc` feature, which is enabled by default. Without it, tails
+have to be bound lazily with `@ iter` (or as a subslice with
+[`cons_slice!`]):
+
+```toml
+[dependencies]
+cons = { version = "0.1", default-features = false }
+```
+
+A [`StaticList`] built with [`static_list!`] lives entirely in
+`static` items, so it is available either way.
+
+The `std` feature, which is off by default, adds the hashed set
+operations on [`List`], such as `union_hashed`, and a conversion from
+[`ConsError`] to `std::io::Error`.
+
+# Optional integrations
+
+These features are off by default and each pull in the crate they
+are named after:
+
+- `serde`: `Serialize` and `Deserialize` for [`List`], as a sequence.
+- `smallvec`: binding a tail with `rest @ small<N>` collects it into a
+  `SmallVec` that keeps up to `N` elements inline, see [`cons!`].
+- `unicode`: `Graphemes`, which splits a `&str` into grapheme
+  clusters rather than `char`s.
+- `futures`: `async_cons!` and `StreamConsExt::uncons`, which split a
+  `Stream` into its first items and the rest of the stream.
+- `rayon`: parallel iterators over [`List`], `par_map` and
+  `par_filter`, and binding a tail with `rest @ par` as a parallel
+  iterator, see the `rayon` module.
+- `proptest` and `quickcheck`: `Arbitrary` for [`List`], shrinking
+  towards `nil` and towards smaller elements.
+- `rkyv`: zero-copy archiving for [`List`], read back as an
+  `ArchivedList` without deserializing, see the `rkyv` module.
+- `tracing`: [`cons!`] and the macros built on it emit `trace!`
+  events for each binding, how many elements were consumed and how
+  many were left, and why a pattern failed.
 */
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+// lets `#[derive(Uncons)]`, which refers to `::cons`, work in the tests
+#[cfg(test)]
+extern crate self as cons;
+
+#[cfg(feature = "alloc")]
+mod assert_cons;
+#[cfg(feature = "futures")]
+mod async_cons;
+mod cons_array;
+mod cons_bytes;
+#[cfg(feature = "alloc")]
+mod cons_drain;
+mod cons_lines;
+#[cfg(feature = "alloc")]
+mod cons_match;
+mod cons_pattern;
+mod cons_peek;
+mod cons_slice;
+mod debug_cons;
+mod error;
+mod ext;
+#[cfg(feature = "unicode")]
+mod graphemes;
+mod fallible;
+mod hcons;
+#[cfg(feature = "alloc")]
+mod explode;
+#[cfg(feature = "alloc")]
+mod fun;
+mod matches_cons;
+mod snoc;
+mod uncons;
+mod while_cons;
+pub mod array;
+pub mod static_list;
+#[cfg(feature = "alloc")]
+pub mod arena;
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+mod arbitrary;
+#[cfg(feature = "alloc")]
+pub mod chunked;
+#[cfg(feature = "alloc")]
+pub mod dlist;
+#[cfg(feature = "alloc")]
+pub mod list;
+#[cfg(feature = "alloc")]
+pub mod list_pair;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+#[cfg(feature = "rkyv")]
+pub mod rkyv;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "alloc")]
+pub mod mut_list;
+#[cfg(feature = "alloc")]
+pub mod stream;
+#[cfg(feature = "alloc")]
+pub mod vec_list;
+#[cfg(feature = "alloc")]
+pub mod vect;
+
+#[cfg(feature = "futures")]
+pub use async_cons::{StreamConsExt, UnconsFuture};
+pub use error::ConsError;
+#[cfg(feature = "alloc")]
+pub use error::ContextError;
+pub use ext::IterConsExt;
+#[cfg(feature = "unicode")]
+pub use graphemes::Graphemes;
+pub use hcons::TupleCons;
+pub use static_list::StaticList;
+pub use uncons::Uncons;
+pub use cons_macros::Uncons;
+/// Allows cons patterns in place of a function's parameter names.
+///
+/// Each parameter whose pattern is a cons pattern is taken apart with
+/// [`cons!`] at the top of the function body, so the function can use
+/// the bindings directly:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// use cons::cons_fn;
+///
+/// #[cons_fn]
+/// fn sum(x::xs: Vec<i32>) -> i32 {
+///     x + xs.iter().sum::<i32>()
+/// }
+///
+/// assert_eq!(sum(vec![1, 2, 3]), 6);
+/// # }
+/// ```
+///
+/// The patterns have to be valid Rust parameter patterns as well, so
+/// they can only use names, as in `x::y::rest` or `x::nil`; a name
+/// starting with `_`, such as `_rest`, stands in for `_`. For literals,
+/// guards, or destructuring, use [`cons!`] in the body instead.
+///
+/// Without arguments, the function panics like [`cons!`] when an
+/// argument doesn't fit its pattern. `#[cons_fn(try)]` uses
+/// [`try_cons!`] instead, so the function returns an error that
+/// converts from [`ConsError`], and `#[cons_fn(else { ... })]` runs
+/// the block, which has to diverge:
+///
+/// ```rust
+/// # use cons::{cons_fn, ConsError};
+/// #[cons_fn(try)]
+/// fn first_two(a::b::_rest: Vec<&str>) -> Result<String, ConsError> {
+///     Ok(format!("{a}{b}"))
+/// }
+///
+/// #[cons_fn(else { return None })]
+/// fn second(_first::x::_rest: &[u8]) -> Option<u8> {
+///     Some(*x)
+/// }
+///
+/// assert_eq!(first_two(vec!["a", "b", "c"]).unwrap(), "ab");
+/// assert!(first_two(vec!["a"]).is_err());
+/// assert_eq!(second(b"hi"), Some(b'i'));
+/// assert_eq!(second(b"h"), None);
+/// ```
+pub use cons_macros::cons_fn;
+#[cfg(feature = "alloc")]
+pub use arena::ConsArena;
+#[cfg(feature = "alloc")]
+pub use chunked::ChunkedList;
+#[cfg(feature = "alloc")]
+pub use dlist::DList;
+#[cfg(feature = "alloc")]
+pub use list::List;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use list::SyncList;
+#[cfg(feature = "alloc")]
+pub use mut_list::MutList;
+#[cfg(feature = "alloc")]
+pub use stream::Stream;
+#[cfg(feature = "alloc")]
+pub use vec_list::VecList;
+#[cfg(feature = "alloc")]
+pub use vect::Vect;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use crate::assert_cons::{Consumed, Recorded};
+    pub use crate::cons_array::ArrayLen;
+    pub use crate::fallible::TryItems;
+    pub use crate::uncons::{
+        Dispatch, Elements, KnownLen, Len, UnknownLen, ViaIntoIterator, ViaUncons,
+    };
+    #[cfg(feature = "alloc")]
+    pub use crate::cons_drain::DrainFront;
+    #[cfg(feature = "alloc")]
+    pub use crate::cons_match::{DerefEqLit, EqLit, Lit};
+    #[cfg(feature = "alloc")]
+    pub use alloc::{collections::VecDeque, format, string::String, vec::Vec};
+    #[cfg(feature = "smallvec")]
+    pub use smallvec::SmallVec;
+    #[cfg(feature = "rayon")]
+    pub use rayon::iter::IntoParallelIterator;
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+    #[cfg(feature = "alloc")]
+    pub use cons_macros::{check_arms, fun};
+    pub use cons_macros::{check_bindings, diagnose, expand_alias, print_bindings};
+
+    /// Collects an iterator into a `Vec` in `alloc`, since `Vec<T, A>`
+    /// only implements `FromIterator` for the global allocator.
+    #[cfg(feature = "allocator_api")]
+    pub fn collect_in<I, A>(iter: I, alloc: A) -> Vec<I::Item, A>
+    where
+        I: IntoIterator,
+        A: alloc::alloc::Allocator,
+    {
+        let mut v = Vec::new_in(alloc);
+        v.extend(iter);
+        v
+    }
+}
+
+/// Binds the tail of a cons pattern as a `Vec`.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail {
+    ($iter:expr, $tl:ident) => {
+        <$crate::__private::Vec<_> as ::core::iter::FromIterator<_>>::from_iter($iter)
+    };
+}
+
+/// Binds the tail of a cons pattern as a `Vec` in the given allocator.
+#[cfg(feature = "allocator_api")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_in {
+    ($iter:expr, $tl:ident, $alloc:expr) => {
+        $crate::__private::collect_in($iter, $alloc)
+    };
+}
+
+#[cfg(not(feature = "allocator_api"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_in {
+    ($iter:expr, $tl:ident, $alloc:expr) => {
+        ::core::compile_error!(::core::concat!(
+            "binding the tail `",
+            ::core::stringify!($tl),
+            " in ...` requires the nightly-only `allocator_api` feature",
+        ))
+    };
+}
+
+/// Binds the tail of a cons pattern as a `SmallVec` with `N` elements
+/// inline.
+#[cfg(feature = "smallvec")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_small {
+    ($iter:expr, $tl:ident, $n:tt) => {
+        <$crate::__private::SmallVec<[_; $n]> as ::core::iter::FromIterator<_>>::from_iter($iter)
+    };
+}
+
+#[cfg(not(feature = "smallvec"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_small {
+    ($iter:expr, $tl:ident, $n:tt) => {
+        ::core::compile_error!(::core::concat!(
+            "binding the tail `",
+            ::core::stringify!($tl),
+            " @ small<",
+            ::core::stringify!($n),
+            ">` requires the `smallvec` feature",
+        ))
+    };
+}
+
+/// Binds the tail of a cons pattern as a rayon parallel iterator.
+#[cfg(feature = "rayon")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_par {
+    ($iter:expr, $tl:ident) => {
+        $crate::__private::IntoParallelIterator::into_par_iter(
+            <$crate::__private::Vec<_> as ::core::iter::FromIterator<_>>::from_iter($iter),
+        )
+    };
+}
+
+#[cfg(not(feature = "rayon"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail_par {
+    ($iter:expr, $tl:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "binding the tail `",
+            ::core::stringify!($tl),
+            " @ par` requires the `rayon` feature",
+        ))
+    };
+}
+
+/// Emits a `trace!` event for a step of a cons pattern.
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    (head $name:tt) => {
+        $crate::__private::tracing::trace!(binding = ::core::stringify!($name), "cons: bound head")
+    };
+    (tail $tl:ident $(, $remaining:expr)?) => {
+        $crate::__private::tracing::trace!(
+            binding = ::core::stringify!($tl),
+            $(remaining = $remaining,)?
+            "cons: bound tail",
+        )
+    };
+    (nil $remaining:ident) => {
+        $crate::__private::tracing::trace!(remaining = $remaining, "cons: reached nil")
+    };
+    (failed $($reason:tt)+) => {
+        $crate::__private::tracing::trace!(
+            reason = %$crate::cons!(@__message $($reason)+),
+            "cons: pattern failed",
+        )
+    };
+    (matched $($pat:tt)+) => {
+        $crate::__private::tracing::trace!(
+            pattern = ::core::stringify!($($pat)+),
+            consumed = $crate::__trace!(@heads [] $($pat)+),
+            "cons: pattern matched",
+        )
+    };
+    // counts the `::`s before the tail, stopping at a type ascription
+    (@heads [$($n:tt)*] :: $($rest:tt)*) => {
+        $crate::__trace!(@heads [$($n)* 1] $($rest)*)
+    };
+    (@heads [$($n:tt)*] : $($rest:tt)*) => {
+        $crate::__trace!(@heads [$($n)*])
+    };
+    (@heads [$($n:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__trace!(@heads [$($n)*] $($rest)*)
+    };
+    (@heads [$($n:tt)*]) => {
+        0usize $(+ $n)*
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace {
+    ($($step:tt)*) => {};
+}
+
+/// Without `alloc` there is nothing to collect the tail into, so point
+/// the user at the lazy alternative.
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __collect_tail {
+    ($iter:expr, $tl:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "binding the tail `",
+            ::core::stringify!($tl),
+            "` as a `Vec` requires the `alloc` feature; use `",
+            ::core::stringify!($tl),
+            " @ iter` to bind the rest of the iterator instead",
+        ))
+    };
+}
 
 /// Brings the "cons" feature from SML into Rust. I'm not the most
 /// experienced with SML so this most likely will not be feature
@@ -14,22 +405,43 @@ See the [`cons!`] macro for more information.
 /// in the current scope. For example:
 ///
 /// ```rust
+/// # #[cfg(feature = "alloc")] {
 /// # use cons::cons;
 /// let v = [1, 2, 3];
 /// cons!(v as x::xs);
 /// assert_eq!(x, 1);
 /// assert_eq!(xs, vec![2, 3]);
+/// # }
+/// ```
+///
+/// You can even use iterators directly in the macro invocation:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = [1, 2, 3];
+/// cons!(v.iter().rev() as x::xs);
+/// assert_eq!(x, &3);
+/// assert_eq!(xs, vec![&2, &1]);
+/// # }
 /// ```
 ///
-/// You can even use iterators directly in the macro invocation;
-/// however, in order to match the syntax to SML as closely as
-/// possible, you must wrap the iterator in parentheses:
+/// Anything else that implements [`Uncons`] can be taken apart the same
+/// way, so custom containers and parsers can be used in place of an
+/// iterator.
+///
+/// Everything before the first `as` is taken as the iterator, so an
+/// expression that contains `as` itself, such as a cast, has to be
+/// wrapped in parentheses. Parentheses are always allowed:
 ///
 /// ```rust
+/// # #[cfg(feature = "alloc")] {
 /// # use cons::cons;
-/// cons!((1..=3) as x::xs);
+/// let n = 3u8;
+/// cons!((1..=n as u32) as x::xs);
 /// assert_eq!(x, 1);
 /// assert_eq!(xs, vec![2, 3]);
+/// # }
 /// ```
 ///
 /// You can also exhaust (i.e. bind every element of) the iterator:
@@ -49,12 +461,14 @@ See the [`cons!`] macro for more information.
 /// Notice the `nil` at the end of the cons pattern. This special
 /// case is used to indicate the end of the iterator and bind the
 /// last element rather than a vector of the remaining elements.
-/// See the _Panics_ section for how this can go wrong.
+/// See the _Panics_ section for how this can go wrong. As in SML,
+/// `[]` can be used instead of `nil`.
 ///
 /// So far, the examples have only shown slices with elements that
 /// implement `Copy`. This is not a requirement:
 ///
 /// ```rust
+/// # #[cfg(feature = "alloc")] {
 /// # use cons::cons;
 /// let v = vec![
 ///    String::from("hello"),
@@ -66,6 +480,7 @@ See the [`cons!`] macro for more information.
 /// assert_eq!(x, "hello");
 /// assert_eq!(y, "to");
 /// assert_eq!(zs, vec!["the", "world"]);
+/// # }
 /// ```
 ///
 /// In these cases, the elements are borrowed from the iterator
@@ -73,10 +488,98 @@ See the [`cons!`] macro for more information.
 /// `String`s, but `x` and `y` are `&str` slices and `zs` is
 /// a `Vec<&str>`.
 ///
+/// The tail is a `Vec` by default. To collect it into some other
+/// collection instead, give its type after the name, as in a `let`:
+///
+/// ```rust
+/// # use cons::cons;
+/// use std::collections::BTreeSet;
+///
+/// let v = [3, 1, 2, 1];
+/// cons!(v as x::rest: BTreeSet<_>);
+/// assert_eq!(x, 3);
+/// assert_eq!(rest, BTreeSet::from([1, 2]));
+/// ```
+///
+/// Any type that implements [`FromIterator`] works, including ones that
+/// don't need an allocator. Matching a string's characters, the tail
+/// can be a `String`, as in `cons!((s.chars()) as c::rest: String)`;
+/// [`explode!`] and [`implode!`] convert between the two forms.
+///
+/// Writing the tail as `(rest, n)` also binds `n` to the number of
+/// elements in it, counted while it is collected. The tail can still
+/// be given a type, as in `(rest: String, n)`:
+///
+/// ```rust
+/// # use cons::cons;
+/// cons!(("héllo".chars()) as h::(rest: String, n));
+/// assert_eq!((h, rest.as_str(), n), ('h', "éllo", 4));
+/// ```
+///
+/// A length in brackets after the tail checks how many elements it
+/// got, the same way a too-short iterator is reported. `xs[3]` needs
+/// exactly three, and `xs[>= 2]`, `xs[> 2]`, `xs[<= 2]` and `xs[< 2]`
+/// compare:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = ["mv", "a.txt", "b.txt", "dir/"];
+/// cons!(v as cmd::files[>= 2]);
+/// assert_eq!((cmd, files.len()), ("mv", 3));
+/// # }
+/// ```
+///
+/// With the nightly-only `allocator_api` feature, `rest in alloc`
+/// collects the tail into a `Vec` in the allocator `alloc` instead of
+/// the global one. A [`List`] can be kept in a custom allocator with
+/// `list::ListIn`, which also works as a tail type.
+///
+/// With the `rayon` feature, `rest @ par` binds the tail as an indexed
+/// rayon parallel iterator, so the work on a large tail can be spread
+/// over threads. The elements keep their order when collected:
+///
+/// ```rust
+/// # #[cfg(feature = "rayon")] {
+/// # use cons::cons;
+/// use rayon::prelude::*;
+///
+/// let rows = vec!["id", "1", "2", "3"];
+/// cons!(rows as header::rest @ par);
+/// let ids: Vec<u32> = rest.map(|row| row.parse().unwrap()).collect();
+/// assert_eq!((header, ids), ("id", vec![1, 2, 3]));
+/// # }
+/// ```
+///
+/// With the `smallvec` feature, `rest @ small<N>` collects the tail
+/// into a `SmallVec<[T; N]>`, which only allocates once there are more
+/// than `N` elements. This avoids allocator churn on hot paths where
+/// tails are usually short:
+///
+/// ```rust
+/// # #[cfg(feature = "smallvec")] {
+/// # use cons::cons;
+/// cons!(("GET /index.html HTTP/1.1".split(' ')) as method::rest @ small<4>);
+/// assert_eq!(method, "GET");
+/// assert_eq!(rest.as_slice(), ["/index.html", "HTTP/1.1"]);
+/// assert!(!rest.spilled());
+/// # }
+/// ```
+///
+/// If the input is a slice, [`cons_slice!`] binds the tail as a
+/// subslice instead, which avoids allocating a `Vec` altogether, and
+/// [`cons_bytes!`] does the same for binary data. If it
+/// is an array, [`cons_array!`] checks the pattern against its length
+/// at compile time. To move the first few elements out of a `Vec`
+/// while leaving the rest of it in place, see [`cons_drain!`], and to
+/// split the lines of a reader into a header and the rest, see
+/// [`cons_lines!`].
+///
 /// The macro also supports destructuring by wrapping the pattern
 /// in parentheses:
 ///
 /// ```rust
+/// # #[cfg(feature = "alloc")] {
 /// # use cons::cons;
 /// let tuples = [(1, 2), (3, 4), (5, 6)];
 /// cons!(tuples as ((x, y))::zs);
@@ -92,8 +595,217 @@ See the [`cons!`] macro for more information.
 /// assert_eq!(x, 1);
 /// assert_eq!(y, 2);
 /// assert_eq!(zs, vec![Point(3, 4), Point(5, 6)]);
+/// # }
+/// ```
+///
+/// Elements you don't care about can be skipped with `_`. At the
+/// end of the pattern, `_` drops the rest of the iterator instead of
+/// collecting it into a vector:
+///
+/// ```rust
+/// # use cons::cons;
+/// cons!((1..) as _::x::_::y::_);
+/// assert_eq!(x, 2);
+/// assert_eq!(y, 4);
+/// ```
+///
+/// If you need the tail but don't want to pay for collecting it, add
+/// `@ iter` after the tail binding. The rest of the iterator is then
+/// bound as-is (and mutably), without consuming anything else:
+///
+/// ```rust
+/// # use cons::cons;
+/// cons!((1..) as x::y::rest @ iter);
+/// assert_eq!(x, 1);
+/// assert_eq!(y, 2);
+/// assert_eq!(rest.next(), Some(3));
+/// assert_eq!(rest.take(2).collect::<Vec<_>>(), vec![4, 5]);
+/// ```
+///
+/// `@ ..` is another way to write `@ iter`, which reads like the rest
+/// pattern of a slice. Either way the iterator can be passed on to
+/// other code or have adapters chained onto it:
+///
+/// ```rust
+/// # use cons::cons;
+/// fn sum(numbers: impl Iterator<Item = u32>) -> u32 {
+///     numbers.sum()
+/// }
+///
+/// let line = "add 1 2 3";
+/// cons!((line.split(' ')) as "add"::args @ ..);
+/// assert_eq!(sum(args.map(|n| n.parse::<u32>().unwrap())), 6);
+/// ```
+///
+/// Head patterns don't have to be irrefutable. Literals and other
+/// refutable patterns are checked against the element, just like in
+/// SML:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = [1, 2, 3];
+/// cons!(v as 1::rest);
+/// assert_eq!(rest, vec![2, 3]);
+///
+/// let args = ["add", "x", "y"];
+/// cons!(args as "add"::a::b::[]);
+/// assert_eq!((a, b), ("x", "y"));
+/// # }
+/// ```
+///
+/// Two iterators can be consed in parallel, like SML's `ListPair`, by
+/// giving both of them in parentheses. Each head is then a pair of
+/// patterns, one for each iterator, and the tail is the rest of the two
+/// zipped together:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let names = ["a", "b", "c"];
+/// let ages = [1, 2, 3];
+/// cons!((names, ages) as (n, a)::rest);
+/// assert_eq!((n, a), ("a", 1));
+/// assert_eq!(rest, vec![("b", 2), ("c", 3)]);
+/// # }
+/// ```
+///
+/// If one side runs out before the other, the panic names the variable
+/// on that side. A message and an `else` block can follow the pattern
+/// as usual, and [`try_cons!`] counts the pairs taken before the
+/// failure in its error.
+///
+/// Ending the pattern with `; enumerate` pairs each element with its
+/// index, as [`Iterator::enumerate`] does. Heads can then be written as
+/// `(i, x)`, and the tail is a `Vec` of `(index, element)` pairs:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = ["a", "b", "c"];
+/// cons!(v as (i, x)::_::rest; enumerate);
+/// assert_eq!((i, x), (0, "a"));
+/// assert_eq!(rest, vec![(2, "c")]);
+/// # }
+/// ```
+///
+/// Marking the final segment with `!` binds it to the *last* element
+/// instead, and the segment before it to everything in between, like
+/// Python's `first, *mid, last = seq`. This needs a
+/// [`DoubleEndedIterator`]:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = ["<", "a", "b", ">"];
+/// cons!(v as open::mid::close!);
+/// assert_eq!((open, close), ("<", ">"));
+/// assert_eq!(mid, vec!["a", "b"]);
+/// # }
+/// ```
+///
+/// A head pattern can be followed by a guard, like a `match` arm. The
+/// guard is checked after the element is bound:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = [3, 1, 2];
+/// cons!(v as (x if x > 2)::rest);
+/// assert_eq!(x, 3);
+/// assert_eq!(rest, vec![1, 2]);
+/// # }
+/// ```
+///
+/// A head that is just a name can be given a type, as in a `let`
+/// statement, which helps when the element type would otherwise have to
+/// be inferred from how it is used later:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let words = "7 11 13".split(' ').map(|w| w.parse().unwrap());
+/// cons!(words as (x: u64)::rest);
+/// assert_eq!(x.pow(2), 49);
+/// assert_eq!(rest, vec![11, 13]);
+/// # }
+/// ```
+///
+/// A head written as `(name = default)` takes the value of `default`
+/// if the iterator has already run out, instead of failing. This is
+/// handy for optional trailing arguments:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let args = "make".split(' ');
+/// cons!(args as cmd::(target = "all")::(jobs = "1")::rest);
+/// assert_eq!((cmd, target, jobs), ("make", "all", "1"));
+/// assert!(rest.is_empty());
+/// # }
+/// ```
+///
+/// The default is only evaluated when it is needed, and can refer to
+/// names bound earlier in the pattern.
+///
+/// A head can be bound as a whole while also being destructured, with
+/// `@` as in any other Rust pattern. Errors then refer to it by name:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// #[derive(Debug, PartialEq)]
+/// enum Kind { Error, Warning }
+/// struct Message { kind: Kind, text: &'static str }
+///
+/// let msgs = vec![
+///     Message { kind: Kind::Error, text: "oops" },
+///     Message { kind: Kind::Warning, text: "hmm" },
+/// ];
+/// cons!(msgs as (m @ Message { kind: Kind::Error, .. })::rest);
+/// assert_eq!(m.text, "oops");
+/// assert_eq!(rest[0].kind, Kind::Warning);
+/// # }
+/// ```
+///
+/// Names can be declared `mut`, and heads can also be bound with `ref`
+/// or `ref mut`, just like in a `let` statement:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let v = vec![String::from("a"), String::from("b"), String::from("c")];
+/// cons!(v as mut first::ref second::mut rest);
+/// first.push('!');
+/// rest.push(second.clone());
+/// assert_eq!((first.as_str(), second.as_str()), ("a!", "b"));
+/// assert_eq!(rest, ["c", "b"]);
+/// # }
 /// ```
 ///
+/// Writing `=>` instead of `as` assigns to variables that already
+/// exist, rather than declaring new ones that shadow them. This is
+/// handy for state that is carried around a loop:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons;
+/// let mut op = "";
+/// let mut queue = vec!["push", "push", "pop"];
+/// let mut depth = 0;
+/// while !queue.is_empty() {
+///     cons!(queue => op::queue);
+///     depth += if op == "push" { 1 } else { -1 };
+/// }
+/// assert_eq!((op, depth), ("pop", 1));
+/// # }
+/// ```
+///
+/// Since the names are assigned to, heads can only be names, `_`, or
+/// literals, and the tail a name (optionally with `@ iter`), `_`, or
+/// `nil`. An `else` block works as usual. The variables can also be
+/// declared with `let x;` and given their first value this way.
+///
 /// # Panics
 ///
 /// If there are not enough elements in the iterator to match the
@@ -103,9 +815,17 @@ See the [`cons!`] macro for more information.
 /// ```rust,should_panic
 /// # use cons::cons;
 /// let v = [1];
-/// cons!(v as x::y::zs); // panics, iter not long enough
+/// cons!(v as x::y::_); // panics, iter not long enough
 /// ```
 ///
+/// When the iterator knows its exact length up front, as a `Vec`,
+/// array, or slice iterator does, the length is checked before
+/// anything is taken from it, so the message says how many elements
+/// the pattern needs ("the pattern needs at least 3 elements, got 1")
+/// and no head pattern or guard is run on a value that is too short.
+/// The same check lets the tail be collected into a `Vec` that is
+/// allocated at the right size.
+///
 /// If `nil` is at the end of the pattern and there are still elements
 /// left in the iterator, the macro will panic with a message indicating
 /// what elements were left:
@@ -115,67 +835,962 @@ See the [`cons!`] macro for more information.
 /// let v = [1, 2];
 /// cons!(v as x::nil); // panics, iter still has an element
 /// ```
+///
+/// If a literal or other refutable head pattern doesn't match, or a
+/// guard is not satisfied, the macro panics with a message
+/// showing the pattern that failed:
+///
+/// ```rust,should_panic
+/// # use cons::cons;
+/// let v = [1, 2];
+/// cons!(v as (x if x > 1)::_); // panics, 1 is not greater than 1
+/// ```
+///
+/// Like `let ... else`, an `else` block can be given to run instead
+/// of panicking. It must diverge, e.g. by returning or breaking:
+///
+/// ```rust
+/// # use cons::cons;
+/// fn second(v: &[i32]) -> Option<i32> {
+///     cons!((v.iter().copied()) as _::x::_ else { return None });
+///     Some(x)
+/// }
+///
+/// assert_eq!(second(&[1, 2, 3]), Some(2));
+/// assert_eq!(second(&[1]), None);
+/// ```
+///
+/// The panic is reported at the line of the `cons!` call, not inside
+/// the macro. For more context, a message can follow the pattern, with
+/// arguments as in [`format_args!`]. It is put in front of
+/// the usual message:
+///
+/// ```rust,should_panic
+/// # use cons::cons;
+/// let path = "Cargo.toml";
+/// let lines = vec!["[package]"];
+/// cons!(lines as header::name::_, "while parsing {}", path);
+/// // panics with "while parsing Cargo.toml: Iterator exhausted before
+/// // reaching variable name (the pattern needs at least 2 elements, got 1)"
+/// ```
+///
+/// To handle running out of elements some other way, add
+/// `; on_missing = handler` after the pattern. When the iterator is
+/// exhausted before a named head, `handler` is called with the head's
+/// name, and whatever it returns is bound instead, as with a
+/// `(name = default)` head. It can log, count, or build a value:
+///
+/// ```rust
+/// # use cons::cons;
+/// let mut missing = vec![];
+/// let v = vec![10];
+/// cons!(v as x::y::z::_; on_missing = |name| {
+///     missing.push(name);
+///     0
+/// });
+/// assert_eq!((x, y, z), (10, 0, 0));
+/// assert_eq!(missing, ["y", "z"]);
+/// ```
+///
+/// Only plain names get the handler; other heads and `nil` still
+/// panic as above.
+///
+/// # Errors
+///
+/// Malformed patterns are reported at compile time, pointing at the
+/// offending token. For example, destructuring patterns must be
+/// wrapped in parentheses:
+///
+/// ```rust,compile_fail
+/// # use cons::cons;
+/// # struct Point(i32, i32);
+/// let points = [Point(1, 2), Point(3, 4)];
+/// cons!(points as Point(x, y)::zs);
+/// // error: destructuring patterns must be wrapped in parentheses: `(Point(x, y))`
+/// ```
+///
+/// Binding the same name twice is an error too, rather than quietly
+/// shadowing the first one:
+///
+/// ```rust,compile_fail
+/// # use cons::cons;
+/// let v = vec![1, 2, 3];
+/// cons!(v as x::x::rest);
+/// // error: identifier `x` is bound more than once in the same cons pattern
+/// ```
+///
+/// The alternatives of an or-pattern, as in `(Ok(x) | Err(x))`, each
+/// bind the same names, as Rust requires, so they don't count as
+/// binding them twice.
+///
+/// To borrow the elements instead of consuming the collection, see
+/// [`cons_ref!`] and [`cons_mut!`].
+///
+/// See [`try_cons!`] for a version that returns an error instead,
+/// [`cons_match!`] to try several patterns in turn, or [`snoc!`] to
+/// bind elements from the back. Fragments of patterns that come up
+/// again and again can be given a name with [`cons_pattern!`].
 #[macro_export]
 macro_rules! cons {
+    (($a:expr, $b:expr) as $($rest:tt)+) => {
+        $crate::cons!(@__zip_else ($a, $b) => [] $($rest)+);
+    };
     ($iter:ident as $($rest:tt)+) => {
-        $crate::cons!(@__ $iter => $($rest)+);
+        $crate::cons!(@__else $iter => [] $($rest)+);
     };
     (($iter:expr) as $($rest:tt)+) => {
-        $crate::cons!(@__ $iter => $($rest)+);
+        $crate::cons!(@__else $iter => [] $($rest)+);
     };
-    (@__ $iter:expr => $hd:ident :: nil) => {
-        $crate::cons!(@__ $iter => ($hd)::nil);
+    ($iter:ident => $($rest:tt)+) => {
+        $crate::cons!(@__assign $iter => [] [] $($rest)+);
     };
-    (@__ $iter:expr => ($hd:pat) :: nil) => {
-        let mut iter = $iter.into_iter();
-        let $hd = iter.next().unwrap_or_else(|| {
-            panic!("Iterator exhausted before reaching variable {}", stringify!($hd));
-        });
-        {
-            let rest = iter.count();
-            assert_eq!(rest, 0, "Found `nil` in cons but iterator is not empty ({rest} elements left)\nConsider removing `::nil`");
-        }
+    (($iter:expr) => $($rest:tt)+) => {
+        $crate::cons!(@__assign $iter => [] [] $($rest)+);
     };
-    (@__ $iter:expr => $hd:ident) => {
-        let iter = $iter.into_iter();
-        let $hd = iter.collect::<Vec<_>>();
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__assign $iter => [$($pat)* $hd ::] [$($name)* $hd] $($rest)+);
     };
-    (@__ $iter:expr => $hd:ident :: $($rest:tt)+) => {
-        $crate::cons!(@__ $iter => ($hd) :: $($rest)+);
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] _ :: $($rest:tt)+) => {
+        $crate::cons!(@__assign $iter => [$($pat)* _ ::] [$($name)*] $($rest)+);
     };
-    (@__ $iter:expr => ($hd:pat) :: $($rest:tt)+) => {
-        let mut iter = $iter.into_iter();
-        let $hd = iter.next().unwrap_or_else(|| {
-            panic!("Iterator exhausted before reaching variable {}", stringify!($hd));
-        });
-        $crate::cons!(@__ iter => $($rest)+);
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] $hd:literal :: $($rest:tt)+) => {
+        $crate::cons!(@__assign $iter => [$($pat)* $hd ::] [$($name)*] $($rest)+);
     };
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_get_head() {
-        let v = [1, 2, 3];
-        let expected = (1, vec![2, 3]);
-        cons!(v as x::xs);
-        assert_eq!(x, expected.0);
-        assert_eq!(xs, expected.1);
-    }
-
-    #[test]
-    fn test_triple_cons() {
-        let v = [1, 2, 3, 4, 5];
-        let expected = (1, 2, vec![3, 4, 5]);
-        cons!(v as x::y::zs);
-        assert_eq!(x, expected.0);
-        assert_eq!(y, expected.1);
-        assert_eq!(zs, expected.2);
-    }
-
-    #[test]
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] nil $(else $else:block)?) => {
+        $crate::cons!(@__assign_ $iter => [$($pat)* nil] [$($name)*] $(else $else)?);
+    };
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] [] $(else $else:block)?) => {
+        $crate::cons!(@__assign_ $iter => [$($pat)* nil] [$($name)*] $(else $else)?);
+    };
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] _ $(else $else:block)?) => {
+        $crate::cons!(@__assign_ $iter => [$($pat)* _] [$($name)*] $(else $else)?);
+    };
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] $tl:ident $(@ $iter_kw:ident)? $(else $else:block)?) => {
+        $crate::cons!(@__assign_ $iter => [$($pat)* $tl $(@ $iter_kw)?] [$($name)* $tl] $(else $else)?);
+    };
+    (@__assign $iter:expr => [$($pat:tt)*] [$($name:ident)*] $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "`=>` can only assign to names; expected a name, `_`, or a literal followed by `::`, ",
+            "or a name, `_`, or `nil` at the end, found `",
+            ::core::stringify!($($rest)*),
+            "`",
+        ));
+    };
+    (@__assign_ $iter:expr => [$($pat:tt)+] [$($name:ident)*] $(else $else:block)?) => {
+        // the pattern declares its names inside the block, where they
+        // shadow the outer ones, and are then assigned to the outer ones
+        ($($name,)*) = {
+            $crate::cons!(@__else $iter => [] $($pat)+ $(else $else)?);
+            ($($name,)*)
+        };
+    };
+    (@__else $iter:expr => [$($pat:tt)+] ; enumerate else $else:block) => {
+        $crate::cons!(@__start [else $else] ($crate::cons!(@__enumerate $iter)) => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] ; enumerate) => {
+        $crate::cons!(@__start [panic] ($crate::cons!(@__enumerate $iter)) => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] , $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::cons!(@__start [panic_with ($fmt $(, $arg)*)] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] ; on_missing = $handler:expr) => {
+        #[allow(unused_mut)]
+        let mut on_missing = $handler;
+        $crate::cons!(@__on_missing on_missing $iter => [] $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::cons!(@__start [else $else] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+]) => {
+        $crate::cons!(@__start [panic] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__else $iter => [$($pat)* $next] $($rest)*);
+    };
+    // gives every named head a default that calls the handler `$f`
+    (@__on_missing $f:ident $iter:expr => [$($pat:tt)*] $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__on_missing $f $iter => [$($pat)* ($hd = $f(::core::stringify!($hd))) ::] $($rest)+);
+    };
+    (@__on_missing $f:ident $iter:expr => [$($pat:tt)*] $kw:ident $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__on_missing $f $iter => [$($pat)* $kw $hd ::] $($rest)+);
+    };
+    (@__on_missing $f:ident $iter:expr => [$($pat:tt)*] @ $kw:tt :: $($rest:tt)+) => {
+        $crate::cons!(@__on_missing $f $iter => [$($pat)* @ $kw ::] $($rest)+);
+    };
+    (@__on_missing $f:ident $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__on_missing $f $iter => [$($pat)* $next] $($rest)*);
+    };
+    (@__on_missing $f:ident $iter:expr => [$($pat:tt)+]) => {
+        $crate::cons!(@__start [panic] $iter => $($pat)+);
+    };
+    (@__start $mode:tt $iter:expr => $($pat:tt)+) => {
+        $crate::__private::check_bindings!($($pat)+);
+        let iter = $crate::cons!(@__dispatch __cons_start $iter);
+        $crate::cons!(@__check $mode iter [] $($pat)+);
+        $crate::cons!(@__ $mode iter => $($pat)+);
+        $crate::__trace!(matched $($pat)+);
+    };
+    (@__ $mode:tt $iter:expr => nil) => {
+        let rest = ::core::iter::Iterator::count($crate::cons!(@__dispatch __cons_iter $iter));
+        $crate::__trace!(nil rest);
+        let 0 = rest else {
+            $crate::cons!(@__traced_fail $mode not_empty rest)
+        };
+    };
+    (@__ $mode:tt $iter:expr => []) => {
+        $crate::cons!(@__ $mode $iter => nil);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident) => {
+        let $hd = $crate::__collect_tail!($crate::cons!(@__dispatch __cons_iter $iter), $hd);
+        $crate::__trace!(tail $hd, $hd.len());
+    };
+    (@__ $mode:tt $iter:expr => ($hd:ident $(: $ty:ty)?, $n:ident)) => {
+        // counted while collecting, so it works for any tail type
+        let mut $n = 0usize;
+        let iter = ::core::iter::Iterator::inspect(
+            $crate::cons!(@__dispatch __cons_iter $iter),
+            |_| $n += 1,
+        );
+        $crate::cons!(@__ $mode iter => $hd $(: $ty)?);
+        let $n = $n;
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident [$($len:tt)+]) => {
+        let $hd = $crate::__collect_tail!($crate::cons!(@__dispatch __cons_iter $iter), $hd);
+        $crate::__trace!(tail $hd, $hd.len());
+        $crate::cons!(@__tail_len $mode $hd [$($len)+]);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident in $alloc:expr) => {
+        let $hd = $crate::__collect_tail_in!($crate::cons!(@__dispatch __cons_iter $iter), $hd, $alloc);
+        $crate::__trace!(tail $hd, $hd.len());
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident : $ty:ty) => {
+        let $hd: $ty = ::core::iter::FromIterator::from_iter($crate::cons!(@__dispatch __cons_iter $iter));
+        $crate::__trace!(tail $hd);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident @ ..) => {
+        $crate::cons!(@__ $mode $iter => $hd @ iter);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident @ par) => {
+        let $hd = $crate::__collect_tail_par!($crate::cons!(@__dispatch __cons_iter $iter), $hd);
+        $crate::__trace!(tail $hd);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident @ small < $n:tt >) => {
+        let $hd = $crate::__collect_tail_small!($crate::cons!(@__dispatch __cons_iter $iter), $hd, $n);
+        $crate::__trace!(tail $hd, $hd.len());
+    };
+    (@__ [try_ok $errors:ident $($got:tt)*] $iter:expr => $hd:ident @ iter) => {
+        // hand back the iterator of `Result`s, so that errors after the
+        // heads are left for the caller instead of being dropped
+        #[allow(unused_mut)]
+        let mut $hd = $crate::__private::TryItems::into_inner($iter);
+        $crate::__trace!(tail $hd);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident @ iter) => {
+        #[allow(unused_mut)]
+        let mut $hd = $iter;
+        $crate::__trace!(tail $hd);
+    };
+    (@__ $mode:tt $iter:expr => _) => {
+        let _ = { $iter };
+    };
+    (@__ $mode:tt $iter:expr => $mid:ident @ iter :: $last:tt !) => {
+        $crate::snoc!(@__ $mode $iter => $mid @ iter :: $last);
+    };
+    (@__ $mode:tt $iter:expr => $mid:ident @ .. :: $last:tt !) => {
+        $crate::snoc!(@__ $mode $iter => $mid @ iter :: $last);
+    };
+    (@__ $mode:tt $iter:expr => $mid:tt :: $last:tt !) => {
+        $crate::snoc!(@__ $mode $iter => $mid :: $last);
+    };
+    (@__ $mode:tt $iter:expr => $hd:literal :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => nil :: $($rest:tt)+) => {
+        $crate::__private::diagnose!(pattern nil :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => ($hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => _ :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => (_) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => mut $hd:ident) => {
+        let mut $hd = $crate::__collect_tail!($crate::cons!(@__dispatch __cons_iter $iter), $hd);
+        $crate::__trace!(tail $hd, $hd.len());
+    };
+    (@__ $mode:tt $iter:expr => mut $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => (mut $hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ref mut $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => (ref mut $hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ref $hd:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => (ref $hd) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => (mut $hd:ident : $ty:ty) :: $($rest:tt)+) => {
+        let ::core::option::Option::Some((hd, rest)) = $crate::Uncons::uncons($iter) else {
+            $crate::cons!(@__traced_fail $mode exhausted $hd)
+        };
+        let mut $hd: $ty = hd;
+        $crate::__trace!(head $hd);
+        $crate::cons!(@__next $mode rest => $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ($hd:ident : $ty:ty) :: $($rest:tt)+) => {
+        let ::core::option::Option::Some((hd, rest)) = $crate::Uncons::uncons($iter) else {
+            $crate::cons!(@__traced_fail $mode exhausted $hd)
+        };
+        let $hd: $ty = hd;
+        $crate::__trace!(head $hd);
+        $crate::cons!(@__next $mode rest => $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ($hd:ident = $default:expr) :: $($rest:tt)+) => {
+        let (hd, rest) = match $crate::Uncons::uncons($iter) {
+            ::core::option::Option::Some((hd, rest)) => {
+                (hd, ::core::option::Option::Some($crate::cons!(@__dispatch __cons_iter rest)))
+            }
+            ::core::option::Option::None => ($default, ::core::option::Option::None),
+        };
+        let $hd = hd;
+        $crate::__trace!(head $hd);
+        // once a default has been used, the rest of the pattern sees an
+        // empty iterator
+        let rest = ::core::iter::Iterator::flatten(::core::iter::IntoIterator::into_iter(rest));
+        $crate::cons!(@__next $mode rest => $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ($name:ident @ $hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__head $mode $iter => $name ($name @ $hd $(if $guard)?) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => ($hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__head $mode $iter => $hd ($hd $(if $guard)?) :: $($rest)+);
+    };
+    // `$name` is what an error says wasn't reached
+    (@__head $mode:tt $iter:expr => $name:tt ($hd:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        // `mut` so that the head can be bound with `ref mut`
+        #[allow(unused_mut)]
+        let ::core::option::Option::Some((mut hd, rest)) = $crate::Uncons::uncons($iter) else {
+            $crate::cons!(@__traced_fail $mode exhausted $name)
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens, clippy::toplevel_ref_arg)]
+        let ($hd) = hd else {
+            $crate::cons!(@__traced_fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__traced_fail $mode mismatch [$hd if $guard])
+        };)?
+        $crate::__trace!(head $name);
+        $crate::cons!(@__next $mode rest => $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => $alias:ident ! () $($rest:tt)*) => {
+        $alias!($alias [$crate::cons] [@__ $mode $iter =>] [$($rest)*]);
+    };
+    (@__ $mode:tt $iter:expr => ($i:pat, $x:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => (($i, $x) $(if $guard)?) :: $($rest)+);
+    };
+    (@__ $mode:tt $iter:expr => $($rest:tt)*) => {
+        $crate::__private::diagnose!(pattern $($rest)*);
+    };
+    (@__dispatch $method:ident $iter:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaIntoIterator as _, ViaUncons as _};
+        (&&$crate::__private::Dispatch::new($iter)).$method()
+    }};
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $mid:ident @ iter :: $last:tt !) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)* $last] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $mid:ident @ .. :: $last:tt !) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)* $last] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $mid:tt :: $last:tt !) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)* $last] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] nil) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] exactly);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] []) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] exactly);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident @ ..) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident @ par) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident @ small < $n:tt >) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident $(@ iter)?) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($tl:ident $(: $ty:ty)?, $n:ident)) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident [$($len:tt)+]) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident in $alloc:expr) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $tl:ident : $ty:ty) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] _) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $next:literal :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $alias:ident ! () $($rest:tt)*) => {
+        // the length of an alias isn't known until it is expanded
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($i:pat, $x:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* ($i, $x)] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] mut $tl:ident) => {
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] mut $next:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ref mut $next:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ref $next:ident :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] (mut $next:ident : $ty:ty) :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($next:ident : $ty:ty) :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($next:ident = $default:expr) :: $($rest:tt)+) => {
+        // only the heads before the first default are required
+        $crate::cons!(@__check_len $mode $iter [$($hd)*] at_least);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($next:ident @ $pat:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] ($next:pat $(if $guard:expr)?) :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $next:tt :: $($rest:tt)+) => {
+        $crate::cons!(@__check $mode $iter [$($hd)* $next] $($rest)+);
+    };
+    (@__check $mode:tt $iter:ident [$($hd:tt)*] $($rest:tt)*) => {
+        // malformed patterns are reported by `@__`
+    };
+    (@__check_len $mode:tt $iter:ident [$($hd:tt)*] $len:ident) => {
+        let known_len = {
+            #[allow(unused_imports)]
+            use $crate::__private::{KnownLen as _, UnknownLen as _};
+            (&&$crate::__private::Len(&$iter)).__cons_len()
+        };
+        if let ::core::option::Option::Some(len) = known_len {
+            let heads: &[&'static str] = &[$(::core::stringify!($hd)),*];
+            if len < heads.len() {
+                $crate::cons!(@__traced_fail $mode too_short (heads[len]), len, heads.len())
+            }
+            $crate::cons!(@__check_exact $mode $len len - heads.len());
+        }
+    };
+    (@__tail_len $mode:tt $tl:ident [>= $n:expr]) => {
+        $crate::cons!(@__tail_len $mode $tl >= $n, "at least");
+    };
+    (@__tail_len $mode:tt $tl:ident [> $n:expr]) => {
+        $crate::cons!(@__tail_len $mode $tl > $n, "more than");
+    };
+    (@__tail_len $mode:tt $tl:ident [<= $n:expr]) => {
+        $crate::cons!(@__tail_len $mode $tl <= $n, "at most");
+    };
+    (@__tail_len $mode:tt $tl:ident [< $n:expr]) => {
+        $crate::cons!(@__tail_len $mode $tl < $n, "fewer than");
+    };
+    (@__tail_len $mode:tt $tl:ident [$n:expr]) => {
+        $crate::cons!(@__tail_len $mode $tl == $n, "exactly");
+    };
+    (@__tail_len $mode:tt $tl:ident $op:tt $n:expr, $words:literal) => {
+        let n: usize = $n;
+        if !($tl.len() $op n) {
+            $crate::cons!(@__traced_fail $mode tail_len $tl, ::core::concat!($words, " ", ::core::stringify!($n)))
+        }
+    };
+    (@__check_exact $mode:tt exactly $rest:expr) => {
+        let rest = $rest;
+        if rest != 0 {
+            $crate::cons!(@__traced_fail $mode not_empty rest)
+        }
+    };
+    (@__check_exact $mode:tt at_least $rest:expr) => {};
+    (@__enumerate $iter:expr) => {
+        ::core::iter::Iterator::enumerate(::core::iter::IntoIterator::into_iter($iter))
+    };
+    (@__try $iter:expr => [$($pat:tt)+] ; enumerate) => {
+        $crate::cons!(@__start [try] ($crate::cons!(@__enumerate $iter)) => $($pat)+);
+    };
+    (@__try $iter:expr => [$($pat:tt)+] , $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::cons!(@__start [try_with ($fmt $(, $arg)*)] $iter => $($pat)+);
+    };
+    (@__try $iter:expr => [$($pat:tt)+]) => {
+        $crate::cons!(@__start [try] $iter => $($pat)+);
+    };
+    (@__try $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__try $iter => [$($pat)* $next] $($rest)*);
+    };
+    (@__try_ok $iter:expr => [$($pat:tt)+]) => {
+        let errors = ::core::cell::Cell::new(::core::option::Option::None);
+        let items = $crate::__private::TryItems::new(
+            ::core::iter::IntoIterator::into_iter($iter),
+            &errors,
+        );
+        $crate::cons!(@__start [try_ok errors] items => $($pat)+);
+        if let ::core::option::Option::Some(e) = errors.take() {
+            return ::core::result::Result::Err(::core::convert::From::from(e));
+        }
+    };
+    (@__try_ok $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__try_ok $iter => [$($pat)* $next] $($rest)*);
+    };
+    // the two iterators of a zip come first, since `(a, b)` is an
+    // expression too
+    (@__next [try_with $msg:tt $($got:tt)*] ($a:ident, $b:ident) => $($rest:tt)+) => {
+        $crate::cons!(@__zip_ [try_with $msg $($got)* 1] $a $b => $($rest)+);
+    };
+    (@__next [try $($got:tt)*] ($a:ident, $b:ident) => $($rest:tt)+) => {
+        $crate::cons!(@__zip_ [try $($got)* 1] $a $b => $($rest)+);
+    };
+    (@__next $mode:tt ($a:ident, $b:ident) => $($rest:tt)+) => {
+        $crate::cons!(@__zip_ $mode $a $b => $($rest)+);
+    };
+    (@__next [try_ok $errors:ident $($got:tt)*] $iter:expr => $($rest:tt)+) => {
+        $crate::cons!(@__ [try_ok $errors $($got)* 1] $iter => $($rest)+);
+    };
+    (@__next [try_with $msg:tt $($got:tt)*] $iter:expr => $($rest:tt)+) => {
+        $crate::cons!(@__ [try_with $msg $($got)* 1] $iter => $($rest)+);
+    };
+    (@__next [try $($got:tt)*] $iter:expr => $($rest:tt)+) => {
+        $crate::cons!(@__ [try $($got)* 1] $iter => $($rest)+);
+    };
+    (@__next $mode:tt $iter:expr => $($rest:tt)+) => {
+        $crate::cons!(@__ $mode $iter => $($rest)+);
+    };
+    (@__zip_else ($a:expr, $b:expr) => [$($pat:tt)+] , $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::cons!(@__zip [panic_with ($fmt $(, $arg)*)] ($a, $b) => $($pat)+);
+    };
+    (@__zip_else ($a:expr, $b:expr) => [$($pat:tt)+] else $else:block) => {
+        $crate::cons!(@__zip [else $else] ($a, $b) => $($pat)+);
+    };
+    (@__zip_else ($a:expr, $b:expr) => [$($pat:tt)+]) => {
+        $crate::cons!(@__zip [panic] ($a, $b) => $($pat)+);
+    };
+    (@__zip_else ($a:expr, $b:expr) => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__zip_else ($a, $b) => [$($pat)* $next] $($rest)*);
+    };
+    (@__zip_try ($a:expr, $b:expr) => [$($pat:tt)+] , $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::cons!(@__zip [try_with ($fmt $(, $arg)*)] ($a, $b) => $($pat)+);
+    };
+    (@__zip_try ($a:expr, $b:expr) => [$($pat:tt)+]) => {
+        $crate::cons!(@__zip [try] ($a, $b) => $($pat)+);
+    };
+    (@__zip_try ($a:expr, $b:expr) => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__zip_try ($a, $b) => [$($pat)* $next] $($rest)*);
+    };
+    (@__zip $mode:tt ($a:expr, $b:expr) => $($pat:tt)+) => {
+        $crate::__private::check_bindings!($($pat)+);
+        #[allow(unused_mut)]
+        let mut a = $a.into_iter();
+        #[allow(unused_mut)]
+        let mut b = $b.into_iter();
+        $crate::cons!(@__zip_ $mode a b => $($pat)+);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => nil) => {
+        let rest = $a.count() + $b.count();
+        let 0 = rest else {
+            $crate::cons!(@__fail $mode not_empty rest)
+        };
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => []) => {
+        $crate::cons!(@__zip_ $mode $a $b => nil);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => $tl:ident) => {
+        let $tl = $crate::__collect_tail!(::core::iter::Iterator::zip($a, $b), $tl);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => $tl:ident : $ty:ty) => {
+        let $tl: $ty = ::core::iter::FromIterator::from_iter(::core::iter::Iterator::zip($a, $b));
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => $tl:ident @ ..) => {
+        $crate::cons!(@__zip_ $mode $a $b => $tl @ iter);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => $tl:ident @ iter) => {
+        #[allow(unused_mut)]
+        let mut $tl = ::core::iter::Iterator::zip($a, $b);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => _) => {
+        let _ = ($a, $b);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => _ :: $($rest:tt)+) => {
+        $crate::cons!(@__zip_ $mode $a $b => (_, _) :: $($rest)+);
+    };
+    (
+        @__zip_ $mode:tt $a:ident $b:ident =>
+        ($x:pat, $y:pat $(if $guard:expr)?) :: $($rest:tt)+
+    ) => {
+        let ::core::option::Option::Some(x) = $a.next() else {
+            $crate::cons!(@__fail $mode exhausted $x)
+        };
+        let ::core::option::Option::Some(y) = $b.next() else {
+            $crate::cons!(@__fail $mode exhausted $y)
+        };
+        #[allow(irrefutable_let_patterns)]
+        let ($x, $y) = (x, y) else {
+            $crate::cons!(@__fail $mode mismatch [($x, $y)])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [($x, $y if $guard)])
+        };)?
+        $crate::cons!(@__next $mode ($a, $b) => $($rest)+);
+    };
+    (@__zip_ $mode:tt $a:ident $b:ident => $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "expected a pair pattern such as `(x, y)::rest` when consing two iterators, found `",
+            ::core::stringify!($($rest)*),
+            "`",
+        ));
+    };
+    // `@__fail` itself is also used by the slice macros in a `const fn`,
+    // where an event can't be emitted
+    (@__traced_fail $mode:tt $($reason:tt)+) => {{
+        $crate::__trace!(failed $($reason)+);
+        $crate::cons!(@__fail $mode $($reason)+)
+    }};
+    (@__fail [panic] $($reason:tt)+) => {
+        ::core::panic!("{}", $crate::cons!(@__message $($reason)+))
+    };
+    (@__fail [panic_with $msg:tt] $($reason:tt)+) => {
+        ::core::panic!("{}: {}", ::core::format_args!$msg, $crate::cons!(@__message $($reason)+))
+    };
+    (@__message exhausted $hd:tt) => {
+        // a single `{}` with a `&str` is all a panic in a `const fn` allows
+        ::core::concat!("Iterator exhausted before reaching variable ", ::core::stringify!($hd))
+    };
+    (@__message too_short $missing:expr, $got:ident, $needed:expr) => {
+        ::core::format_args!(
+            "Iterator exhausted before reaching variable {} (the pattern needs at least {} elements, got {})",
+            $missing,
+            $needed,
+            $got,
+        )
+    };
+    (@__message not_empty $rest:ident) => {
+        ::core::format_args!("Found `nil` in cons but iterator is not empty ({} elements left)\nConsider removing `::nil`", $rest)
+    };
+    (@__message mismatch [$($pat:tt)+]) => {
+        ::core::concat!("Element did not match pattern `", ::core::stringify!($($pat)+), "`")
+    };
+    (@__message tail_len $tl:ident, $expected:expr) => {
+        ::core::format_args!(
+            "Tail {} has {} elements, but the pattern expects {}",
+            stringify!($tl),
+            $tl.len(),
+            $expected,
+        )
+    };
+    (@__fail [else $else:block] $($reason:tt)+) => {
+        $else
+    };
+    (@__fail [try_ok $errors:ident $($got:tt)*] $($reason:tt)+) => {{
+        // an error from the iterator ends it early, so report that
+        // rather than the pattern not fitting
+        if let ::core::option::Option::Some(e) = $errors.take() {
+            return ::core::result::Result::Err(::core::convert::From::from(e));
+        }
+        $crate::cons!(@__fail [try $($got)*] $($reason)+)
+    }};
+    (@__fail [try $($got:tt)*] $($reason:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::cons!(@__error [$($got)*] $($reason)+),
+        ))
+    };
+    (@__fail [try_with $msg:tt $($got:tt)*] $($reason:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::ContextError::new(
+                $crate::__private::format!$msg,
+                $crate::cons!(@__error [$($got)*] $($reason)+),
+            ),
+        ))
+    };
+    (@__error [$($got:tt)*] exhausted $hd:tt) => {
+        $crate::ConsError::Exhausted {
+            missing_binding: stringify!($hd),
+            got: 0 $(+ $got)*,
+        }
+    };
+    (@__error [$($got:tt)*] too_short $missing:expr, $len:ident, $needed:expr) => {
+        $crate::ConsError::Exhausted {
+            missing_binding: $missing,
+            got: $len,
+        }
+    };
+    (@__error [$($got:tt)*] mismatch [$($pat:tt)+]) => {
+        $crate::ConsError::Mismatch {
+            pattern: stringify!($($pat)+),
+            position: 0 $(+ $got)*,
+        }
+    };
+    (@__error [$($got:tt)*] tail_len $tl:ident, $expected:expr) => {
+        $crate::ConsError::TailLength {
+            binding: stringify!($tl),
+            expected: $expected,
+            got: $tl.len(),
+        }
+    };
+    (@__error [$($got:tt)*] not_empty $rest:ident) => {
+        $crate::ConsError::TrailingElements { remaining: $rest }
+    };
+    (@__front $mac:ident [$($front:tt)+] as $($rest:tt)+) => {
+        $crate::$mac!(($($front)+) as $($rest)+);
+    };
+    (@__front $mac:ident [$($front:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::cons!(@__front $mac [$($front)* $next] $($rest)*);
+    };
+    (@__front $mac:ident [$($front:tt)*]) => {
+        $crate::__private::diagnose!(invocation $($front)*);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons [] $($invocation)*);
+    };
+}
+
+/// The fallible counterpart of [`cons!`]. Rather than panicking when
+/// the iterator does not fit the pattern, it returns early from the
+/// enclosing function with an `Err`, much like the `?` operator.
+///
+/// The pattern syntax is exactly the same as [`cons!`]:
+///
+/// ```rust
+/// # use cons::{try_cons, ConsError};
+/// fn first_two(v: &[i32]) -> Result<(i32, i32), ConsError> {
+///     try_cons!((v.iter().copied()) as x::y::_);
+///     Ok((x, y))
+/// }
+///
+/// assert_eq!(first_two(&[1, 2, 3]), Ok((1, 2)));
+/// assert_eq!(
+///     first_two(&[1]),
+///     Err(ConsError::Exhausted { missing_binding: "y", got: 1 })
+/// );
+/// ```
+///
+/// The error is passed through [`From::from`] before being returned,
+/// so any error type that implements `From<ConsError>` works as the
+/// return type of the enclosing function:
+///
+/// ```rust
+/// # use cons::{try_cons, ConsError};
+/// #[derive(Debug)]
+/// enum ParseError {
+///     Cons(ConsError),
+/// }
+///
+/// impl From<ConsError> for ParseError {
+///     fn from(e: ConsError) -> Self {
+///         Self::Cons(e)
+///     }
+/// }
+///
+/// fn only(v: Vec<i32>) -> Result<i32, ParseError> {
+///     try_cons!(v as x::nil);
+///     Ok(x)
+/// }
+///
+/// assert_eq!(only(vec![1]).unwrap(), 1);
+/// assert!(matches!(
+///     only(vec![1, 2]),
+///     Err(ParseError::Cons(ConsError::TrailingElements { remaining: 1 }))
+/// ));
+/// ```
+///
+/// `ConsError` implements [`Error`](core::error::Error) and is `Send`,
+/// `Sync` and `'static`, so `?`-style conversion into `anyhow::Error`
+/// or `Box<dyn Error>` works too. A message with arguments, as in
+/// [`format!`](alloc::format!), can follow the pattern to say what was
+/// being matched; the error is then a [`ContextError`] whose
+/// [`source`](core::error::Error::source) is the `ConsError`:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::try_cons;
+/// # use std::error::Error;
+/// fn version(line: &str) -> Result<u32, Box<dyn Error + Send + Sync>> {
+///     try_cons!((line.split('=')) as "version"::v::nil, "parsing {:?}", line);
+///     Ok(v.trim().parse()?)
+/// }
+///
+/// assert_eq!(version("version=3").unwrap(), 3);
+/// let err = version("name=cons").unwrap_err();
+/// assert_eq!(err.to_string(), "parsing \"name=cons\"");
+/// assert_eq!(
+///     err.source().unwrap().to_string(),
+///     "Element 0 did not match pattern `\"version\"`"
+/// );
+/// # }
+/// ```
+///
+/// # Fallible iterators
+///
+/// Putting `?` after the iterator matches an iterator of `Result`s,
+/// such as the lines of a file. Heads and the tail are bound to the
+/// `Ok` values, and the first `Err` is returned from the enclosing
+/// function, so its error type needs to implement both `From<E>` and
+/// `From<ConsError>`:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::{try_cons, ConsError};
+/// # use std::io::{self, BufRead};
+/// #[derive(Debug)]
+/// enum CsvError {
+///     Io(io::Error),
+///     Cons(ConsError),
+/// }
+/// # impl From<io::Error> for CsvError {
+/// #     fn from(e: io::Error) -> Self { Self::Io(e) }
+/// # }
+/// # impl From<ConsError> for CsvError {
+/// #     fn from(e: ConsError) -> Self { Self::Cons(e) }
+/// # }
+///
+/// fn read_csv(input: impl BufRead) -> Result<(String, Vec<String>), CsvError> {
+///     try_cons!((input.lines())? as header::rows);
+///     Ok((header, rows))
+/// }
+///
+/// let (header, rows) = read_csv("a,b\n1,2\n3,4\n".as_bytes()).unwrap();
+/// assert_eq!(header, "a,b");
+/// assert_eq!(rows, ["1,2", "3,4"]);
+///
+/// assert!(matches!(read_csv(&b""[..]), Err(CsvError::Cons(_))));
+/// assert!(matches!(read_csv(&b"\xFF"[..]), Err(CsvError::Io(_))));
+/// # }
+/// ```
+///
+/// An expression needs to be wrapped in parentheses before the `?`.
+/// A tail bound with `@ iter` is the original iterator of `Result`s,
+/// so errors after the heads are left to the caller.
+#[macro_export]
+macro_rules! try_cons {
+    ($iter:ident ? as $($rest:tt)+) => {
+        $crate::cons!(@__try_ok $iter => [] $($rest)+);
+    };
+    (($iter:expr) ? as $($rest:tt)+) => {
+        $crate::cons!(@__try_ok $iter => [] $($rest)+);
+    };
+    (($a:expr, $b:expr) as $($rest:tt)+) => {
+        $crate::cons!(@__zip_try ($a, $b) => [] $($rest)+);
+    };
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::cons!(@__try $iter => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::cons!(@__try $iter => [] $($rest)+);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front try_cons [] $($invocation)*);
+    };
+}
+
+/// A non-consuming version of [`cons!`]. The collection is iterated by
+/// reference, so heads are bound as references, the tail is a vector
+/// of references, and the collection can still be used afterwards.
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons_ref;
+/// let v = vec![String::from("a"), String::from("b"), String::from("c")];
+/// cons_ref!(v as x::xs);
+/// assert_eq!(x, "a");
+/// assert_eq!(xs, vec!["b", "c"]);
+/// // `v` is still usable
+/// assert_eq!(v.len(), 3);
+/// # }
+/// ```
+///
+/// This works for anything that can be iterated by reference, i.e.
+/// where `&T` implements [`IntoIterator`]. The pattern syntax is the
+/// same as [`cons!`], including the `else` block.
+#[macro_export]
+macro_rules! cons_ref {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::cons!(@__else (&$iter) => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::cons!(@__else (&$iter) => [] $($rest)+);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_ref [] $($invocation)*);
+    };
+}
+
+/// Like [`cons_ref!`], but iterates the collection by mutable
+/// reference. Heads are bound as `&mut T` and the tail as a
+/// `Vec<&mut T>`, so the elements can be modified in place.
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::cons_mut;
+/// let mut v = vec![1, 2, 3, 4];
+/// cons_mut!(v as x::y::rest);
+/// *x += 10;
+/// std::mem::swap(x, y);
+/// for z in rest {
+///     *z = 0;
+/// }
+/// assert_eq!(v, vec![2, 11, 0, 0]);
+/// # }
+/// ```
+///
+/// This works for anything that can be iterated by mutable reference,
+/// i.e. where `&mut T` implements [`IntoIterator`].
+#[macro_export]
+macro_rules! cons_mut {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::cons!(@__else (&mut $iter) => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::cons!(@__else (&mut $iter) => [] $($rest)+);
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front cons_mut [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_get_head() {
+        let v = [1, 2, 3];
+        let expected = (1, vec![2, 3]);
+        cons!(v as x::xs);
+        assert_eq!(x, expected.0);
+        assert_eq!(xs, expected.1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_triple_cons() {
+        let v = [1, 2, 3, 4, 5];
+        let expected = (1, 2, vec![3, 4, 5]);
+        cons!(v as x::y::zs);
+        assert_eq!(x, expected.0);
+        assert_eq!(y, expected.1);
+        assert_eq!(zs, expected.2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
     fn test_destructure_in_middle() {
         let v = [(1, 2), (3, 4), (5, 6)];
         cons!(v as w::((x, y))::zs);
@@ -185,6 +1800,43 @@ mod tests {
         assert_eq!(zs, vec![(5, 6)]);
     }
 
+    #[test]
+    #[should_panic(expected = "the pattern needs at least 3 elements, got 1")]
+    fn test_known_length_checked_up_front() {
+        cons!((vec![1]) as x::y::z::_);
+        let _ = (x, y, z);
+    }
+
+    #[test]
+    fn test_known_length_skips_heads() {
+        let checked = core::cell::Cell::new(0);
+        let check = |x: i32| {
+            checked.set(checked.get() + 1);
+            x > 0
+        };
+        let f = |v: Vec<i32>| -> Result<(), ConsError> {
+            try_cons!(v as (x if check(x))::y::nil);
+            let _ = (x, y);
+            Ok(())
+        };
+        assert_eq!(
+            f(vec![1]),
+            Err(ConsError::Exhausted { missing_binding: "y", got: 1 })
+        );
+        assert!(f(vec![1, 2, 3]).is_err());
+        assert_eq!(f(vec![1, 2]), Ok(()));
+        assert_eq!(checked.get(), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_tail_allocated_to_size() {
+        cons!((0..100) as _::rest);
+        assert_eq!(rest.len(), 99);
+        assert_eq!(rest.capacity(), 99);
+    }
+
+    #[cfg(feature = "alloc")]
     #[test]
     #[should_panic(expected = "Iterator exhausted before reaching variable y")]
     #[allow(unused_variables)]
@@ -202,4 +1854,823 @@ mod tests {
         let v = [1, 2];
         cons!(v as x::nil);
     }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_wildcard_head() {
+        let v = [1, 2, 3];
+        cons!(v as _::x::xs);
+        assert_eq!(x, 2);
+        assert_eq!(xs, vec![3]);
+    }
+
+    #[test]
+    fn test_wildcard_tail_not_collected() {
+        let mut iter = 1..=5;
+        cons!((iter.by_ref()) as x::_);
+        assert_eq!(x, 1);
+        assert_eq!(iter.next(), Some(2));
+    }
+
+    #[test]
+    fn test_wildcard_before_nil() {
+        let v = [1, 2];
+        cons!(v as x::_::nil);
+        assert_eq!(x, 1);
+    }
+
+    #[test]
+    fn test_lazy_tail() {
+        let v = vec![String::from("a"), String::from("b"), String::from("c")];
+        cons!(v as x::rest @ iter);
+        assert_eq!(x, "a");
+        assert_eq!(rest.next().as_deref(), Some("b"));
+        assert_eq!(rest.len(), 1);
+    }
+
+    // also runs without `alloc`, so nothing in it may be collected
+    #[test]
+    fn test_fixed_heads_and_lazy_tail_without_alloc() {
+        let frame = [0xAA, 3, 1, 2, 3];
+        cons!(frame as 0xAA::len::payload @ iter);
+        assert_eq!(len, 3);
+        assert!(payload.eq([1, 2, 3]));
+
+        cons!((1..=3) as a::b::c::nil);
+        assert_eq!((a, b, c), (1, 2, 3));
+
+        fn header(bytes: &[u8]) -> Result<(u8, u8), ConsError> {
+            try_cons!(bytes as (&tag)::(&len)::_);
+            Ok((tag, len))
+        }
+        assert_eq!(header(&frame), Ok((0xAA, 3)));
+        assert_eq!(
+            header(&frame[..1]),
+            Err(ConsError::Exhausted { missing_binding: "&len", got: 1 })
+        );
+    }
+
+    #[test]
+    fn test_lazy_tail_is_not_consumed() {
+        let mut consumed = 0;
+        let iter = (1..=5).inspect(|_| consumed += 1);
+        cons!((iter) as x::rest @ iter);
+        assert_eq!(x, 1);
+        drop(rest);
+        assert_eq!(consumed, 1);
+    }
+
+    #[test]
+    fn test_lazy_tail_dots() {
+        cons!((1..=6) as x::rest @ ..);
+        assert_eq!((x, rest.next_back()), (1, Some(6)));
+
+        cons!((1..=6) as _::mid @ ..::y!);
+        assert_eq!((mid.count(), y), (4, 6));
+
+        cons!(((1..3), "ab".chars()) as (n, c)::rest @ ..);
+        assert_eq!((n, c), (1, 'a'));
+        assert_eq!(rest.collect::<Vec<_>>(), [(2, 'b')]);
+
+        crate::snoc!((1..=3) as init @ ..::last);
+        assert_eq!((init.collect::<Vec<_>>(), last), (vec![1, 2], 3));
+
+        crate::cons_array!([1, 2, 3] as _::rest @ ..);
+        assert_eq!(rest.as_slice(), [2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the pattern needs at least 2 elements, got 1")]
+    fn test_lazy_tail_dots_too_short() {
+        cons!((0..1) as _::_::rest @ ..);
+        assert_eq!(rest.next(), None);
+    }
+
+    #[test]
+    fn test_guard() {
+        let v = [1, 2, 3];
+        cons!(v as (x if x == 1)::(y if y % 2 == 0)::z::nil);
+        assert_eq!((x, y, z), (1, 2, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Element did not match pattern `y if y > x`")]
+    #[allow(unused_variables)]
+    fn test_guard_failed() {
+        let v = [2, 1];
+        cons!(v as x::(y if y > x)::nil);
+    }
+
+    #[test]
+    fn test_nil_alias() {
+        let v = [1, 2];
+        cons!(v as x::y::[]);
+        assert_eq!((x, y), (1, 2));
+        let empty: [i32; 0] = [];
+        cons!(empty as []);
+        cons!(empty as nil);
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (2 elements left)")]
+    fn test_lone_nil_not_empty() {
+        let v = [1, 2];
+        cons!(v as nil);
+    }
+
+    #[test]
+    fn test_typed_tail() {
+        use std::collections::{HashMap, VecDeque};
+
+        let v = vec!["a", "b", "c"];
+        cons!(v as x::rest: VecDeque<_>);
+        assert_eq!(x, "a");
+        assert_eq!(rest, VecDeque::from(["b", "c"]));
+
+        cons!((["x", "y"], [1, 2]) as _::rest: HashMap<&str, i32>);
+        assert_eq!(rest, HashMap::from([("y", 2)]));
+
+        cons!(("hello".chars()) as 'h'::rest: String else { unreachable!() });
+        assert_eq!(rest, "ello");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_typed_heads() {
+        let nums = ["3", "4", "5"].map(|s| s.parse().unwrap());
+        cons!(nums as (x: u16)::(mut y: u16)::rest);
+        y += 1000;
+        assert_eq!((x, y, rest), (3, 1004, vec![5]));
+
+        let second = |v: Vec<i64>| -> Result<i64, ConsError> {
+            try_cons!(v as _::(z: i64)::_);
+            Ok(z)
+        };
+        assert_eq!(second(vec![1, 2]), Ok(2));
+        assert_eq!(second(vec![1]), Err(ConsError::Exhausted { missing_binding: "z", got: 1 }));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_binding_modifiers() {
+        let v = vec![1, 2, 3, 4];
+        cons!(v as mut a::ref b::ref mut c::mut rest);
+        a += *b;
+        *c += 10;
+        rest.push(*c);
+        assert_eq!((a, *b, *c, rest), (3, 2, 13, vec![4, 13]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable x")]
+    #[allow(unused_variables, unused_mut)]
+    fn test_binding_modifiers_too_short() {
+        let v = [1];
+        cons!(v as _::mut x::_);
+    }
+
+    #[test]
+    fn test_at_binding_heads() {
+        let v = vec![Some(3), None, Some(12)];
+        cons!(v as (x @ Some(1..=5))::_::(y @ Some(n) if n > 10)::nil);
+        assert_eq!((x, y, n), (Some(3), Some(12), 12));
+
+        let first = |v: Vec<(i32, i32)>| -> Result<i32, ConsError> {
+            try_cons!(v as (p @ (a, _))::_);
+            Ok(p.1 + a)
+        };
+        assert_eq!(first(vec![(1, 2)]), Ok(3));
+        assert_eq!(first(vec![]), Err(ConsError::Exhausted { missing_binding: "p", got: 0 }));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_default_heads() {
+        let run = |line: &'static str| {
+            cons!((line.split(' ')) as cmd::(target = "all")::(out = target)::rest);
+            (cmd, target, out, rest)
+        };
+        assert_eq!(run("make"), ("make", "all", "all", vec![]));
+        assert_eq!(run("make test"), ("make", "test", "test", vec![]));
+        assert_eq!(run("make a b c"), ("make", "a", "b", vec!["c"]));
+
+        cons!((vec![1]) as _::(x = 0)::nil);
+        assert_eq!(x, 0);
+
+        let parse = |v: Vec<u8>| -> Result<(u8, u8), ConsError> {
+            try_cons!(v as a::(b = a)::nil);
+            Ok((a, b))
+        };
+        assert_eq!(parse(vec![1]), Ok((1, 1)));
+        assert_eq!(parse(vec![]), Err(ConsError::Exhausted { missing_binding: "a", got: 0 }));
+        assert_eq!(parse(vec![1, 2, 3]), Err(ConsError::TrailingElements { remaining: 1 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "line 3 of a.txt: Found `nil` in cons but iterator is not empty (1 elements left)")]
+    #[allow(unused_variables)]
+    fn test_panic_message() {
+        let (n, file) = (3, "a.txt");
+        cons!((1..=3) as x::y::nil, "line {} of {file}", n,);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 0: Element did not match pattern `0`")]
+    fn test_panic_message_iterator() {
+        cons!((1..) as 0::_, "row {}", 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_on_missing() {
+        let fallback = |name: &str| name.len();
+        let v = vec![7];
+        cons!(v as a::bb::ccc::rest @ iter; on_missing = fallback);
+        assert_eq!((a, bb, ccc, rest.count()), (7, 2, 3, 0));
+
+        let v = vec![1, 2, 3];
+        cons!(v as mut x::y::rest; on_missing = |_| unreachable!());
+        x += y;
+        assert_eq!((x, rest), (3, vec![3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable Some(y)")]
+    #[allow(unused_variables)]
+    fn test_on_missing_pattern_heads() {
+        cons!((vec![Some(1)]) as x::(Some(y))::_; on_missing = |_| None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_tail_with_count() {
+        use std::collections::HashSet;
+
+        let v = vec![1, 2, 2, 3];
+        cons!(v as x::(xs: HashSet<_>, n));
+        assert_eq!((x, xs.len(), n), (1, 2, 3));
+
+        cons!((0..2) as _::_::(rest, m));
+        assert_eq!((rest, m), (vec![], 0));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_tail_length() {
+        let v = vec![1, 2, 3, 4];
+        cons!(v as x::xs[3]);
+        assert_eq!((x, xs), (1, vec![2, 3, 4]));
+
+        let check = |v: Vec<i32>| -> Result<usize, ConsError> {
+            try_cons!(v as _::_::rest[< 2]);
+            Ok(rest.len())
+        };
+        assert_eq!(check(vec![1, 2]), Ok(0));
+        assert_eq!(
+            check(vec![1, 2, 3, 4]),
+            Err(ConsError::TailLength { binding: "rest", expected: "fewer than 2", got: 2 })
+        );
+        assert_eq!(
+            ConsError::TailLength { binding: "xs", expected: "exactly 3", got: 1 }.to_string(),
+            "Tail xs has 1 elements, but the pattern expects exactly 3"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    #[should_panic(expected = "Tail ys has 1 elements, but the pattern expects at least n + 1")]
+    #[allow(unused_variables)]
+    fn test_tail_length_too_short() {
+        let n = 1;
+        cons!((0..2) as y::ys[>= n + 1]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_or_pattern_heads() {
+        let v = [Some(2), None, Some(4)];
+        cons!(v as (Some(1 | 2) | None)::(None | Some(0))::rest);
+        assert_eq!(rest, vec![Some(4)]);
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn test_small_tail() {
+        cons!((1..=3) as x::rest @ small<2>);
+        assert_eq!((x, rest.as_slice(), rest.spilled()), (1, &[2, 3][..], false));
+        cons!((1..=4) as _::rest @ small<2>);
+        assert!(rest.spilled());
+        let short = || -> Result<usize, ConsError> {
+            try_cons!((1..=1) as _::_::rest @ small<2>);
+            Ok(rest.len())
+        };
+        assert!(matches!(short(), Err(ConsError::Exhausted { got: 1, .. })));
+    }
+
+    #[test]
+    #[cfg(all(feature = "tracing", feature = "alloc"))]
+    fn test_trace_events() {
+        use std::fmt::{self, Write as _};
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Clone, Default)]
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        struct Fields(String);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                let _ = write!(self.0, "{}={:?} ", field.name(), value);
+            }
+        }
+
+        impl Subscriber for Events {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0.trim_end().to_owned());
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Events::default();
+        tracing::subscriber::with_default(events.clone(), || {
+            cons!((1..=4) as x::(y if y > x)::rest);
+            assert_eq!((x, y, rest.len()), (1, 2, 2));
+            // a `Vec` knows its length, so it fails before taking any heads
+            let pair = |v: Vec<i32>| -> Option<i32> {
+                cons!(v as a::_::nil else { return None });
+                Some(a)
+            };
+            assert_eq!(pair(vec![1, 2, 3]), None);
+            let pair = |v: Vec<i32>| -> Option<i32> {
+                cons!((v.into_iter().filter(|_| true)) as a::_::nil else { return None });
+                Some(a)
+            };
+            assert_eq!(pair(vec![1, 2, 3]), None);
+        });
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            [
+                r#"message=cons: bound head binding="x""#,
+                r#"message=cons: bound head binding="y""#,
+                r#"message=cons: bound tail binding="rest" remaining=2"#,
+                r#"message=cons: pattern matched pattern="x::(y if y > x)::rest" consumed=2"#,
+                "message=cons: pattern failed reason=Found `nil` in cons but iterator is not empty \
+                 (1 elements left)\nConsider removing `::nil`",
+                r#"message=cons: bound head binding="a""#,
+                r#"message=cons: bound head binding="_""#,
+                r#"message=cons: reached nil remaining=1"#,
+                "message=cons: pattern failed reason=Found `nil` in cons but iterator is not empty \
+                 (1 elements left)\nConsider removing `::nil`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_or_pattern_bindings() {
+        let v: [Result<i32, i32>; 2] = [Err(1), Ok(2)];
+        cons!(v as (Ok(x) | Err(x))::(Ok(y) | Err(y))::_);
+        assert_eq!((x, y), (1, 2));
+    }
+
+    #[test]
+    fn test_literal_heads() {
+        let v = [Some(1), None, Some(-1)];
+        cons!(v as (Some(1))::(None)::(Some(x))::nil);
+        assert_eq!(x, -1);
+        let v = [-1, 0, 'a' as i32];
+        cons!(v as -1::0::x::nil);
+        assert_eq!(x, 97);
+    }
+
+    #[test]
+    #[should_panic(expected = "Element did not match pattern `\"b\"`")]
+    fn test_literal_mismatch() {
+        let v = ["a", "c"];
+        cons!(v as "a"::"b"::nil);
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable _")]
+    fn test_wildcard_too_short() {
+        let v = [1];
+        cons!(v as _::_::_);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[derive(Debug, PartialEq, Eq)]
+    struct TooShort;
+
+    #[cfg(feature = "alloc")]
+    fn else_helper(v: Vec<i32>) -> Result<(i32, i32, Vec<i32>), TooShort> {
+        cons!(v as x::y::zs else { return Err(TooShort) });
+        Ok((x, y, zs))
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_else_too_short() {
+        assert_eq!(else_helper(vec![1, 2, 3]), Ok((1, 2, vec![3])));
+        assert_eq!(else_helper(vec![1, 2]), Ok((1, 2, vec![])));
+        assert_eq!(else_helper(vec![1]), Err(TooShort));
+        assert_eq!(else_helper(vec![]), Err(TooShort));
+    }
+
+    #[test]
+    fn test_else_too_long() {
+        let mut pairs = vec![];
+        for v in [vec![1, 2], vec![3], vec![4, 5, 6], vec![7, 8]] {
+            cons!(v as x::y::nil else { continue });
+            pairs.push((x, y));
+        }
+        assert_eq!(pairs, vec![(1, 2), (7, 8)]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_cons_ref_does_not_consume() {
+        let v = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        cons_ref!(v as ((n, c))::_::rest);
+        assert_eq!((*n, *c), (1, 'a'));
+        assert_eq!(rest, vec![&(3, 'c')]);
+        assert_eq!(v.len(), 3);
+    }
+
+    #[test]
+    fn test_cons_ref_else() {
+        let mut found = vec![];
+        for v in [vec![], vec![1, 2], vec![3]] {
+            cons_ref!(v as x::_ else { continue });
+            found.push(*x);
+            assert!(!v.is_empty());
+        }
+        assert_eq!(found, vec![1, 3]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_cons_mut() {
+        let mut v = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        cons_mut!(v as ((n, _))::_::rest);
+        *n = 10;
+        for (_, c) in rest {
+            *c = 'z';
+        }
+        assert_eq!(v, vec![(10, 'a'), (2, 'b'), (3, 'z')]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_cons_mut_list() {
+        let mut xs = crate::list![1, 2, 3];
+        let ys = xs.clone();
+        cons_mut!(xs as _::y::_);
+        *y = 0;
+        assert_eq!(xs, crate::list![1, 0, 3]);
+        assert_eq!(ys, crate::list![1, 2, 3]);
+    }
+
+    #[cfg(feature = "alloc")]
+    fn try_cons_helper(v: Vec<i32>) -> Result<(i32, Vec<i32>), ConsError> {
+        try_cons!(v as x::xs);
+        Ok((x, xs))
+    }
+
+    fn try_cons_nil_helper(v: Vec<i32>) -> Result<(i32, i32), ConsError> {
+        try_cons!(v as x::y::nil);
+        Ok((x, y))
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_try_cons_ok() {
+        assert_eq!(try_cons_helper(vec![1, 2, 3]), Ok((1, vec![2, 3])));
+        assert_eq!(try_cons_nil_helper(vec![1, 2]), Ok((1, 2)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_try_cons_too_short() {
+        assert_eq!(
+            try_cons_helper(vec![]),
+            Err(ConsError::Exhausted { missing_binding: "x", got: 0 })
+        );
+        assert_eq!(
+            try_cons_nil_helper(vec![1]),
+            Err(ConsError::Exhausted { missing_binding: "y", got: 1 })
+        );
+    }
+
+    fn try_cons_guard_helper(v: Vec<i32>) -> Result<i32, ConsError> {
+        try_cons!(v as (x if x > 0)::_);
+        Ok(x)
+    }
+
+    #[test]
+    fn test_try_cons_guard() {
+        assert_eq!(try_cons_guard_helper(vec![1]), Ok(1));
+        assert_eq!(
+            try_cons_guard_helper(vec![0]),
+            Err(ConsError::Mismatch { pattern: "x if x > 0", position: 0 })
+        );
+    }
+
+    #[test]
+    fn test_try_cons_too_long() {
+        assert_eq!(
+            try_cons_nil_helper(vec![1, 2, 3, 4]),
+            Err(ConsError::TrailingElements { remaining: 2 })
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum ReadError {
+        Bad(char),
+        Cons(ConsError),
+    }
+
+    impl From<char> for ReadError {
+        fn from(c: char) -> Self {
+            Self::Bad(c)
+        }
+    }
+
+    impl From<ConsError> for ReadError {
+        fn from(e: ConsError) -> Self {
+            Self::Cons(e)
+        }
+    }
+
+    fn digits(s: &str) -> impl Iterator<Item = Result<u32, char>> + '_ {
+        s.chars().map(|c| c.to_digit(10).ok_or(c))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn try_ok_helper(s: &str) -> Result<(u32, Vec<u32>), ReadError> {
+        try_cons!((digits(s))? as x::(1 | 2)::rest);
+        Ok((x, rest))
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_try_ok() {
+        assert_eq!(try_ok_helper("0123"), Ok((0, vec![2, 3])));
+        assert_eq!(try_ok_helper("a123"), Err(ReadError::Bad('a')));
+        assert_eq!(try_ok_helper("0a23"), Err(ReadError::Bad('a')));
+        assert_eq!(try_ok_helper("012a"), Err(ReadError::Bad('a')));
+        assert_eq!(
+            try_ok_helper("0"),
+            Err(ReadError::Cons(ConsError::Exhausted { missing_binding: "1 | 2", got: 1 }))
+        );
+        assert_eq!(
+            try_ok_helper("03"),
+            Err(ReadError::Cons(ConsError::Mismatch { pattern: "1 | 2", position: 1 }))
+        );
+    }
+
+    #[test]
+    fn test_try_ok_lazy_tail() {
+        fn first(s: &str) -> Result<u32, ReadError> {
+            let items = digits(s);
+            try_cons!(items? as x::rest @ iter);
+            assert_eq!(rest.last(), s.chars().nth(1).map(|c| c.to_digit(10).ok_or(c)));
+            Ok(x)
+        }
+        assert_eq!(first("1a"), Ok(1));
+        assert_eq!(first("a1"), Err(ReadError::Bad('a')));
+    }
+
+    #[test]
+    fn test_try_ok_nil() {
+        fn pair(s: &str) -> Result<(u32, u32), ReadError> {
+            try_cons!((digits(s))? as x::y::nil);
+            Ok((x, y))
+        }
+        assert_eq!(pair("12"), Ok((1, 2)));
+        assert_eq!(pair("12a"), Err(ReadError::Bad('a')));
+        assert_eq!(
+            pair("123"),
+            Err(ReadError::Cons(ConsError::TrailingElements { remaining: 1 }))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_assign() {
+        let (mut a, mut b, mut rest) = (0, 0, vec![]);
+        for v in [vec![1, 2, 3], vec![4], vec![5, 6]] {
+            cons!(v => a::b::rest else { continue });
+        }
+        assert_eq!((a, b, rest), (5, 6, vec![]));
+
+        // declared but not yet initialized
+        let key;
+        let args;
+        cons!(("set x 1".split(' ')) => "set"::key::args @ iter);
+        assert_eq!((key, args.collect::<Vec<_>>()), ("x", vec!["1"]));
+
+        let last: i32;
+        let v = [7, 8];
+        cons!(v => _::last::nil);
+        assert_eq!(last, 8);
+    }
+
+    #[test]
+    fn test_cons_fn() {
+        struct Scale(i32);
+
+        impl Scale {
+            #[cons_fn]
+            fn apply<T: Into<i32>>(&self, offset: i32, x::y::nil: Vec<T>, z::_zs: [T; 3]) -> i32 {
+                self.0 * (x.into() + y.into() + z.into()) + offset
+            }
+        }
+
+        #[cons_fn(try)]
+        fn head(x::_xs: impl IntoIterator<Item = u8>, f: fn(u8) -> u8) -> Result<u8, ConsError> {
+            Ok(f(x))
+        }
+
+        assert_eq!(Scale(2).apply(1, vec![1u8, 2], [3, 4, 5]), 13);
+        assert_eq!(head([3], |x| x * 2), Ok(6));
+        assert_eq!(head([], |x| x), Err(ConsError::Exhausted { missing_binding: "x", got: 0 }));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_first_mid_last() {
+        cons!((1..=5) as a::b::mid::z!);
+        assert_eq!((a, b, z), (1, 2, 5));
+        assert_eq!(mid, vec![3, 4]);
+
+        cons!((1..=2) as x::mid::(y if y > x)!);
+        assert_eq!((x, y), (1, 2));
+        assert!(mid.is_empty());
+
+        cons!((1..=4) as _::mid @ iter::_!);
+        assert_eq!(mid.next_back(), Some(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable last")]
+    fn test_first_mid_last_too_short() {
+        let v = [1];
+        cons!(v as first::_::last!);
+        let _ = (first, last);
+    }
+
+    #[test]
+    fn test_unparenthesized_expressions() {
+        let v = [1, 2, 3];
+        cons!(v.iter().copied().filter(|x| x % 2 == 1) as x::y::nil);
+        assert_eq!((x, y), (1, 3));
+        cons_ref!(v[1..] as a::_);
+        assert_eq!(a, &2);
+        try_cons_expr_helper().unwrap();
+    }
+
+    fn try_cons_expr_helper() -> Result<(), ConsError> {
+        try_cons!(core::iter::repeat_n(5, 2) as x::y::nil);
+        assert_eq!(x + y, 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip() {
+        let xs = vec![String::from("a"), String::from("b")];
+        cons!((xs, 1..=2) as (x, i)::(_, j if j > i)::nil);
+        assert_eq!((x.as_str(), i, j), ("a", 1, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (1 elements left)")]
+    fn test_zip_uneven_nil() {
+        cons!(([1, 2], [3]) as (x, y)::nil);
+        let _ = (x, y);
+    }
+
+    fn try_zip_helper(a: Vec<i32>, b: Vec<i32>) -> Result<i32, ConsError> {
+        try_cons!((a, b) as (x, y)::_);
+        Ok(x + y)
+    }
+
+    #[test]
+    fn test_zip_reports_exhausted_side() {
+        assert_eq!(try_zip_helper(vec![1], vec![2, 3]), Ok(3));
+        assert_eq!(
+            try_zip_helper(vec![1], vec![]),
+            Err(ConsError::Exhausted { missing_binding: "y", got: 0 })
+        );
+        assert_eq!(
+            try_zip_helper(vec![], vec![1]),
+            Err(ConsError::Exhausted { missing_binding: "x", got: 0 })
+        );
+    }
+
+    fn try_zip_pairs(a: [i32; 2], b: [i32; 2]) -> Result<(i32, i32), ConsError> {
+        try_cons!((a, b) as (x, y)::(p, q if p < q)::(_, _)::_);
+        Ok((x + y, p + q))
+    }
+
+    #[test]
+    fn test_zip_counts_pairs() {
+        assert_eq!(
+            try_zip_pairs([1, 2], [3, 4]),
+            Err(ConsError::Exhausted { missing_binding: "_", got: 2 })
+        );
+        assert_eq!(
+            try_zip_pairs([1, 4], [3, 2]),
+            Err(ConsError::Mismatch { pattern: "(p, q if p < q)", position: 1 })
+        );
+        cons!(([1], [2]) as (x, y)::_, "zipping {}", "pairs");
+        assert_eq!((x, y), (1, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "zipping pairs: Iterator exhausted before reaching variable q")]
+    fn test_zip_message() {
+        cons!(([1, 2], [3]) as (_, _)::(p, q)::_, "zipping {}", "pairs");
+        let _ = (p, q);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_try_zip_context() {
+        fn pair(a: Vec<i32>, b: Vec<i32>) -> Result<(i32, i32), ContextError> {
+            try_cons!((a, b) as (x, y)::(p, q)::_, "row {}", 3);
+            Ok((x + p, y + q))
+        }
+        assert_eq!(pair(vec![1, 2], vec![3, 4]), Ok((3, 7)));
+        let err = pair(vec![1], vec![1]).unwrap_err();
+        assert_eq!(err.message(), "row 3");
+        assert_eq!(err.error(), ConsError::Exhausted { missing_binding: "p", got: 1 });
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_enumerate() {
+        let v = vec!['a', 'b', 'c', 'd'];
+        cons_ref!(v as (0, &a)::(i, _)::rest; enumerate);
+        assert_eq!((a, i), ('a', 1));
+        assert_eq!(rest, vec![(2, &'c'), (3, &'d')]);
+
+        cons!(v as _::_::_::last::nil; enumerate else { unreachable!() });
+        assert_eq!(last, (3, 'd'));
+    }
+
+    fn second_index(s: &str) -> Result<usize, ConsError> {
+        try_cons!((s.chars()) as _::(i, _)::_; enumerate);
+        Ok(i)
+    }
+
+    #[test]
+    fn test_try_enumerate() {
+        assert_eq!(second_index("ab"), Ok(1));
+        assert!(second_index("a").is_err());
+    }
+
+    #[test]
+    fn test_cons_error_display() {
+        let err = ConsError::Exhausted { missing_binding: "z", got: 2 };
+        assert_eq!(
+            err.to_string(),
+            "Iterator exhausted after 2 elements, before reaching variable z"
+        );
+        let err: &dyn core::error::Error = &ConsError::TrailingElements { remaining: 3 };
+        assert!(err.to_string().contains("3 elements left"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_try_cons_context() {
+        fn parse(v: Vec<i32>, name: &str) -> Result<i32, ContextError> {
+            try_cons!(v as 0::(x if x > 0)::_, "{name} row");
+            Ok(x)
+        }
+        assert_eq!(parse(vec![0, 5], "first"), Ok(5));
+        let err = parse(vec![0, -1], "second").unwrap_err();
+        assert_eq!(err.message(), "second row");
+        assert_eq!(err.to_string(), "second row");
+        assert_eq!(err.error(), ConsError::Mismatch { pattern: "x if x > 0", position: 1 });
+        assert_eq!(
+            ConsError::from(parse(vec![], "").unwrap_err()),
+            ConsError::Exhausted { missing_binding: "0", got: 0 }
+        );
+
+        let err: Box<dyn core::error::Error + Send + Sync> = err.into();
+        assert!(err.source().unwrap().is::<ConsError>());
+    }
 }
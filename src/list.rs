@@ -0,0 +1,3199 @@
+#[cfg(feature = "allocator_api")]
+use alloc::alloc::Allocator;
+use alloc::collections::{LinkedList, VecDeque};
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::vec::{self, Vec};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::{Add, Deref, DerefMut, Index};
+use core::ptr::NonNull;
+use core::str::FromStr;
+use core::{mem, ptr};
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+/// Creates a [`List`] containing the arguments, in order.
+///
+/// ```rust
+/// # use cons::{list, List};
+/// let xs = list![1, 2, 3];
+/// assert_eq!(xs.head(), Some(&1));
+/// assert_eq!(xs.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+///
+/// let empty: List<i32> = list![];
+/// assert!(empty.is_empty());
+/// ```
+#[macro_export]
+macro_rules! list {
+    () => {
+        $crate::List::new()
+    };
+    ($($elem:expr),+ $(,)?) => {
+        <$crate::List<_> as ::core::iter::FromIterator<_>>::from_iter([$($elem),+])
+    };
+}
+
+/// Joins lists end to end, like SML's `xs @ ys @ zs`.
+///
+/// The lists are appended from the right, so every list but the last
+/// is copied exactly once, and the last one is shared.
+///
+/// ```rust
+/// # use cons::{append, list};
+/// let xs = list![1, 2];
+/// let ys = list![3];
+/// let zs = list![4, 5];
+/// assert_eq!(append!(xs, ys, zs), list![1, 2, 3, 4, 5]);
+/// ```
+#[macro_export]
+macro_rules! append {
+    ($list:expr $(,)?) => {
+        ::core::clone::Clone::clone(&$list)
+    };
+    ($list:expr, $($rest:expr),+ $(,)?) => {
+        $list.append(&$crate::append!($($rest),+))
+    };
+}
+
+/// A persistent, immutable singly linked list in the style of SML.
+///
+/// Every node is reference counted, so consing onto a list or taking
+/// its tail never copies any elements. Instead, the new list shares
+/// its nodes with the old one:
+///
+/// ```rust
+/// # use cons::List;
+/// let xs = List::new().cons(3).cons(2);
+/// let ys = xs.cons(1);
+///
+/// assert_eq!(ys.head(), Some(&1));
+/// assert_eq!(ys.tail().unwrap().head(), Some(&2));
+/// // `xs` is untouched
+/// assert_eq!(xs.head(), Some(&2));
+/// ```
+///
+/// Nothing walks the list recursively, including dropping, comparing,
+/// and formatting it, so lists with millions of elements are fine.
+///
+/// Each node also stores the length of the list from that node on, so
+/// [`len`](GenericList::len) is O(1) for every list, including tails.
+///
+/// # Layout
+///
+/// A list is a single pointer to its first node, and `nil` isn't
+/// stored as a null pointer, so `Option<List<T>>` is pointer-sized too.
+/// This holds for every `T` and for [`SyncList`], and is checked when
+/// the crate is compiled:
+///
+/// ```rust
+/// # use cons::List;
+/// use std::mem::size_of;
+///
+/// assert_eq!(size_of::<List<String>>(), size_of::<usize>());
+/// assert_eq!(size_of::<Option<List<String>>>(), size_of::<usize>());
+/// ```
+pub type List<T> = GenericList<T, RcKind>;
+
+/// The list type behind [`List`] and [`SyncList`], generic over the
+/// kind of pointer its nodes are shared with. Use one of the aliases
+/// rather than naming this directly.
+pub struct GenericList<T, P: PointerKind> {
+    head: Link<T, P>,
+}
+
+struct Node<T, P: PointerKind> {
+    elem: T,
+    /// The length of the list that starts at this node. Every node
+    /// stores it, so that the tail of any list knows its length too.
+    len: usize,
+    next: Link<T, P>,
+}
+
+/// A shared pointer to a node, or `nil`.
+///
+/// This is an `Option<P::Pointer<Node>>` that stores `nil` as a
+/// dangling pointer rather than a null one, which leaves null free as a
+/// niche, so that `Option<List<T>>` is the size of a pointer too.
+///
+/// No node can be at the dangling address: a node sits after the
+/// reference counts in its allocation, so its address is at least its
+/// alignment past the start of an allocation, which can't be null.
+struct Link<T, P: PointerKind> {
+    ptr: NonNull<Node<T, P>>,
+    owns: PhantomData<P::Pointer<Node<T, P>>>,
+}
+
+const _: () = {
+    use core::mem::size_of;
+    assert!(size_of::<List<u8>>() == size_of::<usize>());
+    assert!(size_of::<Option<List<u8>>>() == size_of::<usize>());
+    assert!(size_of::<Option<List<[u64; 4]>>>() == size_of::<usize>());
+    #[cfg(target_has_atomic = "ptr")]
+    assert!(size_of::<Option<SyncList<u8>>>() == size_of::<usize>());
+};
+
+// SAFETY: a `Link` is a `P::Pointer<Node>` in all but representation.
+unsafe impl<T, P: PointerKind> Send for Link<T, P> where P::Pointer<Node<T, P>>: Send {}
+// SAFETY: as above.
+unsafe impl<T, P: PointerKind> Sync for Link<T, P> where P::Pointer<Node<T, P>>: Sync {}
+
+impl<T, P: PointerKind> Link<T, P> {
+    const fn nil() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            owns: PhantomData,
+        }
+    }
+
+    fn new(node: Node<T, P>) -> Self {
+        Self::from(Some(P::new(node)))
+    }
+
+    fn is_none(&self) -> bool {
+        self.ptr == NonNull::dangling()
+    }
+
+    fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// The length of the list that starts here.
+    fn len(&self) -> usize {
+        self.as_deref().map_or(0, |node| node.len)
+    }
+
+    fn as_deref(&self) -> Option<&Node<T, P>> {
+        if self.is_none() {
+            return None;
+        }
+        // SAFETY: the node is kept alive by the pointer this stands for
+        Some(unsafe { self.ptr.as_ref() })
+    }
+
+    fn take(&mut self) -> Self {
+        mem::replace(self, Self::nil())
+    }
+
+    fn into_pointer(self) -> Option<P::Pointer<Node<T, P>>> {
+        let this = ManuallyDrop::new(self);
+        if this.is_none() {
+            return None;
+        }
+        // SAFETY: `ptr` came from `P::into_raw`, and `this` won't be
+        // dropped, so the pointer is only rebuilt once
+        Some(unsafe { P::from_raw(this.ptr) })
+    }
+
+    /// Calls `f` with the pointer this stands for, which `f` may
+    /// replace, as [`PointerKind::make_mut`] does.
+    fn with_pointer<R>(&mut self, f: impl FnOnce(&mut P::Pointer<Node<T, P>>) -> R) -> Option<R> {
+        let mut pointer = self.take().into_pointer()?;
+        let result = f(&mut pointer);
+        *self = Self::from(Some(pointer));
+        Some(result)
+    }
+
+    /// Returns the node if this is the only pointer to it.
+    fn get_mut(&mut self) -> Option<&mut Node<T, P>> {
+        if !self.with_pointer(|pointer| P::get_mut(pointer).is_some())? {
+            return None;
+        }
+        // SAFETY: nothing else can reach the node
+        Some(unsafe { self.ptr.as_mut() })
+    }
+
+    /// Returns the node, cloning it first if it is shared.
+    fn make_mut(&mut self) -> Option<&mut Node<T, P>>
+    where
+        T: Clone,
+    {
+        self.with_pointer(|pointer| {
+            P::make_mut(pointer);
+        })?;
+        // SAFETY: `make_mut` left this as the only pointer to the node
+        Some(unsafe { self.ptr.as_mut() })
+    }
+
+    /// Replaces the link with one to a new node, and returns the node.
+    fn insert(&mut self, node: Node<T, P>) -> &mut Node<T, P> {
+        *self = Self::new(node);
+        self.get_mut().unwrap()
+    }
+}
+
+impl<T, P: PointerKind> From<Option<P::Pointer<Node<T, P>>>> for Link<T, P> {
+    fn from(pointer: Option<P::Pointer<Node<T, P>>>) -> Self {
+        match pointer {
+            Some(pointer) => Self {
+                ptr: P::into_raw(pointer),
+                owns: PhantomData,
+            },
+            None => Self::nil(),
+        }
+    }
+}
+
+impl<T, P: PointerKind> Clone for Link<T, P> {
+    fn clone(&self) -> Self {
+        if self.is_none() {
+            return Self::nil();
+        }
+        // SAFETY: as in `into_pointer`, but the rebuilt pointer is only
+        // borrowed, so this keeps its reference
+        let pointer = ManuallyDrop::new(unsafe { P::from_raw(self.ptr) });
+        Self::from(Some(P::Pointer::clone(&pointer)))
+    }
+}
+
+impl<T, P: PointerKind> Drop for Link<T, P> {
+    fn drop(&mut self) {
+        drop(self.take().into_pointer());
+    }
+}
+
+/// A [`List`] whose nodes are shared with [`Arc`] instead of [`Rc`], so
+/// it can be sent to and shared between threads.
+///
+/// It has the same API as `List`:
+///
+/// ```rust
+/// # use cons::SyncList;
+/// let xs = SyncList::new().cons(2).cons(1);
+/// let handle = std::thread::spawn({
+///     let xs = xs.clone();
+///     move || xs.iter().sum::<i32>()
+/// });
+/// assert_eq!(handle.join().unwrap(), 3);
+/// assert_eq!(xs.head(), Some(&1));
+/// ```
+#[cfg(target_has_atomic = "ptr")]
+pub type SyncList<T> = GenericList<T, ArcKind>;
+
+/// A [`List`] whose nodes are allocated with the allocator `A`
+/// instead of the global one. Needs the nightly-only `allocator_api`
+/// feature.
+///
+/// The allocator is part of the type rather than stored in the list,
+/// so it is created with [`Default`] for every node. That suits
+/// zero-sized handles to a static pool:
+///
+/// ```rust
+/// #![feature(allocator_api)]
+/// # use cons::list::ListIn;
+/// use std::alloc::{AllocError, Allocator, Global, Layout};
+/// use std::ptr::NonNull;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+///
+/// #[derive(Clone, Copy, Default)]
+/// struct Counting;
+///
+/// unsafe impl Allocator for Counting {
+///     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+///         ALLOCATED.fetch_add(1, Ordering::Relaxed);
+///         Global.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         Global.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let xs: ListIn<i32, Counting> = (1..=3).collect();
+/// assert_eq!(xs.iter().sum::<i32>(), 6);
+/// assert_eq!(ALLOCATED.load(Ordering::Relaxed), 3);
+/// ```
+#[cfg(feature = "allocator_api")]
+pub type ListIn<T, A> = GenericList<T, RcIn<A>>;
+
+/// The kind of reference-counted pointer a [`List`] uses for its
+/// nodes: [`RcKind`] for [`List`] and [`ArcKind`] for [`SyncList`].
+///
+/// This trait is sealed and can't be implemented outside this crate.
+pub trait PointerKind: sealed::Sealed + 'static {
+    #[doc(hidden)]
+    type Pointer<U>: Deref<Target = U> + Clone;
+
+    #[doc(hidden)]
+    fn new<U>(value: U) -> Self::Pointer<U>;
+
+    #[doc(hidden)]
+    fn get_mut<U>(this: &mut Self::Pointer<U>) -> Option<&mut U>;
+
+    #[doc(hidden)]
+    fn make_mut<U: Clone>(this: &mut Self::Pointer<U>) -> &mut U;
+
+    #[doc(hidden)]
+    fn try_unwrap<U>(this: Self::Pointer<U>) -> Result<U, Self::Pointer<U>>;
+
+    #[doc(hidden)]
+    fn into_inner<U>(this: Self::Pointer<U>) -> Option<U>;
+
+    #[doc(hidden)]
+    fn into_raw<U>(this: Self::Pointer<U>) -> NonNull<U>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have come from [`into_raw`](Self::into_raw), and each
+    /// call to `into_raw` must be matched by one call to this.
+    #[doc(hidden)]
+    unsafe fn from_raw<U>(ptr: NonNull<U>) -> Self::Pointer<U>;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Nodes are shared with [`Rc`]. This is the default.
+pub enum RcKind {}
+
+impl sealed::Sealed for RcKind {}
+
+impl PointerKind for RcKind {
+    type Pointer<U> = Rc<U>;
+
+    fn new<U>(value: U) -> Rc<U> {
+        Rc::new(value)
+    }
+
+    fn get_mut<U>(this: &mut Rc<U>) -> Option<&mut U> {
+        Rc::get_mut(this)
+    }
+
+    fn make_mut<U: Clone>(this: &mut Rc<U>) -> &mut U {
+        Rc::make_mut(this)
+    }
+
+    fn try_unwrap<U>(this: Rc<U>) -> Result<U, Rc<U>> {
+        Rc::try_unwrap(this)
+    }
+
+    fn into_inner<U>(this: Rc<U>) -> Option<U> {
+        Rc::into_inner(this)
+    }
+
+    fn into_raw<U>(this: Rc<U>) -> NonNull<U> {
+        // SAFETY: the pointer to the value in an allocation isn't null
+        unsafe { NonNull::new_unchecked(Rc::into_raw(this).cast_mut()) }
+    }
+
+    unsafe fn from_raw<U>(ptr: NonNull<U>) -> Rc<U> {
+        // SAFETY: guaranteed by the caller
+        unsafe { Rc::from_raw(ptr.as_ptr()) }
+    }
+}
+
+/// Nodes are shared with [`Arc`], as in [`SyncList`].
+#[cfg(target_has_atomic = "ptr")]
+pub enum ArcKind {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl sealed::Sealed for ArcKind {}
+
+#[cfg(target_has_atomic = "ptr")]
+impl PointerKind for ArcKind {
+    type Pointer<U> = Arc<U>;
+
+    fn new<U>(value: U) -> Arc<U> {
+        Arc::new(value)
+    }
+
+    fn get_mut<U>(this: &mut Arc<U>) -> Option<&mut U> {
+        Arc::get_mut(this)
+    }
+
+    fn make_mut<U: Clone>(this: &mut Arc<U>) -> &mut U {
+        Arc::make_mut(this)
+    }
+
+    fn try_unwrap<U>(this: Arc<U>) -> Result<U, Arc<U>> {
+        Arc::try_unwrap(this)
+    }
+
+    fn into_inner<U>(this: Arc<U>) -> Option<U> {
+        Arc::into_inner(this)
+    }
+
+    fn into_raw<U>(this: Arc<U>) -> NonNull<U> {
+        // SAFETY: the pointer to the value in an allocation isn't null
+        unsafe { NonNull::new_unchecked(Arc::into_raw(this).cast_mut()) }
+    }
+
+    unsafe fn from_raw<U>(ptr: NonNull<U>) -> Arc<U> {
+        // SAFETY: guaranteed by the caller
+        unsafe { Arc::from_raw(ptr.as_ptr()) }
+    }
+}
+
+/// Nodes are shared with [`Rc`] and allocated with `A`, as in
+/// [`ListIn`].
+#[cfg(feature = "allocator_api")]
+pub struct RcIn<A>(PhantomData<A>);
+
+#[cfg(feature = "allocator_api")]
+impl<A> sealed::Sealed for RcIn<A> {}
+
+#[cfg(feature = "allocator_api")]
+impl<A: Allocator + Clone + Default + 'static> PointerKind for RcIn<A> {
+    type Pointer<U> = Rc<U, A>;
+
+    fn new<U>(value: U) -> Rc<U, A> {
+        Rc::new_in(value, A::default())
+    }
+
+    fn get_mut<U>(this: &mut Rc<U, A>) -> Option<&mut U> {
+        Rc::get_mut(this)
+    }
+
+    fn make_mut<U: Clone>(this: &mut Rc<U, A>) -> &mut U {
+        Rc::make_mut(this)
+    }
+
+    fn try_unwrap<U>(this: Rc<U, A>) -> Result<U, Rc<U, A>> {
+        Rc::try_unwrap(this)
+    }
+
+    fn into_inner<U>(this: Rc<U, A>) -> Option<U> {
+        Rc::into_inner(this)
+    }
+
+    fn into_raw<U>(this: Rc<U, A>) -> NonNull<U> {
+        // the allocator is made again with `Default` in `from_raw`
+        let (ptr, _) = Rc::into_raw_with_allocator(this);
+        // SAFETY: the pointer to the value in an allocation isn't null
+        unsafe { NonNull::new_unchecked(ptr.cast_mut()) }
+    }
+
+    unsafe fn from_raw<U>(ptr: NonNull<U>) -> Rc<U, A> {
+        // SAFETY: guaranteed by the caller
+        unsafe { Rc::from_raw_in(ptr.as_ptr(), A::default()) }
+    }
+}
+
+impl<T, P: PointerKind> GenericList<T, P> {
+    /// Creates a new, empty list (SML's `nil`).
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs: List<i32> = List::new();
+    /// assert!(xs.is_empty());
+    /// ```
+    pub const fn new() -> Self {
+        Self { head: Link::nil() }
+    }
+
+    /// Returns a new list with `elem` at the front and `self` as the
+    /// tail. This is SML's `elem :: self`.
+    ///
+    /// This is O(1); `self` is shared, not copied.
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs = List::new().cons(2).cons(1);
+    /// assert_eq!(xs.head(), Some(&1));
+    /// ```
+    #[must_use]
+    pub fn cons(&self, elem: T) -> Self {
+        Self {
+            head: Link::new(Node {
+                elem,
+                len: self.len() + 1,
+                next: self.head.clone(),
+            }),
+        }
+    }
+
+    /// Returns a reference to the first element of the list, or `None`
+    /// if it is empty.
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// assert_eq!(List::new().cons(1).head(), Some(&1));
+    /// assert_eq!(List::<i32>::new().head(), None);
+    /// ```
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_deref().map(|node| &node.elem)
+    }
+
+    /// Returns everything but the first element of the list, or `None`
+    /// if it is empty.
+    ///
+    /// This is O(1); the returned list shares its nodes with `self`.
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs = List::new().cons(2).cons(1);
+    /// let tl = xs.tail().unwrap();
+    /// assert_eq!(tl.head(), Some(&2));
+    /// assert!(tl.tail().unwrap().is_empty());
+    /// assert!(List::<i32>::new().tail().is_none());
+    /// ```
+    pub fn tail(&self) -> Option<Self> {
+        self.head.as_deref().map(|node| Self {
+            head: node.next.clone(),
+        })
+    }
+
+    /// Splits the list into its head and tail, or returns `None` if it
+    /// is empty.
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs = List::new().cons(2).cons(1);
+    /// let (x, tl) = xs.uncons().unwrap();
+    /// assert_eq!(*x, 1);
+    /// assert_eq!(tl.head(), Some(&2));
+    /// ```
+    pub fn uncons(&self) -> Option<(&T, Self)> {
+        self.head.as_deref().map(|node| {
+            (
+                &node.elem,
+                Self {
+                    head: node.next.clone(),
+                },
+            )
+        })
+    }
+
+    /// Returns a [`ConsView`] of the list, so it can be taken apart with
+    /// an ordinary `match` and the compiler's own exhaustiveness
+    /// checking.
+    ///
+    /// ```rust
+    /// use cons::list::ConsView;
+    /// use cons::{list, List};
+    ///
+    /// fn sum(xs: &List<i32>) -> i32 {
+    ///     match xs.view() {
+    ///         ConsView::Nil => 0,
+    ///         ConsView::Cons(x, xs) => x + sum(&xs),
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(sum(&list![1, 2, 3]), 6);
+    /// ```
+    pub fn view(&self) -> ConsView<'_, T, P> {
+        match self.uncons() {
+            Some((x, xs)) => ConsView::Cons(x, xs),
+            None => ConsView::Nil,
+        }
+    }
+
+    /// Returns `true` if the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns the number of elements in the list. This is SML's
+    /// `length`.
+    ///
+    /// Each node stores the length of the list from there on, so this is
+    /// O(1).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.head.len()
+    }
+
+    /// Sets the stored lengths of the first `n` nodes, which must not be
+    /// shared, given the length of the list after them.
+    fn set_lens(&mut self, n: usize, rest: usize) {
+        let mut cur = &mut self.head;
+        for i in 0..n {
+            let node = cur.get_mut().unwrap();
+            node.len = n - i + rest;
+            cur = &mut node.next;
+        }
+    }
+
+    /// Returns the list without its first `n` elements, or an empty list
+    /// if it has fewer than `n`. This is SML's `drop`.
+    ///
+    /// This is O(n) and doesn't allocate; the returned list shares its
+    /// nodes with `self`.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let xs = list![1, 2, 3, 4];
+    /// assert_eq!(xs.drop(2), list![3, 4]);
+    /// assert_eq!(xs.drop(5), List::new());
+    /// ```
+    #[must_use]
+    pub fn drop(&self, n: usize) -> Self {
+        if n >= self.len() {
+            return Self::new();
+        }
+        let mut cur = &self.head;
+        for _ in 0..n {
+            match cur.as_deref() {
+                Some(node) => cur = &node.next,
+                None => break,
+            }
+        }
+        Self { head: cur.clone() }
+    }
+
+    /// Returns the list without the longest prefix of elements for which
+    /// `f` returns `true`. Like [`drop`](Self::drop), this shares the
+    /// rest of the list rather than copying it.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3, 1];
+    /// assert_eq!(xs.drop_while(|&x| x < 3), list![3, 1]);
+    /// ```
+    #[must_use]
+    pub fn drop_while(&self, mut f: impl FnMut(&T) -> bool) -> Self {
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref() {
+            if !f(&node.elem) {
+                break;
+            }
+            cur = &node.next;
+        }
+        Self { head: cur.clone() }
+    }
+
+    /// Returns a reference to the last element, or `None` if the list is
+    /// empty. This walks the whole list, so it is O(n).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].last(), Some(&3));
+    /// ```
+    pub fn last(&self) -> Option<&T> {
+        self.iter().last()
+    }
+
+    /// Returns a reference to the element at index `n`, counting from
+    /// zero, or `None` if the list is too short. This is SML's `nth`.
+    ///
+    /// Lists have no random access, so this walks the first `n` nodes
+    /// and is O(n), though an index past the end is rejected in O(1).
+    /// Indexing with `xs[n]` does the same, but panics if the list is
+    /// too short.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// assert_eq!(xs.get(1), Some(&2));
+    /// assert_eq!(xs.get(3), None);
+    /// assert_eq!(xs[2], 3);
+    /// ```
+    pub fn get(&self, n: usize) -> Option<&T> {
+        if n >= self.len() {
+            return None;
+        }
+        self.iter().nth(n)
+    }
+
+    /// Same as [`get`](Self::get), under SML's name.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].nth(1), Some(&2));
+    /// assert_eq!(list![1, 2, 3].nth(3), None);
+    /// ```
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        self.get(n)
+    }
+
+    /// Returns the first element for which `f` returns `true`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3, 4].find(|x| x % 2 == 0), Some(&2));
+    /// ```
+    pub fn find(&self, mut f: impl FnMut(&T) -> bool) -> Option<&T> {
+        self.iter().find(|x| f(x))
+    }
+
+    /// Returns `true` if `f` returns `true` for any element. This is
+    /// SML's `exists`.
+    pub fn any(&self, f: impl FnMut(&T) -> bool) -> bool {
+        self.iter().any(f)
+    }
+
+    /// Returns `true` if `f` returns `true` for every element, including
+    /// when the list is empty.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// assert!(list![2, 4].all(|x| x % 2 == 0));
+    /// assert!(!list![2, 3].all(|x| x % 2 == 0));
+    /// assert!(List::<i32>::new().all(|_| false));
+    /// ```
+    pub fn all(&self, f: impl FnMut(&T) -> bool) -> bool {
+        self.iter().all(f)
+    }
+
+    /// Calls `f` on each element, in order. This is SML's `app`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let mut seen = vec![];
+    /// list![1, 2, 3].for_each(|x| seen.push(*x));
+    /// assert_eq!(seen, vec![1, 2, 3]);
+    /// ```
+    pub fn for_each(&self, f: impl FnMut(&T)) {
+        self.iter().for_each(f);
+    }
+
+    /// Returns an iterator over references to the elements of the list.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// assert_eq!(xs.iter().sum::<i32>(), 6);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            next: self.head.as_deref(),
+            back: vec::IntoIter::default(),
+        }
+    }
+
+    /// Combines the elements from first to last, starting with `init`.
+    /// This is SML's `foldl`, with the arguments of `f` the other way
+    /// around to match [`Iterator::fold`].
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// assert_eq!(xs.fold_left(String::new(), |s, x| format!("{s}{x}")), "123");
+    /// ```
+    pub fn fold_left<B>(&self, init: B, f: impl FnMut(B, &T) -> B) -> B {
+        self.iter().fold(init, f)
+    }
+
+    /// Combines the elements from last to first, starting with `init`.
+    /// This is SML's `foldr`.
+    ///
+    /// Unlike the usual recursive definition, this doesn't use any
+    /// stack space per element, so it works on lists of any length.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let xs = list![1, 2, 3];
+    /// assert_eq!(xs.fold_right(String::new(), |x, s| format!("{s}{x}")), "321");
+    ///
+    /// let long = (0..1_000_000).collect::<List<u64>>();
+    /// assert_eq!(long.fold_right(0, |x, sum| x + sum), 499_999_500_000);
+    /// ```
+    pub fn fold_right<B>(&self, init: B, mut f: impl FnMut(&T, B) -> B) -> B {
+        let elems = self.iter().collect::<Vec<_>>();
+        elems.into_iter().rev().fold(init, |acc, x| f(x, acc))
+    }
+
+    /// Returns a new list with `f` applied to each element, in order.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].map(|x| x * 10), list![10, 20, 30]);
+    /// ```
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> GenericList<U, P> {
+        self.iter().map(f).collect()
+    }
+
+    /// Returns a new list of the values `f` returns `Some` for, in
+    /// order. This is SML's `mapPartial`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let words = list!["1", "two", "3"];
+    /// assert_eq!(words.filter_map(|s| s.parse().ok()), list![1, 3]);
+    /// ```
+    pub fn filter_map<U>(&self, f: impl FnMut(&T) -> Option<U>) -> GenericList<U, P> {
+        self.iter().filter_map(f).collect()
+    }
+
+    /// Returns a new list of `f` applied to pairs of elements from
+    /// `self` and `other`, stopping at the end of the shorter list. This
+    /// is SML's `ListPair.map`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// let ys = list![10, 20];
+    /// assert_eq!(xs.zip_with(&ys, |x, y| x + y), list![11, 22]);
+    /// ```
+    pub fn zip_with<U, V>(
+        &self,
+        other: &GenericList<U, P>,
+        mut f: impl FnMut(&T, &U) -> V,
+    ) -> GenericList<V, P> {
+        self.iter().zip(other).map(|(x, y)| f(x, y)).collect()
+    }
+
+    /// Like [`zip_with`](Self::zip_with), but returns an error if the
+    /// lists have different lengths instead of ignoring the extra
+    /// elements. This is SML's `ListPair.mapEq`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// # use cons::list::UnequalLengths;
+    /// let xs = list![1, 2];
+    /// assert_eq!(xs.zip_with_eq(&list![3, 4], |x, y| x * y), Ok(list![3, 8]));
+    /// assert_eq!(xs.zip_with_eq(&list![3], |x, y| x * y), Err(UnequalLengths));
+    /// ```
+    pub fn zip_with_eq<U, V>(
+        &self,
+        other: &GenericList<U, P>,
+        f: impl FnMut(&T, &U) -> V,
+    ) -> Result<GenericList<V, P>, UnequalLengths> {
+        if self.len() != other.len() {
+            return Err(UnequalLengths);
+        }
+        Ok(self.zip_with(other, f))
+    }
+
+    /// Creates a list of `n` elements, where the element at index `i` is
+    /// `f(i)`. This is SML's `tabulate`.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// assert_eq!(List::tabulate(4, |i| i * i), list![0, 1, 4, 9]);
+    /// ```
+    pub fn tabulate(n: usize, f: impl FnMut(usize) -> T) -> Self {
+        (0..n).map(f).collect()
+    }
+
+    /// Moves the elements of a vector into a list, in order.
+    ///
+    /// This is a convenience rather than a bulk load: a list is a chain
+    /// of separately allocated nodes, so it still allocates once per
+    /// element. It builds the list from the back, so every node is
+    /// created with its final length and tail in a single pass, where
+    /// [`collect`](Iterator::collect) has to go back and fill in the
+    /// lengths.
+    ///
+    /// To load a large vector, use
+    /// [`ChunkedList::from_vec`](crate::ChunkedList::from_vec) instead,
+    /// which allocates once per 32 elements. `cargo bench` compares the
+    /// two.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let xs = List::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(xs, list![1, 2, 3]);
+    /// assert_eq!(xs.tail().unwrap().len(), 2);
+    /// ```
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        let mut head = Link::nil();
+        for (len, elem) in vec.into_iter().rev().enumerate() {
+            head = Link::new(Node {
+                elem,
+                len: len + 1,
+                next: head,
+            });
+        }
+        Self { head }
+    }
+
+    /// Returns the running results of folding from the front, starting
+    /// with `init` and ending with the final result, so the new list is
+    /// one longer than `self`. This is Haskell's `scanl`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let sums = list![1, 2, 3, 4].scan_left(0, |acc, x| acc + x);
+    /// assert_eq!(sums, list![0, 1, 3, 6, 10]);
+    /// ```
+    pub fn scan_left<B>(&self, init: B, mut f: impl FnMut(&B, &T) -> B) -> GenericList<B, P> {
+        let mut acc = Vec::with_capacity(self.len() + 1);
+        acc.push(init);
+        for x in self {
+            let next = f(acc.last().unwrap(), x);
+            acc.push(next);
+        }
+        GenericList::from_vec(acc)
+    }
+
+    /// Maps over the list from the front while threading an accumulator
+    /// through `f`, and returns the final accumulator along with the new
+    /// list. This is Haskell's `mapAccumL`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// // number the words, and count the letters
+    /// let words = list!["a", "bc", "def"];
+    /// let (letters, numbered) = words.map_accum_left(0, |n, w| (n + w.len(), (n, *w)));
+    /// assert_eq!(letters, 6);
+    /// assert_eq!(numbered, list![(0, "a"), (1, "bc"), (3, "def")]);
+    /// ```
+    pub fn map_accum_left<A, U>(
+        &self,
+        init: A,
+        mut f: impl FnMut(A, &T) -> (A, U),
+    ) -> (A, GenericList<U, P>) {
+        let mut out = Vec::with_capacity(self.len());
+        let acc = self.iter().fold(init, |acc, x| {
+            let (acc, y) = f(acc, x);
+            out.push(y);
+            acc
+        });
+        (acc, GenericList::from_vec(out))
+    }
+
+    /// Like [`map_accum_left`](Self::map_accum_left), but goes from the
+    /// back of the list to the front. The new list keeps the order of
+    /// `self`. This is Haskell's `mapAccumR`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// // the sum of each element and everything after it
+    /// let (total, suffix_sums) = list![1, 2, 3].map_accum_right(0, |acc, x| (acc + x, acc + x));
+    /// assert_eq!(total, 6);
+    /// assert_eq!(suffix_sums, list![6, 5, 3]);
+    /// ```
+    pub fn map_accum_right<A, U>(
+        &self,
+        init: A,
+        mut f: impl FnMut(A, &T) -> (A, U),
+    ) -> (A, GenericList<U, P>) {
+        let elems = self.iter().collect::<Vec<_>>();
+        elems
+            .into_iter()
+            .rev()
+            .fold((init, GenericList::new()), |(acc, list), x| {
+                let (acc, y) = f(acc, x);
+                (acc, list.cons(y))
+            })
+    }
+}
+
+impl<T: Clone, P: PointerKind> GenericList<T, P> {
+    /// Returns an iterator over mutable references to the elements of
+    /// the list.
+    ///
+    /// Any nodes that are shared with another list are cloned before
+    /// being handed out, so other lists never see the changes.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// let mut ys = xs.clone();
+    /// for y in ys.iter_mut() {
+    ///     *y *= 10;
+    /// }
+    /// assert_eq!(ys, list![10, 20, 30]);
+    /// assert_eq!(xs, list![1, 2, 3]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, P> {
+        IterMut {
+            next: Some(&mut self.head).filter(|link| link.is_some()),
+            back: vec::IntoIter::default(),
+        }
+    }
+
+    /// Returns a mutable reference to the first element, or `None` if
+    /// the list is empty.
+    ///
+    /// Like [`Rc::make_mut`], this only clones the first node if it is
+    /// shared with another list, so other lists never see the change,
+    /// and a list that isn't shared is changed in place.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2];
+    /// let mut ys = xs.clone();
+    /// *ys.make_mut().unwrap() = 10;
+    /// assert_eq!(ys, list![10, 2]);
+    /// assert_eq!(xs, list![1, 2]);
+    /// // only the first node was cloned
+    /// assert!(std::ptr::eq(xs.get(1).unwrap(), ys.get(1).unwrap()));
+    /// ```
+    pub fn make_mut(&mut self) -> Option<&mut T> {
+        self.head.make_mut().map(|node| &mut node.elem)
+    }
+
+    /// Returns a mutable handle to everything but the first element, or
+    /// `None` if the list is empty.
+    ///
+    /// The first node is cloned if it is shared, as in
+    /// [`make_mut`](GenericList::make_mut), and the tail can then be
+    /// changed like any other list, including by taking its own
+    /// `tail_mut`. This makes it possible to edit a list in the middle
+    /// while copying only the nodes in front of the change:
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// let mut ys = xs.clone();
+    /// {
+    ///     let mut rest = ys.tail_mut().unwrap();
+    ///     let mut rest = rest.tail_mut().unwrap();
+    ///     *rest = rest.cons(10);
+    /// }
+    /// assert_eq!(ys, list![1, 2, 10, 3]);
+    /// assert_eq!(ys.len(), 4);
+    /// assert_eq!(xs, list![1, 2, 3]);
+    /// ```
+    pub fn tail_mut(&mut self) -> Option<TailMut<'_, T, P>> {
+        self.head.make_mut().map(|node| {
+            let tail = Self {
+                head: node.next.take(),
+            };
+            TailMut { node, tail }
+        })
+    }
+
+    /// Returns a new list with only the elements for which `f` returns
+    /// `true`, in order.
+    ///
+    /// The new list shares every node after the last element that was
+    /// removed with `self`, so only the elements before it are cloned.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3, 4, 5];
+    /// assert_eq!(xs.filter(|x| x % 2 == 1), list![1, 3, 5]);
+    /// ```
+    #[must_use]
+    pub fn filter(&self, mut f: impl FnMut(&T) -> bool) -> Self {
+        let mut kept = Vec::new();
+        let mut prefix = 0;
+        let mut shared = &self.head;
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref() {
+            if f(&node.elem) {
+                kept.push(&node.elem);
+            } else {
+                prefix = kept.len();
+                shared = &node.next;
+            }
+            cur = &node.next;
+        }
+        Self::cons_prefix(&kept[..prefix], shared)
+    }
+
+    /// Conses clones of `prefix`, in order, onto the list that starts at
+    /// `shared`.
+    fn cons_prefix(prefix: &[&T], shared: &Link<T, P>) -> Self {
+        let list = Self {
+            head: shared.clone(),
+        };
+        prefix
+            .iter()
+            .rev()
+            .fold(list, |list, &x| list.cons(x.clone()))
+    }
+
+    /// Splits the list into the elements for which `f` returns `true`
+    /// and those for which it returns `false`, keeping their order. `f`
+    /// is called once for each element, in one pass.
+    ///
+    /// Like [`filter`](Self::filter), each half shares the nodes after
+    /// the last element that went into the other half.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let (even, odd) = list![1, 2, 3, 4, 5].partition(|x| x % 2 == 0);
+    /// assert_eq!(even, list![2, 4]);
+    /// assert_eq!(odd, list![1, 3, 5]);
+    /// ```
+    pub fn partition(&self, mut f: impl FnMut(&T) -> bool) -> (Self, Self) {
+        // for each half, as in `filter`: the elements it has so far, how
+        // many of them go before the shared nodes, and where those start
+        let mut halves = [(Vec::new(), 0, &self.head), (Vec::new(), 0, &self.head)];
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref() {
+            let side = usize::from(!f(&node.elem));
+            halves[side].0.push(&node.elem);
+            let other = &mut halves[1 - side];
+            other.1 = other.0.len();
+            other.2 = &node.next;
+            cur = &node.next;
+        }
+        let [yes, no] =
+            halves.map(|(kept, prefix, shared)| Self::cons_prefix(&kept[..prefix], shared));
+        (yes, no)
+    }
+
+    /// Splits the list into runs of neighbouring elements, starting a
+    /// new run between `a` and `b` wherever `pred(a, b)` returns
+    /// `false`. This is [`slice::chunk_by`] for lists.
+    ///
+    /// It takes one pass over the list. The elements of every run but
+    /// the last are cloned, and the last run shares its nodes with
+    /// `self`.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let chars = "ab12c".chars().collect::<List<char>>();
+    /// let tokens = chars.chunk_by(|a, b| a.is_ascii_digit() == b.is_ascii_digit());
+    /// assert_eq!(tokens, list![list!['a', 'b'], list!['1', '2'], list!['c']]);
+    /// ```
+    pub fn chunk_by(&self, mut pred: impl FnMut(&T, &T) -> bool) -> GenericList<Self, P> {
+        let mut chunks = Vec::new();
+        let mut chunk = Vec::new();
+        let mut start = &self.head;
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref() {
+            chunk.push(&node.elem);
+            if let Some(next) = node.next.as_deref() {
+                if !pred(&node.elem, &next.elem) {
+                    chunks.push(Self::cons_prefix(&chunk, &Link::nil()));
+                    chunk.clear();
+                    start = &node.next;
+                }
+            }
+            cur = &node.next;
+        }
+        if start.is_some() {
+            chunks.push(Self {
+                head: start.clone(),
+            });
+        }
+        GenericList::from_vec(chunks)
+    }
+
+    /// Returns a new list with `sep` between each pair of neighbouring
+    /// elements. The last node is shared with `self`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].intersperse(0), list![1, 0, 2, 0, 3]);
+    /// assert_eq!(list![1].intersperse(0), list![1]);
+    /// ```
+    #[must_use]
+    pub fn intersperse(&self, sep: T) -> Self {
+        let mut elems = Vec::new();
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref().filter(|node| node.next.is_some()) {
+            elems.extend([&node.elem, &sep]);
+            cur = &node.next;
+        }
+        Self::cons_prefix(&elems, cur)
+    }
+
+    /// Returns a new list of the first `n` elements, or the whole list if
+    /// it has fewer than `n`. This is SML's `take`.
+    ///
+    /// The elements are cloned, except when the whole list is taken, in
+    /// which case it is shared.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// assert_eq!(xs.take(2), list![1, 2]);
+    /// assert_eq!(xs.take(5), xs);
+    /// ```
+    #[must_use]
+    pub fn take(&self, n: usize) -> Self {
+        self.split_at(n).0
+    }
+
+    /// Splits the list into its first `n` elements and the rest, as
+    /// [`take`](Self::take) and [`drop`](Self::drop) would. The rest is
+    /// shared with `self`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let (front, back) = list![1, 2, 3].split_at(1);
+    /// assert_eq!(front, list![1]);
+    /// assert_eq!(back, list![2, 3]);
+    /// ```
+    pub fn split_at(&self, n: usize) -> (Self, Self) {
+        if n >= self.len() {
+            return (self.clone(), Self::new());
+        }
+        (self.iter().take(n).cloned().collect(), self.drop(n))
+    }
+
+    /// Returns a new list of the longest prefix of elements for which
+    /// `f` returns `true`. If that is every element, the list is shared
+    /// rather than copied.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3, 1];
+    /// assert_eq!(xs.take_while(|&x| x < 3), list![1, 2]);
+    /// ```
+    #[must_use]
+    pub fn take_while(&self, mut f: impl FnMut(&T) -> bool) -> Self {
+        let mut n = 0;
+        let back = self.drop_while(|x| {
+            n += 1;
+            f(x)
+        });
+        if back.is_empty() {
+            return self.clone();
+        }
+        self.take(n - 1)
+    }
+
+    /// Returns a new list of pairs of elements from `self` and `other`,
+    /// stopping at the end of the shorter list. This is SML's
+    /// `ListPair.zip`; use [`zip_eq`](Self::zip_eq) to reject lists of
+    /// different lengths instead.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2, 3];
+    /// let ys = list!['a', 'b'];
+    /// assert_eq!(xs.zip(&ys), list![(1, 'a'), (2, 'b')]);
+    /// ```
+    pub fn zip<U: Clone>(&self, other: &GenericList<U, P>) -> GenericList<(T, U), P> {
+        self.zip_with(other, |x, y| (x.clone(), y.clone()))
+    }
+
+    /// Like [`zip`](Self::zip), but returns an error if the lists have
+    /// different lengths. This is SML's `ListPair.zipEq`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// # use cons::list::UnequalLengths;
+    /// assert_eq!(list![1, 2].zip_eq(&list!['a', 'b']), Ok(list![(1, 'a'), (2, 'b')]));
+    /// assert_eq!(list![1, 2].zip_eq(&list!['a']), Err(UnequalLengths));
+    /// ```
+    pub fn zip_eq<U: Clone>(
+        &self,
+        other: &GenericList<U, P>,
+    ) -> Result<GenericList<(T, U), P>, UnequalLengths> {
+        self.zip_with_eq(other, |x, y| (x.clone(), y.clone()))
+    }
+
+    /// Sorts the list in place. The sort is stable, so equal elements
+    /// keep their order.
+    ///
+    /// This is a bottom-up merge sort that relinks the existing nodes
+    /// rather than copying the elements out, so it takes O(n log n) time
+    /// and no extra memory besides a fixed-size array on the stack. As
+    /// with [`iter_mut`](Self::iter_mut), nodes that are shared with
+    /// another list are cloned first, and the other list is unchanged.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![3, 1, 2];
+    /// let mut ys = xs.clone();
+    /// ys.sort();
+    /// assert_eq!(ys, list![1, 2, 3]);
+    /// assert_eq!(xs, list![3, 1, 2]);
+    /// ```
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    /// Sorts the list in place with a comparison function. See
+    /// [`sort`](Self::sort).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let mut xs = list![1, 3, 2];
+    /// xs.sort_by(|a, b| b.cmp(a));
+    /// assert_eq!(xs, list![3, 2, 1]);
+    /// ```
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&T, &T) -> Ordering) {
+        // `runs[i]` is either empty or a sorted run of 2^i nodes, and
+        // holds elements from earlier in the list than `runs[i - 1]`
+        let mut runs: [Link<T, P>; usize::BITS as usize] = core::array::from_fn(|_| Link::nil());
+        let len = self.len();
+        let mut next = self.head.take();
+        while next.is_some() {
+            let mut node = next;
+            next = node.make_mut().unwrap().next.take();
+            let mut run = node;
+            for slot in &mut runs {
+                if slot.is_some() {
+                    run = Self::merge(slot.take(), run, &mut compare);
+                } else {
+                    *slot = run;
+                    break;
+                }
+            }
+        }
+        self.head = runs
+            .into_iter()
+            .fold(Link::nil(), |later, earlier| Self::merge(earlier, later, &mut compare));
+        self.set_lens(len, 0);
+    }
+
+    /// Sorts the list in place by the key `f` returns for each element.
+    /// See [`sort`](Self::sort).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let mut words = list!["ccc", "a", "bb", "d"];
+    /// words.sort_by_key(|s| s.len());
+    /// assert_eq!(words, list!["a", "d", "bb", "ccc"]);
+    /// ```
+    pub fn sort_by_key<K: Ord>(&mut self, mut f: impl FnMut(&T) -> K) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Merges two sorted chains of unshared nodes, taking from `left`
+    /// when elements are equal.
+    fn merge(
+        mut left: Link<T, P>,
+        mut right: Link<T, P>,
+        compare: &mut impl FnMut(&T, &T) -> Ordering,
+    ) -> Link<T, P> {
+        let mut head = Link::nil();
+        let mut tail = &mut head;
+        while let (Some(l), Some(r)) = (left.as_deref(), right.as_deref()) {
+            let from = if compare(&r.elem, &l.elem) == Ordering::Less {
+                &mut right
+            } else {
+                &mut left
+            };
+            let mut node = from.take();
+            *from = node.get_mut().unwrap().next.take();
+            *tail = node;
+            tail = &mut tail.get_mut().unwrap().next;
+        }
+        *tail = if left.is_some() { left } else { right };
+        head
+    }
+
+    /// Returns a new list with the elements of `self` followed by the
+    /// elements of `other`. This is SML's `self @ other`, and is also
+    /// available as the `+` operator.
+    ///
+    /// The elements of `self` are cloned, and `other` is shared, so this
+    /// is O(n) in the length of `self` only. Appending to an empty list
+    /// or appending an empty list doesn't copy anything.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2];
+    /// let ys = list![3, 4];
+    /// assert_eq!(xs.append(&ys), list![1, 2, 3, 4]);
+    /// assert_eq!(&xs + &ys, list![1, 2, 3, 4]);
+    /// ```
+    #[must_use]
+    pub fn append(&self, other: &Self) -> Self {
+        if other.is_empty() {
+            return self.clone();
+        }
+        let mut list = Self::from_iter(self.iter().cloned());
+        let mut cur = &mut list.head;
+        while cur.is_some() {
+            cur = &mut cur.get_mut().unwrap().next;
+        }
+        *cur = other.head.clone();
+        list.set_lens(self.len(), other.len());
+        list
+    }
+
+    /// Returns a new list with the elements in reverse order. This is
+    /// SML's `rev`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].rev(), list![3, 2, 1]);
+    /// ```
+    #[must_use]
+    pub fn rev(&self) -> Self {
+        self.fold_left(Self::new(), |list, x| list.cons(x.clone()))
+    }
+
+    /// Returns a [`Zipper`] focused on the first element, or `None` if
+    /// the list is empty.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let mut z = list![1, 2, 3].zipper().unwrap();
+    /// z.right();
+    /// z.set(20);
+    /// assert_eq!(z.rebuild(), list![1, 20, 3]);
+    /// ```
+    pub fn zipper(&self) -> Option<Zipper<T, P>> {
+        let mut suffix = self.clone();
+        let focus = suffix.pop()?;
+        Some(Zipper {
+            prefix: Self::new(),
+            focus,
+            suffix,
+        })
+    }
+
+    /// Moves the elements into a vector, in order.
+    ///
+    /// The vector is allocated once, with the list's length, and
+    /// elements are moved out of nodes that aren't shared with another
+    /// list. Once a shared node is reached, the rest of the list is
+    /// cloned without touching any reference counts.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let xs = list![String::from("a"), String::from("b")];
+    /// assert_eq!(xs.into_vec(), ["a", "b"]);
+    ///
+    /// let shared = list![2, 3];
+    /// let ys = shared.cons(1);
+    /// assert_eq!(ys.into_vec(), [1, 2, 3]);
+    /// assert_eq!(shared, list![2, 3]);
+    /// ```
+    pub fn into_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(node) = self.head.take().into_pointer() {
+            match P::try_unwrap(node) {
+                Ok(node) => {
+                    vec.push(node.elem);
+                    self.head = node.next;
+                }
+                Err(node) => {
+                    self.head = Link::from(Some(node));
+                    for elem in self.iter() {
+                        vec.push(elem.clone());
+                    }
+                    break;
+                }
+            }
+        }
+        vec
+    }
+
+    /// Removes the first element, moving it out of the node if it isn't
+    /// shared and cloning it otherwise.
+    fn pop(&mut self) -> Option<T> {
+        self.head.take().into_pointer().map(|node| match P::try_unwrap(node) {
+            Ok(node) => {
+                self.head = node.next;
+                node.elem
+            }
+            Err(node) => {
+                self.head = node.next.clone();
+                node.elem.clone()
+            }
+        })
+    }
+}
+
+impl<A: Clone, B: Clone, P: PointerKind> GenericList<(A, B), P> {
+    /// Splits a list of pairs into a list of the first elements and a
+    /// list of the second elements. This is SML's `ListPair.unzip`.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let (xs, ys) = list![(1, 'a'), (2, 'b')].unzip();
+    /// assert_eq!(xs, list![1, 2]);
+    /// assert_eq!(ys, list!['a', 'b']);
+    /// ```
+    pub fn unzip(&self) -> (GenericList<A, P>, GenericList<B, P>) {
+        (self.map(|(a, _)| a.clone()), self.map(|(_, b)| b.clone()))
+    }
+}
+
+impl<T: Clone, P: PointerKind> GenericList<GenericList<T, P>, P> {
+    /// Joins a list of lists end to end. This is SML's `List.concat`.
+    ///
+    /// The last non-empty list is shared rather than copied, like the
+    /// last argument of [`append!`](crate::append!).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let tail = list![4, 5];
+    /// let xs = list![list![1, 2], list![3], tail.clone(), list![]].concat();
+    /// assert_eq!(xs, list![1, 2, 3, 4, 5]);
+    /// assert!(std::ptr::eq(xs.get(3).unwrap(), tail.head().unwrap()));
+    /// ```
+    pub fn concat(&self) -> GenericList<T, P> {
+        let lists = self.iter().collect::<Vec<_>>();
+        let Some(last) = lists.iter().rposition(|xs| !xs.is_empty()) else {
+            return GenericList::new();
+        };
+        let elems = lists[..last].iter().flat_map(|xs| xs.iter()).collect::<Vec<_>>();
+        GenericList::cons_prefix(&elems, &lists[last].head)
+    }
+
+    /// Joins a list of lists end to end with `sep` between each pair of
+    /// them. This is SML's `String.concatWith`, for lists of any
+    /// elements, and the same as `self.intersperse(sep).concat()`.
+    ///
+    /// The last list is shared rather than copied.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let words = list![list!['a', 'b'], list!['c'], list!['d']];
+    /// assert_eq!(words.join(&list![',', ' ']), list!['a', 'b', ',', ' ', 'c', ',', ' ', 'd']);
+    /// ```
+    pub fn join(&self, sep: &GenericList<T, P>) -> GenericList<T, P> {
+        let mut elems = Vec::new();
+        let mut cur = &self.head;
+        while let Some(node) = cur.as_deref().filter(|node| node.next.is_some()) {
+            elems.extend(node.elem.iter().chain(sep));
+            cur = &node.next;
+        }
+        match cur.as_deref() {
+            Some(node) => GenericList::cons_prefix(&elems, &node.elem.head),
+            None => GenericList::cons_prefix(&elems, &Link::nil()),
+        }
+    }
+}
+
+/// Lists as sets.
+///
+/// These treat a list as a set of its elements, as SML exercises often
+/// do, comparing each element of one list against the whole of the
+/// other, so they are O(n·m). With the `std` feature, the `_hashed`
+/// versions do the same in O(n + m) for elements that are
+/// [`Hash`] + [`Eq`].
+///
+/// Duplicates in `self` are kept as they are, so the results are only
+/// sets if `self` is.
+impl<T: PartialEq, P: PointerKind> GenericList<T, P> {
+    /// Returns `true` if the list has an element equal to `x`. This is
+    /// O(n).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert!(list![1, 2, 3].contains(&2));
+    /// assert!(!list![1, 2, 3].contains(&4));
+    /// ```
+    pub fn contains(&self, x: &T) -> bool {
+        self.iter().any(|y| y == x)
+    }
+}
+
+impl<T: PartialEq + Clone, P: PointerKind> GenericList<T, P> {
+    /// Returns the elements of `self` that aren't in `other`, followed
+    /// by `other`, which is shared rather than copied.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].union(&list![3, 4]), list![1, 2, 3, 4]);
+    /// ```
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        self.difference(other).append(other)
+    }
+
+    /// Returns the elements of `self` that are also in `other`, in
+    /// order.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3, 4].intersection(&list![4, 2]), list![2, 4]);
+    /// ```
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.filter(|x| other.contains(x))
+    }
+
+    /// Returns the elements of `self` that aren't in `other`, in order.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3, 4].difference(&list![4, 2]), list![1, 3]);
+    /// ```
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.filter(|x| !other.contains(x))
+    }
+}
+
+/// The set operations with a [`HashSet`] of the elements of `other`,
+/// for when the lists are too long for the O(n·m) versions.
+#[cfg(feature = "std")]
+impl<T: Hash + Eq + Clone, P: PointerKind> GenericList<T, P> {
+    /// Like [`union`](GenericList::union), but O(n + m).
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs = (0..10_000).collect::<List<u32>>();
+    /// let ys = (5_000..20_000).collect::<List<u32>>();
+    /// assert_eq!(xs.union_hashed(&ys).len(), 20_000);
+    /// ```
+    #[must_use]
+    pub fn union_hashed(&self, other: &Self) -> Self {
+        self.difference_hashed(other).append(other)
+    }
+
+    /// Like [`intersection`](GenericList::intersection), but O(n + m).
+    #[must_use]
+    pub fn intersection_hashed(&self, other: &Self) -> Self {
+        let set = other.iter().collect::<HashSet<_>>();
+        self.filter(|x| set.contains(x))
+    }
+
+    /// Like [`difference`](GenericList::difference), but O(n + m).
+    #[must_use]
+    pub fn difference_hashed(&self, other: &Self) -> Self {
+        let set = other.iter().collect::<HashSet<_>>();
+        self.filter(|x| !set.contains(x))
+    }
+}
+
+/// The error returned by [`List::zip_eq`], [`List::zip_with_eq`], and
+/// the `_eq` functions in [`list_pair`](crate::list_pair) when the
+/// lists have different lengths. This is SML's
+/// `ListPair.UnequalLengths`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnequalLengths;
+
+impl fmt::Display for UnequalLengths {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("lists have different lengths")
+    }
+}
+
+impl core::error::Error for UnequalLengths {}
+
+impl<T, P: PointerKind> Clone for GenericList<T, P> {
+    /// Cloning a list is O(1); only the reference count of the first
+    /// node is incremented.
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<T, P: PointerKind> Drop for GenericList<T, P> {
+    /// Drops the nodes one at a time rather than recursively, so long
+    /// lists can't overflow the stack. Stops at the first node that is
+    /// still shared with another list.
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(node) = next.into_pointer().and_then(P::into_inner) {
+            next = node.next;
+        }
+    }
+}
+
+impl<T, P: PointerKind> Default for GenericList<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for GenericList<T, P> {
+    /// Formats the list as `[1, 2, 3]`, or in SML's cons syntax,
+    /// `1 :: 2 :: 3 :: nil`, with the alternate flag (`{:#?}`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.truncated(usize::MAX), f)
+    }
+}
+
+impl<T: fmt::Display, P: PointerKind> fmt::Display for GenericList<T, P> {
+    /// Formats the list as `[1, 2, 3]`, the way SML prints lists. As
+    /// with `Debug`, the alternate flag (`{:#}`) uses the cons syntax.
+    /// The flags, such as a precision, are passed on to each element.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].to_string(), "[1, 2, 3]");
+    /// assert_eq!(format!("{:#}", list![1, 2]), "1 :: 2 :: nil");
+    /// assert_eq!(format!("{:#?}", list!["a", "b"]), r#""a" :: "b" :: nil"#);
+    /// assert_eq!(format!("{:.1}", list![1.0, 2.25]), "[1.0, 2.2]");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.truncated(usize::MAX), f)
+    }
+}
+
+impl<T, P: PointerKind> GenericList<T, P> {
+    /// Returns a wrapper that formats at most `max` elements of the
+    /// list, followed by `...` if there are more. It has the same
+    /// [`Display`](fmt::Display) and [`Debug`](fmt::Debug) formats as
+    /// the list itself.
+    ///
+    /// ```rust
+    /// # use cons::List;
+    /// let xs = (1..=1000).collect::<List<_>>();
+    /// assert_eq!(xs.truncated(3).to_string(), "[1, 2, 3, ...]");
+    /// assert_eq!(format!("{:#?}", xs.truncated(2)), "1 :: 2 :: ...");
+    /// ```
+    pub fn truncated(&self, max: usize) -> Truncated<'_, T, P> {
+        Truncated { list: self, max }
+    }
+}
+
+/// Formats the first few elements of a [`List`].
+///
+/// Created by [`List::truncated`].
+pub struct Truncated<'a, T, P: PointerKind = RcKind> {
+    list: &'a GenericList<T, P>,
+    max: usize,
+}
+
+impl<T, P: PointerKind> Truncated<'_, T, P> {
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        mut write_elem: impl FnMut(&T, &mut fmt::Formatter<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        let sml = f.alternate();
+        if !sml {
+            f.write_str("[")?;
+        }
+        for (i, x) in self.list.iter().enumerate() {
+            if i > 0 && !sml {
+                f.write_str(", ")?;
+            }
+            if i == self.max {
+                return f.write_str(if sml { "..." } else { "...]" });
+            }
+            write_elem(x, f)?;
+            if sml {
+                f.write_str(" :: ")?;
+            }
+        }
+        f.write_str(if sml { "nil" } else { "]" })
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for Truncated<'_, T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, fmt::Debug::fmt)
+    }
+}
+
+impl<T: fmt::Display, P: PointerKind> fmt::Display for Truncated<'_, T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, fmt::Display::fmt)
+    }
+}
+
+impl<T, P: PointerKind> FromIterator<T> for GenericList<T, P> {
+    /// Builds a list with the elements in iteration order.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        let mut cur = &mut list.head;
+        let mut n = 0;
+        for elem in iter {
+            let node = cur.insert(Node {
+                elem,
+                len: 0,
+                next: Link::nil(),
+            });
+            cur = &mut node.next;
+            n += 1;
+        }
+        list.set_lens(n, 0);
+        list
+    }
+}
+
+/// Moves the elements into a list in one pass, without collecting them
+/// anywhere first.
+///
+/// ```rust
+/// # use cons::{list, List};
+/// use std::collections::LinkedList;
+///
+/// let ll = LinkedList::from([1, 2, 3]);
+/// assert_eq!(List::from(ll), list![1, 2, 3]);
+/// ```
+impl<T, P: PointerKind> From<LinkedList<T>> for GenericList<T, P> {
+    fn from(list: LinkedList<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+/// Moves the elements into a list in one pass, front to back.
+impl<T, P: PointerKind> From<VecDeque<T>> for GenericList<T, P> {
+    fn from(deque: VecDeque<T>) -> Self {
+        deque.into_iter().collect()
+    }
+}
+
+/// Moves the elements of a vector into a list, in order, with
+/// [`from_vec`](GenericList::from_vec).
+///
+/// ```rust
+/// # use cons::{list, List};
+/// assert_eq!(List::from(vec![1, 2, 3]), list![1, 2, 3]);
+/// ```
+impl<T, P: PointerKind> From<Vec<T>> for GenericList<T, P> {
+    fn from(vec: Vec<T>) -> Self {
+        Self::from_vec(vec)
+    }
+}
+
+/// Converts a list to a [`Vec`] with
+/// [`into_vec`](GenericList::into_vec), allocating it once at the
+/// list's length.
+///
+/// ```rust
+/// # use cons::list;
+/// assert_eq!(Vec::from(list![1, 2, 3]), [1, 2, 3]);
+/// ```
+impl<T: Clone, P: PointerKind> From<GenericList<T, P>> for Vec<T> {
+    fn from(list: GenericList<T, P>) -> Self {
+        list.into_vec()
+    }
+}
+
+/// Converts a list to a [`LinkedList`] in one pass. Elements are moved
+/// out of nodes that aren't shared with another list, and cloned out of
+/// those that are, as with [`into_iter`](IntoIterator::into_iter).
+///
+/// ```rust
+/// # use cons::list;
+/// use std::collections::LinkedList;
+///
+/// let ll = LinkedList::from(list![1, 2, 3]);
+/// assert!(ll.into_iter().eq([1, 2, 3]));
+/// ```
+impl<T: Clone, P: PointerKind> From<GenericList<T, P>> for LinkedList<T> {
+    fn from(list: GenericList<T, P>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+/// Converts a list to a [`VecDeque`] in one pass, moving or cloning
+/// the elements as for [`LinkedList`].
+impl<T: Clone, P: PointerKind> From<GenericList<T, P>> for VecDeque<T> {
+    fn from(list: GenericList<T, P>) -> Self {
+        let mut deque = VecDeque::with_capacity(list.len());
+        deque.extend(list);
+        deque
+    }
+}
+
+impl<T: FromStr, P: PointerKind> FromStr for GenericList<T, P> {
+    type Err = ParseListError<T::Err>;
+
+    /// Parses a list written as an SML list literal, either `[1, 2, 3]`
+    /// or `1 :: 2 :: 3 :: nil`. Each element is parsed with its own
+    /// `FromStr` impl, after trimming whitespace.
+    ///
+    /// The input is split at every `,` or `::`, so elements can't
+    /// contain those themselves.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// # use cons::list::ParseListError;
+    /// assert_eq!("[1, 2, 3]".parse::<List<i32>>(), Ok(list![1, 2, 3]));
+    /// assert_eq!("1::2::nil".parse::<List<i32>>(), Ok(list![1, 2]));
+    /// assert_eq!("[]".parse::<List<i32>>(), Ok(list![]));
+    /// assert_eq!("nil".parse::<List<i32>>(), Ok(list![]));
+    ///
+    /// let err = "[1, x]".parse::<List<i32>>().unwrap_err();
+    /// assert!(matches!(err, ParseListError::Element { position: 4, .. }));
+    /// assert_eq!(err.to_string(), "invalid element at position 4: invalid digit found in string");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let offset = s.len() - s.trim_start().len();
+        let body = s.trim();
+        if let Some(inner) = body.strip_prefix('[') {
+            let Some(inner) = inner.strip_suffix(']') else {
+                return Err(ParseListError::Syntax {
+                    position: offset + body.len(),
+                    expected: "`]`",
+                });
+            };
+            if inner.trim().is_empty() {
+                return Ok(Self::new());
+            }
+            return parse_elems(inner, offset + 1, ",");
+        }
+        if body == "nil" {
+            return Ok(Self::new());
+        }
+        match body.rsplit_once("::") {
+            Some((elems, last)) if last.trim() == "nil" => parse_elems(elems, offset, "::"),
+            Some((elems, last)) => Err(ParseListError::Syntax {
+                position: offset + elems.len() + 2 + (last.len() - last.trim_start().len()),
+                expected: "`nil`",
+            }),
+            None => Err(ParseListError::Syntax {
+                position: offset,
+                expected: "`[` or `nil`",
+            }),
+        }
+    }
+}
+
+/// Parses the elements of `s`, separated by `sep`. `offset` is where
+/// `s` starts in the original input.
+fn parse_elems<T: FromStr, P: PointerKind>(
+    s: &str,
+    offset: usize,
+    sep: &str,
+) -> Result<GenericList<T, P>, ParseListError<T::Err>> {
+    let mut start = offset;
+    s.split(sep)
+        .map(|elem| {
+            let position = start + (elem.len() - elem.trim_start().len());
+            start += elem.len() + sep.len();
+            let elem = elem.trim();
+            if elem.is_empty() {
+                return Err(ParseListError::Syntax {
+                    position,
+                    expected: "an element",
+                });
+            }
+            elem.parse()
+                .map_err(|error| ParseListError::Element { position, error })
+        })
+        .collect()
+}
+
+/// The error returned when parsing a [`List`] from a string fails.
+/// Positions are byte offsets into the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseListError<E> {
+    /// The input wasn't a list literal.
+    Syntax {
+        /// Where the unexpected input starts.
+        position: usize,
+        /// What was expected there instead.
+        expected: &'static str,
+    },
+    /// An element couldn't be parsed.
+    Element {
+        /// Where the element starts.
+        position: usize,
+        /// The error from parsing the element.
+        error: E,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for ParseListError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { position, expected } => {
+                write!(f, "expected {expected} at position {position}")
+            }
+            Self::Element { position, error } => {
+                write!(f, "invalid element at position {position}: {error}")
+            }
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for ParseListError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Syntax { .. } => None,
+            Self::Element { error, .. } => Some(error),
+        }
+    }
+}
+
+impl<T: Clone, P: PointerKind> Extend<T> for GenericList<T, P> {
+    /// Appends the elements to the end of the list.
+    ///
+    /// Nodes that are shared with another list are cloned first, so
+    /// other lists are unaffected.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let xs = list![1, 2];
+    /// let mut ys = xs.clone();
+    /// ys.extend([3, 4]);
+    /// assert_eq!(ys, list![1, 2, 3, 4]);
+    /// assert_eq!(xs, list![1, 2]);
+    /// ```
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let rest = Self::from_iter(iter);
+        let n = self.len();
+        let mut cur = &mut self.head;
+        while cur.is_some() {
+            cur = &mut cur.make_mut().unwrap().next;
+        }
+        *cur = rest.head.clone();
+        self.set_lens(n, rest.len());
+    }
+}
+
+impl<T: Clone, P: PointerKind> Clone for Node<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            elem: self.elem.clone(),
+            len: self.len,
+            next: self.next.clone(),
+        }
+    }
+}
+
+impl<T: Clone, P: PointerKind> Add for GenericList<T, P> {
+    type Output = Self;
+
+    /// Same as [`append`](GenericList::append).
+    fn add(self, other: Self) -> Self {
+        self.append(&other)
+    }
+}
+
+impl<T: Clone, P: PointerKind> Add for &GenericList<T, P> {
+    type Output = GenericList<T, P>;
+
+    /// Same as [`append`](GenericList::append).
+    fn add(self, other: Self) -> GenericList<T, P> {
+        self.append(other)
+    }
+}
+
+impl<T, P: PointerKind> Index<usize> for GenericList<T, P> {
+    type Output = T;
+
+    /// Returns the element at index `n`, walking the list to get there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list has `n` or fewer elements.
+    fn index(&self, n: usize) -> &T {
+        match self.get(n) {
+            Some(x) => x,
+            None => panic!(
+                "index out of bounds: the len is {} but the index is {n}",
+                self.len()
+            ),
+        }
+    }
+}
+
+impl<T: PartialEq, P: PointerKind> PartialEq for GenericList<T, P> {
+    /// Compares the lists element by element, stopping early if they
+    /// reach a node they share, since everything after it is the same.
+    /// This makes comparing lists built on a common tail fast, but also
+    /// means that a shared element is always considered equal to itself,
+    /// even a NaN.
+    fn eq(&self, other: &Self) -> bool {
+        Unshared::new(self, other).all(|pair| matches!(pair, (Some(x), Some(y)) if x == y))
+    }
+}
+
+impl<T: Eq, P: PointerKind> Eq for GenericList<T, P> {}
+
+impl<T: PartialOrd, P: PointerKind> PartialOrd for GenericList<T, P> {
+    /// Compares the lists lexicographically, with the same shortcut for
+    /// shared nodes as [`eq`](PartialEq::eq).
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        for pair in Unshared::new(self, other) {
+            match pair {
+                (Some(x), Some(y)) => match x.partial_cmp(y)? {
+                    Ordering::Equal => {}
+                    ordering => return Some(ordering),
+                },
+                (None, _) => return Some(Ordering::Less),
+                (_, None) => return Some(Ordering::Greater),
+            }
+        }
+        Some(Ordering::Equal)
+    }
+}
+
+impl<T: Ord, P: PointerKind> Ord for GenericList<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for pair in Unshared::new(self, other) {
+            match pair {
+                (Some(x), Some(y)) => match x.cmp(y) {
+                    Ordering::Equal => {}
+                    ordering => return ordering,
+                },
+                (None, _) => return Ordering::Less,
+                (_, None) => return Ordering::Greater,
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl<T: Hash, P: PointerKind> Hash for GenericList<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut len = 0;
+        for x in self {
+            x.hash(state);
+            len += 1;
+        }
+        state.write_usize(len);
+    }
+}
+
+/// Walks two lists side by side, until both have ended or they reach
+/// the same node.
+struct Unshared<'a, T, P: PointerKind> {
+    a: Option<&'a Node<T, P>>,
+    b: Option<&'a Node<T, P>>,
+}
+
+impl<'a, T, P: PointerKind> Unshared<'a, T, P> {
+    fn new(a: &'a GenericList<T, P>, b: &'a GenericList<T, P>) -> Self {
+        Self {
+            a: a.head.as_deref(),
+            b: b.head.as_deref(),
+        }
+    }
+}
+
+impl<'a, T, P: PointerKind> Iterator for Unshared<'a, T, P> {
+    type Item = (Option<&'a T>, Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.a, self.b) {
+            (None, None) => None,
+            (Some(a), Some(b)) if ptr::eq(a, b) => None,
+            (a, b) => {
+                self.a = a.and_then(|node| node.next.as_deref());
+                self.b = b.and_then(|node| node.next.as_deref());
+                Some((a.map(|node| &node.elem), b.map(|node| &node.elem)))
+            }
+        }
+    }
+}
+
+impl<'a, T, P: PointerKind> IntoIterator for &'a GenericList<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Clone, P: PointerKind> IntoIterator for &'a mut GenericList<T, P> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone, P: PointerKind> IntoIterator for GenericList<T, P> {
+    type Item = T;
+    type IntoIter = IntoIter<T, P>;
+
+    /// Turns the list into an iterator over its elements.
+    ///
+    /// Elements are moved out of nodes that aren't shared with another
+    /// list, and cloned out of those that are.
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            list: self,
+            back: vec::IntoIter::default(),
+        }
+    }
+}
+
+/// A [`List`] split into its head and tail, or `Nil` if it is empty.
+/// This mirrors SML's `nil` and `x :: xs` patterns.
+///
+/// Created by [`List::view`]. The tail shares its nodes with the
+/// original list, as with [`List::tail`].
+pub enum ConsView<'a, T, P: PointerKind = RcKind> {
+    /// The empty list.
+    Nil,
+    /// The first element and the rest of the list.
+    Cons(&'a T, GenericList<T, P>),
+}
+
+impl<T, P: PointerKind> Clone for ConsView<'_, T, P> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Nil => Self::Nil,
+            Self::Cons(x, xs) => Self::Cons(x, xs.clone()),
+        }
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for ConsView<'_, T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nil => f.write_str("Nil"),
+            Self::Cons(x, xs) => f.debug_tuple("Cons").field(x).field(xs).finish(),
+        }
+    }
+}
+
+impl<T: PartialEq, P: PointerKind> PartialEq for ConsView<'_, T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Nil, Self::Nil) => true,
+            (Self::Cons(x, xs), Self::Cons(y, ys)) => x == y && xs == ys,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Eq, P: PointerKind> Eq for ConsView<'_, T, P> {}
+
+/// An iterator over references to the elements of a [`List`].
+///
+/// Created by [`List::iter`].
+///
+/// It is double-ended, but a list can only be walked from the front, so
+/// the first call to [`next_back`](DoubleEndedIterator::next_back)
+/// collects the remaining elements into a buffer, which is O(n). Every
+/// call after that, from either end, is O(1).
+///
+/// ```rust
+/// # use cons::list;
+/// let xs = list![1, 2, 3, 4];
+/// let mut it = xs.iter();
+/// assert_eq!((it.next(), it.next_back()), (Some(&1), Some(&4)));
+/// assert!(it.rev().eq([&3, &2]));
+/// ```
+pub struct Iter<'a, T, P: PointerKind = RcKind> {
+    next: Option<&'a Node<T, P>>,
+    // the rest of the elements, once `next_back` has been called
+    back: vec::IntoIter<&'a T>,
+}
+
+impl<'a, T, P: PointerKind> Iterator for Iter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next {
+            Some(node) => {
+                self.next = node.next.as_deref();
+                Some(&node.elem)
+            }
+            None => self.back.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.next.map_or(self.back.len(), |node| node.len);
+        (len, Some(len))
+    }
+}
+
+impl<T, P: PointerKind> DoubleEndedIterator for Iter<'_, T, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next.is_some() {
+            let rest = Self {
+                next: self.next.take(),
+                back: vec::IntoIter::default(),
+            };
+            self.back = rest.collect::<Vec<_>>().into_iter();
+        }
+        self.back.next_back()
+    }
+}
+
+impl<T, P: PointerKind> ExactSizeIterator for Iter<'_, T, P> {}
+
+impl<T, P: PointerKind> FusedIterator for Iter<'_, T, P> {}
+
+impl<T, P: PointerKind> Clone for Iter<'_, T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            back: self.back.clone(),
+        }
+    }
+}
+
+/// A mutable handle to the tail of a [`List`].
+///
+/// Created by [`List::tail_mut`]. It dereferences to the tail, and
+/// puts it back into the list when it is dropped, updating the stored
+/// length of the list to match.
+pub struct TailMut<'a, T, P: PointerKind = RcKind> {
+    node: &'a mut Node<T, P>,
+    tail: GenericList<T, P>,
+}
+
+impl<T, P: PointerKind> Deref for TailMut<'_, T, P> {
+    type Target = GenericList<T, P>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tail
+    }
+}
+
+impl<T, P: PointerKind> DerefMut for TailMut<'_, T, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tail
+    }
+}
+
+impl<T, P: PointerKind> Drop for TailMut<'_, T, P> {
+    fn drop(&mut self) {
+        self.node.len = self.tail.len() + 1;
+        self.node.next = self.tail.head.take();
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for TailMut<'_, T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tail.fmt(f)
+    }
+}
+
+/// An iterator over mutable references to the elements of a [`List`].
+///
+/// Created by [`List::iter_mut`]. Like [`Iter`], the first call to
+/// [`next_back`](DoubleEndedIterator::next_back) collects the remaining
+/// elements into a buffer, which also copies any of their nodes that
+/// are shared.
+pub struct IterMut<'a, T, P: PointerKind = RcKind> {
+    // never `nil`
+    next: Option<&'a mut Link<T, P>>,
+    // the rest of the elements, once `next_back` has been called
+    back: vec::IntoIter<&'a mut T>,
+}
+
+impl<'a, T: Clone + 'a, P: PointerKind> Iterator for IterMut<'a, T, P> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next.take() {
+            Some(link) => {
+                let node = link.make_mut().unwrap();
+                self.next = Some(&mut node.next).filter(|link| link.is_some());
+                Some(&mut node.elem)
+            }
+            None => self.back.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self
+            .next
+            .as_ref()
+            .map_or(self.back.len(), |link| link.len());
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Clone + 'a, P: PointerKind> DoubleEndedIterator for IterMut<'a, T, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next.is_some() {
+            let rest = Self {
+                next: self.next.take(),
+                back: vec::IntoIter::default(),
+            };
+            self.back = rest.collect::<Vec<_>>().into_iter();
+        }
+        self.back.next_back()
+    }
+}
+
+impl<'a, T: Clone + 'a, P: PointerKind> ExactSizeIterator for IterMut<'a, T, P> {}
+
+impl<'a, T: Clone + 'a, P: PointerKind> FusedIterator for IterMut<'a, T, P> {}
+
+/// An owning iterator over the elements of a [`List`].
+///
+/// Created by [`List::into_iter`](IntoIterator::into_iter). Like
+/// [`Iter`], the first call to
+/// [`next_back`](DoubleEndedIterator::next_back) moves the remaining
+/// elements into a buffer, with [`List::into_vec`].
+pub struct IntoIter<T, P: PointerKind = RcKind> {
+    list: GenericList<T, P>,
+    // the rest of the elements, once `next_back` has been called
+    back: vec::IntoIter<T>,
+}
+
+impl<T: Clone, P: PointerKind> Iterator for IntoIter<T, P> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop().or_else(|| self.back.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.list.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl<T: Clone, P: PointerKind> DoubleEndedIterator for IntoIter<T, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if !self.list.is_empty() {
+            self.back = mem::take(&mut self.list).into_vec().into_iter();
+        }
+        self.back.next_back()
+    }
+}
+
+impl<T: Clone, P: PointerKind> ExactSizeIterator for IntoIter<T, P> {}
+
+impl<T: Clone, P: PointerKind> FusedIterator for IntoIter<T, P> {}
+
+/// A position in a [`List`], for walking it back and forth and editing
+/// it along the way.
+///
+/// The zipper keeps the focused element, the elements before it in
+/// reverse order, and the elements after it. Moving one step and
+/// changing the focus are O(1), where editing the `n`th element of a
+/// list directly would copy the first `n` nodes every time. Only
+/// [`rebuild`](Zipper::rebuild) copies the elements before the focus
+/// back into a list. The ones after it are shared with the original
+/// list, as long as the focus was never moved past them:
+///
+/// ```rust
+/// # use cons::list;
+/// // double every even number and drop the multiples of 3
+/// let mut z = list![1, 2, 3, 4, 6, 7].zipper().unwrap();
+/// loop {
+///     if z.focus() % 3 == 0 {
+///         z = z.delete().unwrap();
+///         continue;
+///     }
+///     if z.focus() % 2 == 0 {
+///         let x = *z.focus();
+///         z.set(x * 2);
+///     }
+///     if !z.right() {
+///         break;
+///     }
+/// }
+/// assert_eq!(z.rebuild(), list![1, 4, 8, 7]);
+/// ```
+///
+/// Created by [`List::zipper`](GenericList::zipper).
+pub struct Zipper<T, P: PointerKind = RcKind> {
+    // the elements before the focus, nearest first
+    prefix: GenericList<T, P>,
+    focus: T,
+    suffix: GenericList<T, P>,
+}
+
+impl<T: Clone, P: PointerKind> Zipper<T, P> {
+    /// Returns a reference to the focused element.
+    pub fn focus(&self) -> &T {
+        &self.focus
+    }
+
+    /// Returns a mutable reference to the focused element.
+    pub fn focus_mut(&mut self) -> &mut T {
+        &mut self.focus
+    }
+
+    /// Returns the position of the focus in the list.
+    pub fn index(&self) -> usize {
+        self.prefix.len()
+    }
+
+    /// Moves the focus one element to the left, or returns `false` if
+    /// it is already on the first element.
+    pub fn left(&mut self) -> bool {
+        let Some(x) = self.prefix.pop() else {
+            return false;
+        };
+        let focus = core::mem::replace(&mut self.focus, x);
+        self.suffix = self.suffix.cons(focus);
+        true
+    }
+
+    /// Moves the focus one element to the right, or returns `false` if
+    /// it is already on the last element.
+    pub fn right(&mut self) -> bool {
+        let Some(x) = self.suffix.pop() else {
+            return false;
+        };
+        let focus = core::mem::replace(&mut self.focus, x);
+        self.prefix = self.prefix.cons(focus);
+        true
+    }
+
+    /// Replaces the focused element, returning the old one.
+    pub fn set(&mut self, elem: T) -> T {
+        core::mem::replace(&mut self.focus, elem)
+    }
+
+    /// Inserts `elem` at the focus, moving the focused element one
+    /// place to the right. The new element becomes the focus.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let mut z = list![1, 3].zipper().unwrap();
+    /// z.right();
+    /// z.insert(2);
+    /// assert_eq!(*z.focus(), 2);
+    /// assert_eq!(z.rebuild(), list![1, 2, 3]);
+    /// ```
+    pub fn insert(&mut self, elem: T) {
+        let focus = core::mem::replace(&mut self.focus, elem);
+        self.suffix = self.suffix.cons(focus);
+    }
+
+    /// Removes the focused element. The focus moves to the next
+    /// element, or to the previous one if there is no next one.
+    ///
+    /// Returns `None` if the focused element was the only one, since a
+    /// zipper can't be empty.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let z = list![1, 2].zipper().unwrap();
+    /// let z = z.delete().unwrap();
+    /// assert_eq!(*z.focus(), 2);
+    /// assert!(z.delete().is_none());
+    /// ```
+    #[must_use]
+    pub fn delete(mut self) -> Option<Self> {
+        self.focus = match self.suffix.pop() {
+            Some(x) => x,
+            None => self.prefix.pop()?,
+        };
+        Some(self)
+    }
+
+    /// Turns the zipper back into a list.
+    ///
+    /// This copies the elements before the focus, and shares the ones
+    /// after it.
+    pub fn rebuild(self) -> GenericList<T, P> {
+        let mut list = self.suffix.cons(self.focus);
+        for x in self.prefix {
+            list = list.cons(x);
+        }
+        list
+    }
+}
+
+impl<T: Clone, P: PointerKind> Clone for Zipper<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            focus: self.focus.clone(),
+            suffix: self.suffix.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for Zipper<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Zipper")
+            .field("index", &self.prefix.len())
+            .field("focus", &self.focus)
+            .field("suffix", &self.suffix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_shares_suffix() {
+        let xs = list![1, 2, 3, 4, 5];
+        let odd = xs.filter(|&x| x != 2);
+        assert_eq!(odd, list![1, 3, 4, 5]);
+        let suffix = odd.tail().unwrap();
+        assert!(ptr::eq(suffix.head().unwrap(), xs.iter().nth(2).unwrap()));
+        assert_eq!(xs.filter(|_| false), List::new());
+    }
+
+    #[test]
+    fn test_zipper() {
+        let xs = list![1, 2, 3, 4];
+        let mut z = xs.zipper().unwrap();
+        assert!(!z.left());
+        assert!(z.right());
+        *z.focus_mut() += 10;
+        let rebuilt = z.clone().rebuild();
+        assert_eq!(rebuilt, list![1, 12, 3, 4]);
+        // the suffix the focus never reached is shared
+        let suffix = rebuilt.drop(2);
+        assert!(ptr::eq(suffix.head().unwrap(), xs.drop(2).head().unwrap()));
+
+        while z.right() {}
+        assert_eq!((z.index(), *z.focus()), (3, 4));
+        assert!(z.left() && z.left());
+        assert_eq!(*z.focus(), 12);
+
+        z.insert(0);
+        z = z.delete().unwrap().delete().unwrap();
+        assert_eq!(*z.focus(), 3);
+        while z.right() {}
+        let z = z.delete().unwrap();
+        assert_eq!((z.index(), *z.focus()), (1, 3));
+        assert_eq!(z.rebuild(), list![1, 3]);
+
+        assert!(List::<i32>::new().zipper().is_none());
+        let z = list![1].zipper().unwrap();
+        assert!(z.delete().is_none());
+    }
+
+    #[test]
+    fn test_zipper_long() {
+        let xs = (0..100_000).collect::<List<u32>>();
+        let mut z = xs.zipper().unwrap();
+        while z.right() {
+            if z.index() % 2 == 0 {
+                z.set(0);
+            }
+        }
+        let ys = z.rebuild();
+        assert_eq!(ys.len(), xs.len());
+        assert_eq!(ys.iter().filter(|&&x| x == 0).count(), 50_000);
+    }
+
+    #[test]
+    fn test_append_shares_right() {
+        let xs = list![1, 2];
+        let ys = list![3, 4];
+        let zs = xs.append(&ys);
+        assert_eq!(zs, list![1, 2, 3, 4]);
+        let suffix = zs.tail().unwrap().tail().unwrap();
+        assert!(ptr::eq(suffix.head().unwrap(), ys.head().unwrap()));
+        assert!(ptr::eq(xs.append(&List::new()).head().unwrap(), xs.head().unwrap()));
+        assert_eq!(List::new() + ys.clone(), ys);
+        assert_eq!(append!(xs, List::new(), ys, list![5]), list![1, 2, 3, 4, 5]);
+        assert_eq!(append!(xs), xs);
+
+        let long = (0..1_000_000).collect::<List<u32>>();
+        assert_eq!((&long + &long).iter().count(), 2_000_000);
+    }
+
+    #[test]
+    fn test_combinators() {
+        let xs = list![3, 1, 4, 1, 5];
+        assert_eq!((xs.len(), xs.last(), xs.nth(2)), (5, Some(&5), Some(&4)));
+        assert_eq!(xs.find(|&x| x > 3), Some(&4));
+        assert!(xs.any(|&x| x == 5) && !xs.all(|&x| x > 1));
+        assert_eq!(xs.filter_map(|&x| (x > 1).then(|| x * 2)), list![6, 8, 10]);
+        let (small, big) = xs.partition(|&x| x < 3);
+        assert_eq!((small, big), (list![1, 1], list![3, 4, 5]));
+        let mut sum = 0;
+        xs.for_each(|x| sum += x);
+        assert_eq!(sum, 14);
+        assert_eq!(List::<i32>::new().last(), None);
+    }
+
+    #[test]
+    fn test_partition_and_chunk_by() {
+        let xs = list![1, 2, 3, 4, 6, 8];
+        let mut calls = 0;
+        let (even, odd) = xs.partition(|x| {
+            calls += 1;
+            x % 2 == 0
+        });
+        assert_eq!((even.clone(), odd, calls), (list![2, 4, 6, 8], list![1, 3], 6));
+        // `4, 6, 8` come after the last odd element, so they are shared
+        assert!(ptr::eq(even.get(1).unwrap(), xs.get(3).unwrap()));
+
+        let runs = xs.chunk_by(|a, b| a + 1 == *b);
+        assert_eq!(runs, list![list![1, 2, 3, 4], list![6], list![8]]);
+        assert!(ptr::eq(runs.last().unwrap().head().unwrap(), xs.last().unwrap()));
+        assert_eq!(xs.chunk_by(|_, _| true), list![xs.clone()]);
+        assert!(List::<i32>::new().chunk_by(|_, _| false).is_empty());
+        assert_eq!(list![1].chunk_by(|_, _| false), list![list![1]]);
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let xss = list![list![1, 2], list![], list![3]];
+        let sep = list![0, 0];
+        assert_eq!(xss.concat(), list![1, 2, 3]);
+        assert_eq!(xss.join(&sep), list![1, 2, 0, 0, 0, 0, 3]);
+        assert_eq!(xss.join(&sep), xss.intersperse(sep.clone()).concat());
+        assert!(ptr::eq(xss.join(&sep).last().unwrap(), xss.last().unwrap().head().unwrap()));
+        assert_eq!(list![list![1], List::new()].join(&sep), list![1, 0, 0]);
+        assert_eq!(List::<List<i32>>::new().concat(), List::new());
+        assert_eq!(List::<List<i32>>::new().join(&sep), List::new());
+        assert_eq!(List::<i32>::new().intersperse(0), List::new());
+        let xs = list![1, 2, 3];
+        assert!(ptr::eq(xs.intersperse(0).last().unwrap(), xs.last().unwrap()));
+    }
+
+    #[test]
+    fn test_accumulating_maps() {
+        let xs = list![3, 1, 2];
+        assert_eq!(xs.scan_left(1, |acc, x| acc * x), list![1, 3, 3, 6]);
+        assert_eq!(List::<i32>::new().scan_left(7, |_, _| unreachable!()), list![7]);
+        let digits = xs.scan_left(String::new(), |acc, x| format!("{acc}{x}"));
+        assert_eq!(digits.last().map(String::as_str), Some("312"));
+
+        let mut order = Vec::new();
+        let (n, left) = xs.map_accum_left(0, |i, &x| {
+            order.push(x);
+            (i + 1, x * 10 + i)
+        });
+        assert_eq!((n, left), (3, list![30, 11, 22]));
+        let (n, right) = xs.map_accum_right(0, |i, &x| {
+            order.push(x);
+            (i + 1, x * 10 + i)
+        });
+        assert_eq!((n, right), (3, list![32, 11, 20]));
+        assert_eq!(order, [3, 1, 2, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let xs = list![(2, 'a'), (1, 'b'), (2, 'c'), (0, 'd'), (1, 'e')];
+        let mut ys = xs.clone();
+        ys.sort_by_key(|&(n, _)| n);
+        assert_eq!(ys, list![(0, 'd'), (1, 'b'), (1, 'e'), (2, 'a'), (2, 'c')]);
+        assert_eq!(xs.head(), Some(&(2, 'a')));
+
+        for n in 0..40 {
+            let mut zs = (0..n).map(|i| (i * 7) % 11).collect::<List<_>>();
+            let mut expected = zs.iter().copied().collect::<Vec<_>>();
+            expected.sort();
+            zs.sort();
+            assert_eq!(zs.iter().copied().collect::<Vec<_>>(), expected);
+        }
+
+        let mut long = (0..1_000_000).rev().collect::<List<u32>>();
+        long.sort();
+        assert!(long.iter().copied().eq(0..1_000_000));
+    }
+
+    #[test]
+    fn test_zip() {
+        let xs = list![1, 2, 3];
+        let ys = list!["a", "b", "c", "d"];
+        let pairs = xs.zip(&ys);
+        assert_eq!(pairs, list![(1, "a"), (2, "b"), (3, "c")]);
+        assert_eq!(pairs.unzip(), (xs.clone(), list!["a", "b", "c"]));
+        assert_eq!(xs.zip_eq(&ys), Err(UnequalLengths));
+        assert_eq!(ys.zip_with_eq(&ys, |a, b| a.len() + b.len()), Ok(list![2, 2, 2, 2]));
+        assert_eq!(List::<i32>::new().zip(&ys), List::new());
+        assert_eq!(UnequalLengths.to_string(), "lists have different lengths");
+    }
+
+    #[test]
+    fn test_take_and_drop() {
+        let xs = list![1, 2, 3, 4, 5];
+        let (front, back) = xs.split_at(2);
+        assert_eq!(front, list![1, 2]);
+        assert!(ptr::eq(back.head().unwrap(), xs.nth(2).unwrap()));
+        assert_eq!(xs.split_at(0), (List::new(), xs.clone()));
+        assert!(ptr::eq(xs.take(9).head().unwrap(), xs.head().unwrap()));
+
+        assert_eq!(xs.take_while(|&x| x != 4), list![1, 2, 3]);
+        assert_eq!(xs.take_while(|_| true), xs);
+        assert_eq!(xs.take_while(|_| false), List::new());
+        assert!(ptr::eq(xs.drop_while(|&x| x < 5).head().unwrap(), xs.last().unwrap()));
+        assert_eq!(xs.drop_while(|_| true), List::new());
+
+        let long = (0..1_000_000).collect::<List<u32>>();
+        assert_eq!(long.drop(999_999), list![999_999]);
+    }
+
+    #[test]
+    fn test_index() {
+        let xs = (0..10).collect::<List<_>>();
+        assert_eq!(xs[0], 0);
+        assert_eq!(xs[9], 9);
+        assert_eq!(xs.get(10), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+    fn test_index_out_of_bounds() {
+        let _ = list![1, 2, 3][3];
+    }
+
+    #[test]
+    fn test_comparisons() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |xs: &List<i32>| {
+            let mut h = DefaultHasher::new();
+            xs.hash(&mut h);
+            h.finish()
+        };
+        let xs = list![1, 2, 3];
+        assert!(list![1, 2] < xs && xs < list![1, 3] && xs > List::new());
+        assert_eq!(xs.cmp(&list![1, 2, 3]), Ordering::Equal);
+        assert_eq!(hash(&xs), hash(&list![1, 2, 3]));
+        assert_ne!(hash(&xs), hash(&list![1, 2]));
+        assert_eq!(list![f64::NAN].partial_cmp(&list![1.0]), None);
+        assert_ne!(list![f64::NAN], list![f64::NAN]);
+
+        // the shared tail is never looked at
+        let shared = list![f64::NAN];
+        assert_eq!(shared.cons(1.0), shared.cons(1.0));
+        assert_eq!(shared.cons(1.0).partial_cmp(&shared.cons(1.0)), Some(Ordering::Equal));
+        let long = (0..1_000_000).collect::<List<_>>();
+        assert_eq!(long.cons(1), long.cons(1));
+        assert!(long.cons(1) < long.cons(2));
+    }
+
+    #[test]
+    fn test_len_is_cached() {
+        fn check(xs: &List<i32>) {
+            let mut cur = xs.clone();
+            let mut n = cur.iter().count();
+            assert_eq!(cur.len(), n);
+            while let Some(tl) = cur.tail() {
+                n -= 1;
+                assert_eq!(tl.len(), n);
+                cur = tl;
+            }
+        }
+        let xs = list![5, 3, 4];
+        check(&xs);
+        check(&xs.cons(1));
+        check(&xs.append(&xs));
+        let mut ys = xs.clone();
+        ys.extend([9, 8]);
+        check(&ys);
+        check(&xs);
+        ys.sort();
+        check(&ys);
+        check(&ys.filter(|&x| x != 4));
+        assert_eq!(ys.iter().len(), 5);
+        assert_eq!(ys.clone().into_iter().len(), 5);
+        assert_eq!(ys.iter_mut().skip(1).len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "the pattern needs at least 4 elements, got 3")]
+    fn test_len_checked_up_front_by_cons() {
+        let xs = list![1, 2, 3];
+        crate::cons!(xs as a::b::c::d::_);
+        let _ = (a, b, c, d);
+    }
+
+    #[test]
+    fn test_parse() {
+        type Result = core::result::Result<List<i32>, ParseListError<core::num::ParseIntError>>;
+        let syntax = |position, expected| Err(ParseListError::Syntax { position, expected });
+        assert_eq!(" [ 1 ,2, 3 ] ".parse(), Ok(list![1, 2, 3]) as Result);
+        assert_eq!("1 :: 2::3 :: nil".parse(), Ok(list![1, 2, 3]) as Result);
+        assert_eq!(" [ ] ".parse(), Ok(list![]) as Result);
+        assert_eq!("[1, 2".parse(), syntax(5, "`]`") as Result);
+        assert_eq!("[1, , 2]".parse(), syntax(4, "an element") as Result);
+        assert_eq!("[1,]".parse(), syntax(3, "an element") as Result);
+        assert_eq!("1 :: 2".parse(), syntax(5, "`nil`") as Result);
+        assert_eq!(":: nil".parse(), syntax(0, "an element") as Result);
+        assert_eq!("1".parse(), syntax(0, "`[` or `nil`") as Result);
+        assert_eq!("".parse(), syntax(0, "`[` or `nil`") as Result);
+        let Err(ParseListError::Element { position: 5, .. }) = "1 :: a :: nil".parse() as Result
+        else {
+            panic!()
+        };
+
+        let words = "[a, b c]".parse::<List<String>>().unwrap();
+        assert_eq!(words, list![String::from("a"), String::from("b c")]);
+    }
+
+    #[test]
+    fn test_long_list_helpers() {
+        let xs = (0..1_000_000).collect::<List<u64>>();
+        assert_eq!(xs.fold_left(0, |sum, x| sum + x), 499_999_500_000);
+        let evens = xs.map(|x| x * 2).filter(|x| x % 4 == 0);
+        assert_eq!(evens.iter().count(), 500_000);
+        assert_eq!(evens.rev().head(), Some(&1_999_996));
+    }
+
+    #[test]
+    fn test_view() {
+        fn len<T>(xs: &SyncList<T>) -> usize {
+            match xs.view() {
+                ConsView::Nil => 0,
+                ConsView::Cons(_, xs) => 1 + len(&xs),
+            }
+        }
+        let xs = list![1, 2, 3];
+        assert_eq!(xs.view(), ConsView::Cons(&1, list![2, 3]));
+        assert_eq!(List::<i32>::new().view(), ConsView::Nil);
+        assert_eq!(format!("{:?}", xs.view()), "Cons(1, [2, 3])");
+        assert_eq!(len(&(1..=4).collect()), 4);
+    }
+
+    #[test]
+    fn test_sync_list_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncList<String>>();
+
+        let xs = (1..=100).collect::<SyncList<_>>();
+        let handles = (0..4)
+            .map(|i| {
+                let xs = xs.cons(i);
+                std::thread::spawn(move || xs.iter().sum::<i32>())
+            })
+            .collect::<Vec<_>>();
+        let sums = handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>();
+        assert_eq!(sums, vec![5050, 5051, 5052, 5053]);
+        assert_eq!(xs.head(), Some(&1));
+    }
+
+    #[test]
+    fn test_option_is_pointer_sized() {
+        use core::mem::size_of;
+        assert_eq!(size_of::<List<()>>(), size_of::<usize>());
+        assert_eq!(size_of::<Option<List<()>>>(), size_of::<usize>());
+        assert_eq!(size_of::<Option<List<String>>>(), size_of::<usize>());
+        assert_eq!(size_of::<Option<SyncList<[u8; 100]>>>(), size_of::<usize>());
+
+        // `nil` is not the niche `None` uses
+        let xs: [Option<List<()>>; 3] = [None, Some(List::new()), Some(list![()])];
+        assert_eq!(xs.each_ref().map(|x| x.as_ref().map(List::len)), [None, Some(0), Some(1)]);
+        let mut tails = Some(list![1, 2]);
+        let mut lens = vec![];
+        while let Some(xs) = tails {
+            lens.push(xs.len());
+            tails = xs.tail();
+        }
+        assert_eq!(lens, [2, 1, 0]);
+    }
+
+    #[test]
+    fn test_long_lists() {
+        let n = 1_000_000;
+        let xs = (0..n).collect::<List<_>>();
+        let ys = xs.cons(-1);
+        assert_eq!(ys.tail().unwrap(), xs);
+        assert_eq!(format!("{xs:?}").len(), format!("{:?}", (0..n).collect::<Vec<_>>()).len());
+        drop(xs);
+        assert_eq!(ys.iter().count(), n as usize + 1);
+        drop(ys);
+    }
+
+    #[test]
+    fn test_cons_shares_tail() {
+        let xs = List::new().cons(2).cons(1);
+        let ys = xs.cons(0);
+        let tl = ys.tail().unwrap();
+        assert!(ptr::eq(tl.head().unwrap(), xs.head().unwrap()));
+    }
+
+    #[test]
+    fn test_head_and_tail() {
+        let xs = List::new().cons(3).cons(2).cons(1);
+        assert_eq!(xs.head(), Some(&1));
+        let xs = xs.tail().unwrap();
+        assert_eq!(xs.head(), Some(&2));
+        let xs = xs.tail().unwrap();
+        assert_eq!(xs.head(), Some(&3));
+        let xs = xs.tail().unwrap();
+        assert!(xs.is_empty());
+        assert_eq!(xs.head(), None);
+        assert!(xs.tail().is_none());
+    }
+
+    #[test]
+    fn test_elements_not_cloned() {
+        let s = Rc::new(String::from("hello"));
+        let xs = List::new().cons(Rc::clone(&s));
+        let ys = xs.cons(Rc::clone(&s));
+        let _ = ys.tail();
+        let _ = ys.clone();
+        assert_eq!(Rc::strong_count(&s), 3);
+    }
+
+    #[test]
+    fn test_from_iter_order() {
+        let xs: List<_> = (1..=3).collect();
+        assert_eq!(xs.head(), Some(&1));
+        assert_eq!(xs.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list![1, 2, 3], xs);
+        assert_eq!(list![1, 2, 3,], xs);
+    }
+
+    #[test]
+    fn test_into_iter_moves_unshared() {
+        let s = Rc::new(());
+        let xs = list![Rc::clone(&s), Rc::clone(&s)];
+        let v: Vec<_> = xs.into_iter().collect();
+        assert_eq!(v.len(), 2);
+        assert_eq!(Rc::strong_count(&s), 3);
+    }
+
+    #[test]
+    fn test_into_iter_clones_shared() {
+        let xs = list![String::from("a"), String::from("b")];
+        let ys = xs.cons(String::from("z"));
+        let v: Vec<_> = ys.into_iter().collect();
+        assert_eq!(v, vec!["z", "a", "b"]);
+        assert_eq!(xs, list![String::from("a"), String::from("b")]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let xs = list![1, 2, 3, 4];
+        let ys = list![6, 4, 2];
+        let union = xs.union(&ys);
+        assert_eq!(union, list![1, 3, 6, 4, 2]);
+        assert!(ptr::eq(union.get(2).unwrap(), ys.head().unwrap()));
+        assert_eq!(xs.intersection(&ys), list![2, 4]);
+        assert_eq!(xs.difference(&ys), list![1, 3]);
+        assert_eq!(ys.difference(&xs), list![6]);
+        assert_eq!(xs.union(&List::new()), xs);
+        assert!(List::new().intersection(&xs).is_empty());
+        assert!(xs.contains(&4) && !ys.contains(&1));
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(xs.union_hashed(&ys), xs.union(&ys));
+            assert_eq!(xs.intersection_hashed(&ys), xs.intersection(&ys));
+            assert_eq!(xs.difference_hashed(&ys), xs.difference(&ys));
+        }
+    }
+
+    #[test]
+    fn test_vec_round_trip() {
+        let s = Rc::new(());
+        let xs = List::from_vec(vec![Rc::clone(&s), Rc::clone(&s), Rc::clone(&s)]);
+        assert_eq!((xs.len(), xs.drop(1).len(), xs.drop(2).len()), (3, 2, 1));
+        assert_eq!(Rc::strong_count(&s), 4);
+        let shared = xs.drop(1);
+        let v = xs.into_vec();
+        assert_eq!((v.len(), v.capacity()), (3, 3));
+        // the first element was moved, and the shared ones were cloned
+        assert_eq!(Rc::strong_count(&s), 6);
+        drop(shared);
+        assert_eq!(Rc::strong_count(&s), 4);
+        assert_eq!(Vec::from(List::from(vec![1, 2])), [1, 2]);
+        assert!(List::<i32>::from_vec(Vec::new()).into_vec().is_empty());
+    }
+
+    #[test]
+    fn test_double_ended_iterators() {
+        let xs = (1..=5).collect::<List<i32>>();
+        let mut it = xs.iter();
+        assert_eq!((it.next(), it.len()), (Some(&1), 4));
+        assert_eq!((it.next_back(), it.len()), (Some(&5), 3));
+        assert_eq!(it.clone().collect::<Vec<_>>(), [&2, &3, &4]);
+        assert_eq!((it.next(), it.next_back(), it.next()), (Some(&2), Some(&4), Some(&3)));
+        assert_eq!((it.next(), it.next_back(), it.len()), (None, None, 0));
+
+        let mut ys = xs.tail().unwrap();
+        for (i, y) in ys.iter_mut().rev().enumerate() {
+            *y = i as i32;
+        }
+        assert_eq!((ys, xs.clone()), (list![3, 2, 1, 0], list![1, 2, 3, 4, 5]));
+
+        let mut it = xs.clone().into_iter();
+        assert_eq!((it.next_back(), it.next(), it.len()), (Some(5), Some(1), 3));
+        assert!(it.rev().eq([4, 3, 2]));
+        let s = Rc::new(());
+        let zs = list![Rc::clone(&s), Rc::clone(&s)];
+        assert_eq!(zs.into_iter().rev().count(), 2);
+        assert_eq!(Rc::strong_count(&s), 1);
+    }
+
+    #[test]
+    fn test_iter_mut_copies_shared_nodes() {
+        let xs = list![1, 2, 3];
+        let mut ys = xs.tail().unwrap().cons(0);
+        for y in &mut ys {
+            *y += 1;
+        }
+        assert_eq!(ys, list![1, 3, 4]);
+        assert_eq!(xs, list![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cons_macro_on_list() {
+        let xs = list![1, 2, 3];
+        crate::cons!((&xs) as x::rest);
+        assert_eq!(*x, 1);
+        assert_eq!(rest, vec![&2, &3]);
+        crate::cons!(xs as _::y::_::nil);
+        assert_eq!(y, 2);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut xs = List::new();
+        xs.extend([1, 2]);
+        xs.extend(Vec::new());
+        xs.extend([3]);
+        assert_eq!(xs, list![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_debug() {
+        let xs = List::new().cons(3).cons(2).cons(1);
+        assert_eq!(format!("{xs:?}"), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", List::<i32>::new()), "[]");
+        assert_eq!(format!("{:#?}", List::<i32>::new()), "nil");
+        assert_eq!(format!("{xs:#?}"), "1 :: 2 :: 3 :: nil");
+        // the alternate flag reaches the elements too
+        assert_eq!(format!("{:#}", list![list![1], list![]]), "1 :: nil :: nil :: nil");
+        assert_eq!(list!["a"].to_string(), "[a]");
+        assert_eq!(xs.truncated(0).to_string(), "[...]");
+        assert_eq!(xs.truncated(3).to_string(), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", xs.truncated(1)), "[1, ...]");
+        assert_eq!(format!("{:#}", xs.truncated(0)), "...");
+    }
+
+    #[test]
+    fn test_format_flags_reach_elements() {
+        let xs = list![1.0, 2.5];
+        assert_eq!(format!("{xs:.2}"), "[1.00, 2.50]");
+        assert_eq!(format!("{xs:#.1}"), "1.0 :: 2.5 :: nil");
+        assert_eq!(format!("{:?}", xs.truncated(1)), "[1.0, ...]");
+        assert_eq!(format!("{:>3}", list![1, 2]), "[  1,   2]");
+        assert_eq!(format!("{:#?}", list![(1,)]), "(\n    1,\n) :: nil");
+    }
+
+    #[test]
+    fn test_std_collections() {
+        use std::collections::{LinkedList, VecDeque};
+
+        let deque = VecDeque::from([1, 2, 3]);
+        let xs = List::from(deque.clone());
+        assert_eq!(xs, list![1, 2, 3]);
+        assert_eq!(VecDeque::from(xs.clone()), deque);
+        let ll = LinkedList::from(xs.tail().unwrap());
+        assert!(ll.iter().eq(&[2, 3]));
+        assert_eq!(List::from(ll), list![2, 3]);
+        assert_eq!(xs, list![1, 2, 3]);
+        assert!(VecDeque::from(List::<i32>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let xs = list![1, 2, 3];
+        let mut ys = xs.clone();
+        *ys.make_mut().unwrap() = 10;
+        assert_eq!((&xs, &ys), (&list![1, 2, 3], &list![10, 2, 3]));
+        assert!(ptr::eq(xs.get(1).unwrap(), ys.get(1).unwrap()));
+
+        // not shared, so changed in place
+        let first = ys.head().unwrap() as *const i32;
+        *ys.make_mut().unwrap() += 1;
+        assert_eq!(ys.head().unwrap() as *const i32, first);
+
+        {
+            let mut rest = ys.tail_mut().unwrap();
+            *rest = List::new();
+        }
+        assert_eq!((ys.len(), &ys), (1, &list![11]));
+        assert_eq!(xs, list![1, 2, 3]);
+        assert!(List::<i32>::new().make_mut().is_none());
+        assert!(List::<i32>::new().tail_mut().is_none());
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[test]
+    fn test_allocator() {
+        use super::ListIn;
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::ptr::NonNull;
+        use std::sync::atomic::{AtomicIsize, Ordering};
+
+        static LIVE: AtomicIsize = AtomicIsize::new(0);
+
+        #[derive(Clone, Copy, Default)]
+        struct Counting;
+
+        unsafe impl Allocator for Counting {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                LIVE.fetch_add(1, Ordering::Relaxed);
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                LIVE.fetch_sub(1, Ordering::Relaxed);
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let xs: ListIn<i32, Counting> = (1..=3).collect();
+        let ys = xs.cons(0);
+        assert_eq!(LIVE.load(Ordering::Relaxed), 4);
+        crate::cons!((ys.iter().copied()) as _::rest: ListIn<i32, Counting>);
+        assert_eq!(rest, xs);
+        assert_eq!(LIVE.load(Ordering::Relaxed), 7);
+        drop((xs, ys, rest));
+        assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+
+        let v = vec![1, 2, 3];
+        crate::cons!(v as x::rest in Counting);
+        assert_eq!((x, &rest[..]), (1, &[2, 3][..]));
+        assert_eq!(LIVE.load(Ordering::Relaxed), 1);
+        drop(rest);
+        assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,229 @@
+//! Functions on pairs of lists, after SML's `ListPair` structure.
+//!
+//! Each function has the name of its SML counterpart, so SML code can
+//! be ported one call at a time:
+//!
+//! ```rust
+//! # use cons::{list, list_pair};
+//! // ListPair.foldl (fn (x, y, acc) => x * y + acc) 0 (xs, ys)
+//! let dot = list_pair::foldl(&list![1, 2, 3], &list![4, 5, 6], 0, |x, y, acc| x * y + acc);
+//! assert_eq!(dot, 32);
+//! ```
+//!
+//! As in SML, the plain functions stop at the end of the shorter list,
+//! and the ones ending in `_eq` return [`UnequalLengths`] if the lists
+//! have different lengths. Lists know their length, so the `_eq`
+//! functions check before calling `f` at all, where SML would raise
+//! the exception partway through.
+
+use alloc::vec::Vec;
+
+use crate::list::{GenericList, PointerKind, UnequalLengths};
+#[cfg(doc)]
+use crate::List;
+
+/// Pairs up the elements of two lists, stopping at the end of the
+/// shorter one. This is SML's `ListPair.zip`, and the same as
+/// [`List::zip`].
+pub fn zip<A: Clone, B: Clone, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+) -> GenericList<(A, B), P> {
+    xs.zip(ys)
+}
+
+/// Pairs up the elements of two lists of the same length. This is
+/// SML's `ListPair.zipEq`, and the same as [`List::zip_eq`].
+pub fn zip_eq<A: Clone, B: Clone, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+) -> Result<GenericList<(A, B), P>, UnequalLengths> {
+    xs.zip_eq(ys)
+}
+
+/// Splits a list of pairs into two lists. This is SML's
+/// `ListPair.unzip`, and the same as [`List::unzip`].
+pub fn unzip<A: Clone, B: Clone, P: PointerKind>(
+    pairs: &GenericList<(A, B), P>,
+) -> (GenericList<A, P>, GenericList<B, P>) {
+    pairs.unzip()
+}
+
+/// Returns a list of `f` applied to pairs of elements, stopping at the
+/// end of the shorter list. This is SML's `ListPair.map`, and the same
+/// as [`List::zip_with`].
+pub fn map<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    f: impl FnMut(&A, &B) -> C,
+) -> GenericList<C, P> {
+    xs.zip_with(ys, f)
+}
+
+/// Like [`map`], but for lists of the same length. This is SML's
+/// `ListPair.mapEq`, and the same as [`List::zip_with_eq`].
+pub fn map_eq<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    f: impl FnMut(&A, &B) -> C,
+) -> Result<GenericList<C, P>, UnequalLengths> {
+    xs.zip_with_eq(ys, f)
+}
+
+/// Calls `f` on pairs of elements, in order, stopping at the end of the
+/// shorter list. This is SML's `ListPair.app`.
+pub fn app<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    mut f: impl FnMut(&A, &B),
+) {
+    xs.iter().zip(ys).for_each(|(x, y)| f(x, y));
+}
+
+/// Like [`app`], but for lists of the same length. This is SML's
+/// `ListPair.appEq`.
+pub fn app_eq<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    f: impl FnMut(&A, &B),
+) -> Result<(), UnequalLengths> {
+    same_len(xs, ys)?;
+    app(xs, ys, f);
+    Ok(())
+}
+
+/// Folds over pairs of elements from the front, stopping at the end of
+/// the shorter list. This is SML's `ListPair.foldl`.
+pub fn foldl<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    init: C,
+    mut f: impl FnMut(&A, &B, C) -> C,
+) -> C {
+    xs.iter().zip(ys).fold(init, |acc, (x, y)| f(x, y, acc))
+}
+
+/// Like [`foldl`], but for lists of the same length. This is SML's
+/// `ListPair.foldlEq`.
+pub fn foldl_eq<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    init: C,
+    f: impl FnMut(&A, &B, C) -> C,
+) -> Result<C, UnequalLengths> {
+    same_len(xs, ys)?;
+    Ok(foldl(xs, ys, init, f))
+}
+
+/// Folds over pairs of elements from the back, starting with the last
+/// pair of the shorter list. This is SML's `ListPair.foldr`.
+///
+/// ```rust
+/// # use cons::{list, list_pair};
+/// let pairs = list_pair::foldr(&list![1, 2, 3], &list!['a', 'b'], Vec::new(), |x, y, mut acc| {
+///     acc.push((*x, *y));
+///     acc
+/// });
+/// assert_eq!(pairs, [(2, 'b'), (1, 'a')]);
+/// ```
+pub fn foldr<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    init: C,
+    mut f: impl FnMut(&A, &B, C) -> C,
+) -> C {
+    let pairs = xs.iter().zip(ys).collect::<Vec<_>>();
+    pairs.into_iter().rev().fold(init, |acc, (x, y)| f(x, y, acc))
+}
+
+/// Like [`foldr`], but for lists of the same length. This is SML's
+/// `ListPair.foldrEq`.
+pub fn foldr_eq<A, B, C, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    init: C,
+    f: impl FnMut(&A, &B, C) -> C,
+) -> Result<C, UnequalLengths> {
+    same_len(xs, ys)?;
+    Ok(foldr(xs, ys, init, f))
+}
+
+/// Returns `true` if `f` holds for every pair of elements, ignoring
+/// the extra elements of the longer list. This is SML's
+/// `ListPair.all`.
+///
+/// ```rust
+/// # use cons::{list, list_pair};
+/// assert!(list_pair::all(&list![1, 2, 3], &list![2, 3], |x, y| x < y));
+/// assert!(!list_pair::all_eq(&list![1, 2, 3], &list![2, 3], |x, y| x < y));
+/// ```
+pub fn all<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    mut f: impl FnMut(&A, &B) -> bool,
+) -> bool {
+    xs.iter().zip(ys).all(|(x, y)| f(x, y))
+}
+
+/// Returns `true` if the lists have the same length and `f` holds for
+/// every pair of elements. This is SML's `ListPair.allEq`, which
+/// returns `false` for lists of different lengths rather than raising
+/// an exception.
+pub fn all_eq<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    f: impl FnMut(&A, &B) -> bool,
+) -> bool {
+    xs.len() == ys.len() && all(xs, ys, f)
+}
+
+/// Returns `true` if `f` holds for some pair of elements, ignoring the
+/// extra elements of the longer list. This is SML's `ListPair.exists`.
+pub fn exists<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+    mut f: impl FnMut(&A, &B) -> bool,
+) -> bool {
+    xs.iter().zip(ys).any(|(x, y)| f(x, y))
+}
+
+fn same_len<A, B, P: PointerKind>(
+    xs: &GenericList<A, P>,
+    ys: &GenericList<B, P>,
+) -> Result<(), UnequalLengths> {
+    if xs.len() == ys.len() {
+        Ok(())
+    } else {
+        Err(UnequalLengths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list, List};
+
+    #[test]
+    fn test_truncating_and_eq() {
+        let xs = list![1, 2, 3];
+        let ys = list![10, 20];
+        let mut seen = Vec::new();
+        app(&xs, &ys, |x, y| seen.push(x + y));
+        assert_eq!(seen, [11, 22]);
+        assert_eq!(app_eq(&xs, &ys, |_, _| unreachable!()), Err(UnequalLengths));
+        assert_eq!(map(&xs, &ys, |x, y| x * y), list![10, 40]);
+        assert_eq!(map_eq(&xs, &ys, |x, y| x * y), Err(UnequalLengths));
+        assert_eq!(foldl(&xs, &ys, 0, |x, y, acc| acc * 100 + x + y), 1122);
+        assert_eq!(foldl_eq(&xs, &ys, 0, |_, _, acc| acc), Err(UnequalLengths));
+        assert_eq!(foldr(&xs, &ys, 0, |x, y, acc| acc * 100 + x + y), 2211);
+        assert_eq!(foldr_eq(&ys, &ys, 0, |x, y, acc| acc + x * y), Ok(500));
+        assert!(exists(&xs, &ys, |x, y| x * 10 == *y));
+        assert!(!exists(&xs, &ys, |&x, _| x == 3));
+        assert!(all_eq(&ys, &ys, |x, y| x == y));
+        assert!(!all_eq(&List::<i32>::new(), &ys, |_, _| true));
+
+        let pairs = zip(&xs, &ys);
+        assert_eq!(zip_eq(&xs, &ys), Err(UnequalLengths));
+        assert_eq!(unzip(&pairs), (list![1, 2], ys));
+    }
+}
@@ -0,0 +1,70 @@
+/// Returns whether an iterator matches a cons pattern, like
+/// [`matches!`] does for ordinary patterns.
+///
+/// The pattern syntax is the same as [`cons!`](crate::cons), but
+/// nothing is bound and nothing panics: the macro evaluates to `true`
+/// if the pattern fits and `false` if it doesn't.
+///
+/// ```rust
+/// # use cons::matches_cons;
+/// assert!(matches_cons!(vec![1, 2], x::_::nil));
+/// assert!(!matches_cons!(vec![1, 2, 3], x::_::nil));
+/// assert!(matches_cons!("ls -l".split(' '), "ls"::_));
+///
+/// let is_sorted_pair = |v: &[i32]| matches_cons!(v, x::(y if y >= x)::nil);
+/// assert!(is_sorted_pair(&[1, 2]));
+/// assert!(!is_sorted_pair(&[2, 1]));
+/// ```
+///
+/// Names in the pattern can still be used by later guards, but they
+/// are not visible after the macro. The iterator is consumed, so pass
+/// a reference to keep a collection.
+#[macro_export]
+macro_rules! matches_cons {
+    ($iter:expr, $($pat:tt)+) => {{
+        #[allow(unused_variables)]
+        let matched = 'matched: {
+            $crate::cons!(@__start [else { break 'matched false }] $iter => $($pat)+);
+            true
+        };
+        matched
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cons_pattern;
+
+    #[test]
+    fn test_shapes() {
+        assert!(matches_cons!([0; 0], nil));
+        assert!(matches_cons!([1], _::nil));
+        assert!(!matches_cons!([1], _::_::_));
+        assert!(matches_cons!((1..).map(|n| n * 2), 2::4::rest @ iter));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_collected_tails() {
+        assert!(matches_cons!(1..4, 1::rest));
+        assert!(!matches_cons!(1..4, 2::rest));
+        assert!(matches_cons!(1..4, _::mid::3!));
+    }
+
+    #[test]
+    fn test_does_not_bind() {
+        let x = "outer";
+        let v = [(1, 'a'), (2, 'b')];
+        assert!(matches_cons!(&v, (_, x)::((_, y) if *y > *x)::nil));
+        assert_eq!(x, "outer");
+        assert_eq!(v.len(), 2);
+    }
+
+    cons_pattern!(header = 0xCA::0xFE);
+
+    #[test]
+    fn test_alias() {
+        assert!(matches_cons!(vec![0xCA, 0xFE, 0], header!()::_));
+        assert!(!matches_cons!(vec![0xCA], header!()::_));
+    }
+}
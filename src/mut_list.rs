@@ -0,0 +1,690 @@
+//! A mutable singly linked list, edited in place with cursors.
+
+use alloc::boxed::Box;
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// A singly linked list that is changed in place, rather than shared
+/// like [`List`](crate::List).
+///
+/// Its [`CursorMut`] points at a position in the list and can insert
+/// and remove elements there in O(1), or split off everything from
+/// there on, which covers what `std`'s `LinkedList` cursors are usually
+/// wanted for:
+///
+/// ```rust
+/// # use cons::MutList;
+/// let mut xs = (1..=6).collect::<MutList<i32>>();
+/// let mut cursor = xs.cursor_front_mut();
+/// while let Some(&mut x) = cursor.current() {
+///     if x % 2 == 0 {
+///         cursor.remove_current();
+///     } else {
+///         cursor.insert_before(x * 10);
+///         cursor.move_next();
+///     }
+/// }
+/// assert!(xs.iter().eq(&[10, 1, 30, 3, 50, 5]));
+/// ```
+///
+/// Since the list is never shared, elements can be moved out of it and
+/// changed without cloning.
+pub struct MutList<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> MutList<T> {
+    /// Creates a new, empty list.
+    pub const fn new() -> Self {
+        Self { head: None, len: 0 }
+    }
+
+    /// Returns `true` if the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns the number of elements in the list. This is O(1).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns a reference to the first element, or `None` if the list
+    /// is empty.
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    /// Returns a mutable reference to the first element, or `None` if
+    /// the list is empty.
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    /// Adds an element to the front of the list. This is O(1).
+    pub fn push_front(&mut self, elem: T) {
+        self.cursor_front_mut().insert(elem);
+    }
+
+    /// Removes the first element and returns it, or `None` if the list
+    /// is empty. This is O(1).
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.cursor_front_mut().remove_current()
+    }
+
+    /// Removes every element.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns an iterator over references to the elements.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+            len: self.len,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.head.as_deref_mut(),
+            len: self.len,
+        }
+    }
+
+    /// Returns a cursor at the first element, for reading.
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            next: self.head.as_deref(),
+            index: 0,
+        }
+    }
+
+    /// Returns a cursor at the first element, for editing.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            link: Some(&mut self.head),
+            len: &mut self.len,
+            index: 0,
+        }
+    }
+
+    /// Returns a cursor just past the last element, where
+    /// [`insert_before`](CursorMut::insert_before) appends. Getting
+    /// there walks the list, so this is O(n).
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let mut cursor = self.cursor_front_mut();
+        while cursor.move_next() {}
+        cursor
+    }
+
+    /// Keeps only the elements for which `f` returns `true`, in one pass
+    /// and without allocating.
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = (1..=10).collect::<MutList<i32>>();
+    /// xs.retain(|&x| x % 3 == 0);
+    /// assert!(xs.iter().eq(&[3, 6, 9]));
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.extract_if(|x| !f(x)).for_each(drop);
+    }
+
+    /// Returns an iterator that removes the elements for which `pred`
+    /// returns `true` and yields them, in order.
+    ///
+    /// The list is walked lazily, as the iterator is used. If it is
+    /// dropped early, the elements it hasn't reached stay in the list,
+    /// as with `Vec::extract_if`.
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = (1..=6).collect::<MutList<i32>>();
+    /// let evens = xs.extract_if(|x| *x % 2 == 0).collect::<Vec<_>>();
+    /// assert_eq!(evens, [2, 4, 6]);
+    /// assert!(xs.iter().eq(&[1, 3, 5]));
+    ///
+    /// let first = xs.extract_if(|_| true).next();
+    /// assert_eq!(first, Some(1));
+    /// assert!(xs.iter().eq(&[3, 5]));
+    /// ```
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, F> {
+        ExtractIf {
+            cursor: self.cursor_front_mut(),
+            pred,
+        }
+    }
+
+    /// Like [`extract_if`](MutList::extract_if), except that dropping
+    /// the iterator early still removes the rest of the matching
+    /// elements, as `std`'s `drain_filter` did before it was renamed.
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = (1..=6).collect::<MutList<i32>>();
+    /// let first = xs.drain_filter(|x| *x > 3).next();
+    /// assert_eq!(first, Some(4));
+    /// assert!(xs.iter().eq(&[1, 2, 3]));
+    /// ```
+    pub fn drain_filter<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> DrainFilter<'_, T, F> {
+        DrainFilter {
+            inner: self.extract_if(pred),
+        }
+    }
+}
+
+/// A position in a [`MutList`] for reading it.
+///
+/// Created by [`MutList::cursor_front`]. The cursor is either at an
+/// element or just past the last one.
+pub struct Cursor<'a, T> {
+    next: Option<&'a Node<T>>,
+    index: usize,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    /// Returns the index of the current element, or the length of the
+    /// list if the cursor is past the end.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the current element, or `None` if the cursor is past the
+    /// end.
+    pub fn current(&self) -> Option<&'a T> {
+        self.next.map(|node| &node.elem)
+    }
+
+    /// Returns the element after the current one, if there is one.
+    pub fn peek_next(&self) -> Option<&'a T> {
+        self.next
+            .and_then(|node| node.next.as_deref())
+            .map(|node| &node.elem)
+    }
+
+    /// Moves to the next element. Returns `false`, and doesn't move, if
+    /// the cursor is already past the end.
+    pub fn move_next(&mut self) -> bool {
+        match self.next {
+            Some(node) => {
+                self.next = node.next.as_deref();
+                self.index += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            index: self.index,
+        }
+    }
+}
+
+/// A position in a [`MutList`] for editing it.
+///
+/// Created by [`MutList::cursor_front_mut`]. The cursor is either at an
+/// element or just past the last one, and everything it does there is
+/// O(1), except for [`splice_before`](CursorMut::splice_before).
+pub struct CursorMut<'a, T> {
+    // the link to the current node. It is only `None` in the middle of
+    // `move_next`.
+    link: Option<&'a mut Link<T>>,
+    len: &'a mut usize,
+    index: usize,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    fn link(&mut self) -> &mut Link<T> {
+        self.link.as_deref_mut().unwrap()
+    }
+
+    /// Returns the index of the current element, or the length of the
+    /// list if the cursor is past the end.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the current element, or `None` if the cursor is past the
+    /// end.
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.link().as_mut().map(|node| &mut node.elem)
+    }
+
+    /// Returns the element after the current one, if there is one.
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.link()
+            .as_mut()
+            .and_then(|node| node.next.as_mut())
+            .map(|node| &mut node.elem)
+    }
+
+    /// Moves to the next element. Returns `false`, and doesn't move, if
+    /// the cursor is already past the end.
+    pub fn move_next(&mut self) -> bool {
+        let link = self.link.take().unwrap();
+        match link {
+            Some(node) => {
+                self.link = Some(&mut node.next);
+                self.index += 1;
+                true
+            }
+            None => {
+                self.link = Some(link);
+                false
+            }
+        }
+    }
+
+    /// Inserts `elem` at the cursor, so that it becomes the current
+    /// element and the old one follows it.
+    pub fn insert(&mut self, elem: T) {
+        let link = self.link();
+        let next = link.take();
+        *link = Some(Box::new(Node { elem, next }));
+        *self.len += 1;
+    }
+
+    /// Inserts `elem` before the current element, or at the end if the
+    /// cursor is past it. The cursor stays on the same element.
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = MutList::new();
+    /// let mut cursor = xs.cursor_front_mut();
+    /// cursor.insert_before(1);
+    /// cursor.insert_before(2);
+    /// assert_eq!(cursor.index(), 2);
+    /// assert!(xs.iter().eq(&[1, 2]));
+    /// ```
+    pub fn insert_before(&mut self, elem: T) {
+        self.insert(elem);
+        self.move_next();
+    }
+
+    /// Removes the current element and returns it, or returns `None` if
+    /// the cursor is past the end. The cursor moves on to the element
+    /// after it.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let link = self.link();
+        let node = link.take()?;
+        *link = node.next;
+        *self.len -= 1;
+        Some(node.elem)
+    }
+
+    /// Inserts the elements of `list` before the current element, in
+    /// order. The cursor stays on the same element.
+    ///
+    /// The nodes are moved rather than copied, but finding the end of
+    /// `list` takes time in proportion to its length.
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = (1..=3).collect::<MutList<i32>>();
+    /// let mut cursor = xs.cursor_front_mut();
+    /// cursor.move_next();
+    /// cursor.splice_before([10, 20].into_iter().collect());
+    /// assert_eq!(cursor.current(), Some(&mut 2));
+    /// assert!(xs.iter().eq(&[1, 10, 20, 2, 3]));
+    /// ```
+    pub fn splice_before(&mut self, mut list: MutList<T>) {
+        let n = list.len;
+        *list.cursor_back_mut().link() = self.link().take();
+        *self.link() = list.head.take();
+        *self.len += n;
+        for _ in 0..n {
+            self.move_next();
+        }
+    }
+
+    /// Removes the current element and everything after it, and returns
+    /// them as a new list. The cursor is left past the end. This is
+    /// O(1).
+    ///
+    /// ```rust
+    /// # use cons::MutList;
+    /// let mut xs = (1..=5).collect::<MutList<i32>>();
+    /// let mut cursor = xs.cursor_front_mut();
+    /// cursor.move_next();
+    /// let rest = cursor.split_off();
+    /// assert!(xs.iter().eq(&[1]));
+    /// assert!(rest.iter().eq(&[2, 3, 4, 5]));
+    /// ```
+    pub fn split_off(&mut self) -> MutList<T> {
+        let len = *self.len - self.index;
+        *self.len = self.index;
+        MutList {
+            head: self.link().take(),
+            len,
+        }
+    }
+}
+
+impl<T: Clone> Clone for MutList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Drop for MutList<T> {
+    /// Drops the nodes one at a time rather than recursively, so long
+    /// lists can't overflow the stack.
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(mut node) = next {
+            next = node.next.take();
+        }
+    }
+}
+
+impl<T> Default for MutList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MutList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for MutList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for MutList<T> {}
+
+impl<T> FromIterator<T> for MutList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for MutList<T> {
+    /// Appends the elements to the back. This walks the list to find
+    /// the end first.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut cursor = self.cursor_back_mut();
+        for elem in iter {
+            cursor.insert_before(elem);
+        }
+    }
+}
+
+impl<T> IntoIterator for MutList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a MutList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut MutList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over references to the elements of a [`MutList`].
+///
+/// Created by [`MutList::iter`].
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            len: self.len,
+        }
+    }
+}
+
+/// An iterator over mutable references to the elements of a
+/// [`MutList`].
+///
+/// Created by [`MutList::iter_mut`].
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            self.len -= 1;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// An iterator that removes the elements of a [`MutList`] that match a
+/// predicate.
+///
+/// Created by [`MutList::extract_if`].
+pub struct ExtractIf<'a, T, F> {
+    cursor: CursorMut<'a, T>,
+    pred: F,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for ExtractIf<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(elem) = self.cursor.current() {
+            if (self.pred)(elem) {
+                return self.cursor.remove_current();
+            }
+            self.cursor.move_next();
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(*self.cursor.len - self.cursor.index))
+    }
+}
+
+impl<T, F: FnMut(&mut T) -> bool> FusedIterator for ExtractIf<'_, T, F> {}
+
+/// An iterator that removes the elements of a [`MutList`] that match a
+/// predicate, even the ones it hasn't reached when it is dropped.
+///
+/// Created by [`MutList::drain_filter`].
+pub struct DrainFilter<'a, T, F: FnMut(&mut T) -> bool> {
+    inner: ExtractIf<'a, T, F>,
+}
+
+impl<T, F: FnMut(&mut T) -> bool> Iterator for DrainFilter<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, F: FnMut(&mut T) -> bool> FusedIterator for DrainFilter<'_, T, F> {}
+
+impl<T, F: FnMut(&mut T) -> bool> Drop for DrainFilter<'_, T, F> {
+    fn drop(&mut self) {
+        self.inner.by_ref().for_each(drop);
+    }
+}
+
+/// An owning iterator over the elements of a [`MutList`].
+///
+/// Created by [`MutList::into_iter`](IntoIterator::into_iter).
+pub struct IntoIter<T> {
+    list: MutList<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_edits() {
+        let mut xs = MutList::new();
+        xs.push_front(3);
+        xs.push_front(1);
+        let mut cursor = xs.cursor_front_mut();
+        assert_eq!(cursor.peek_next(), Some(&mut 3));
+        cursor.move_next();
+        cursor.insert(2);
+        assert_eq!((cursor.index(), cursor.current()), (1, Some(&mut 2)));
+        while cursor.move_next() {}
+        assert!(!cursor.move_next());
+        assert_eq!((cursor.index(), cursor.current()), (3, None));
+        cursor.splice_before(MutList::from_iter([4, 5]));
+        cursor.insert_before(6);
+        assert_eq!(cursor.remove_current(), None);
+        assert_eq!(cursor.index(), 6);
+        assert_eq!(xs.len(), 6);
+        assert!(xs.iter().copied().eq(1..=6));
+
+        let mut cursor = xs.cursor_front();
+        cursor.move_next();
+        assert_eq!((cursor.current(), cursor.peek_next()), (Some(&2), Some(&3)));
+
+        let mut cursor = xs.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let mut rest = cursor.split_off();
+        assert_eq!((xs.len(), rest.len()), (2, 4));
+        assert_eq!(rest.pop_front(), Some(3));
+        rest.cursor_front_mut().splice_before(MutList::new());
+        for x in &mut rest {
+            *x *= 10;
+        }
+        xs.extend(rest);
+        assert_eq!(format!("{xs:?}"), "[1, 2, 40, 50, 60]");
+        assert_eq!(xs.clone(), xs);
+        xs.clear();
+        assert!(xs.is_empty() && xs.front().is_none());
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut xs = (0..10).collect::<MutList<u32>>();
+        {
+            let mut it = xs.extract_if(|x| {
+                *x += 100;
+                *x % 2 == 1
+            });
+            assert_eq!(it.size_hint(), (0, Some(10)));
+            assert_eq!(it.next(), Some(101));
+            assert_eq!(it.size_hint(), (0, Some(8)));
+        }
+        assert_eq!(xs.len(), 9);
+        assert!(xs.iter().copied().eq([100, 2, 3, 4, 5, 6, 7, 8, 9]));
+
+        drop(xs.drain_filter(|x| *x < 100));
+        assert_eq!(xs.front(), Some(&100));
+        assert_eq!(xs.len(), 1);
+
+        let mut ys = (0..1_000_000).collect::<MutList<u32>>();
+        ys.retain(|x| x % 1000 == 0);
+        assert_eq!(ys.len(), 1000);
+        assert_eq!(ys.drain_filter(|_| true).count(), 1000);
+        assert!(ys.is_empty());
+    }
+
+    #[test]
+    fn test_long_list_drop() {
+        let xs = (0..1_000_000).collect::<MutList<u32>>();
+        assert_eq!(xs.iter().len(), 1_000_000);
+        assert_eq!(xs.into_iter().nth(999_999), Some(999_999));
+        let mut ys = MutList::new();
+        for i in 0..1_000_000 {
+            ys.push_front(i);
+        }
+        *ys.front_mut().unwrap() += 1;
+        assert_eq!(ys.front(), Some(&1_000_000));
+    }
+}
@@ -0,0 +1,114 @@
+//! Parallel iteration over [`List`] with rayon, behind the `rayon`
+//! feature.
+//!
+//! A `List` shares its nodes with [`Rc`](alloc::rc::Rc), so it can't be
+//! walked from several threads at once. Instead, the elements are
+//! gathered on the calling thread, which is cheap next to the work
+//! done on them, and then handed to rayon as an indexed parallel
+//! iterator, so results come back in the list's order:
+//!
+//! ```rust
+//! use cons::{list, List};
+//! use rayon::prelude::*;
+//!
+//! let xs = list![1, 2, 3, 4];
+//! let sum = xs.par_iter().map(|x| x * x).sum::<i32>();
+//! assert_eq!(sum, 30);
+//!
+//! let ys: List<i32> = (1..=4).into_par_iter().map(|x| x * 10).collect();
+//! assert_eq!(ys, list![10, 20, 30, 40]);
+//! ```
+
+use alloc::vec::Vec;
+
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::list::{GenericList, PointerKind};
+#[cfg(doc)]
+use crate::List;
+
+impl<T: Send, P: PointerKind> FromParallelIterator<T> for GenericList<T, P> {
+    /// Collects the items in parallel, then builds the list from them
+    /// in order with [`from_vec`](GenericList::from_vec).
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        Self::from_vec(Vec::from_par_iter(par_iter))
+    }
+}
+
+impl<'a, T: Sync, P: PointerKind> IntoParallelIterator for &'a GenericList<T, P> {
+    type Iter = rayon::vec::IntoIter<&'a T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.iter().collect::<Vec<_>>().into_par_iter()
+    }
+}
+
+impl<T: Clone + Send, P: PointerKind> IntoParallelIterator for GenericList<T, P> {
+    type Iter = rayon::vec::IntoIter<T>;
+    type Item = T;
+
+    /// Moves the elements out with [`into_vec`](GenericList::into_vec),
+    /// cloning those in nodes shared with another list.
+    fn into_par_iter(self) -> Self::Iter {
+        self.into_vec().into_par_iter()
+    }
+}
+
+impl<T: Sync, P: PointerKind> GenericList<T, P> {
+    /// Returns a new list with `f` applied to each element, calling `f`
+    /// on rayon's thread pool. The result is in the same order as with
+    /// [`map`](GenericList::map).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3].par_map(|x| x * 10), list![10, 20, 30]);
+    /// ```
+    pub fn par_map<U: Send>(&self, f: impl Fn(&T) -> U + Sync + Send) -> GenericList<U, P> {
+        self.into_par_iter().map(f).collect()
+    }
+}
+
+impl<T: Clone + Send + Sync, P: PointerKind> GenericList<T, P> {
+    /// Returns a new list of the elements `f` returns `true` for,
+    /// calling `f` on rayon's thread pool. The result is in the same
+    /// order as with [`filter`](GenericList::filter).
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// assert_eq!(list![1, 2, 3, 4].par_filter(|x| x % 2 == 0), list![2, 4]);
+    /// ```
+    #[must_use]
+    pub fn par_filter(&self, f: impl Fn(&T) -> bool + Sync + Send) -> Self {
+        self.into_par_iter().filter(|x| f(x)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use crate::{cons, list, List};
+
+    #[test]
+    fn test_order_is_kept() {
+        let xs = (0..100_000).collect::<List<u64>>();
+        let squares = xs.par_map(|x| x * x);
+        assert!(squares.iter().copied().eq((0..100_000).map(|x| x * x)));
+        let odd = xs.par_filter(|x| x % 2 == 1);
+        assert_eq!(odd.len(), 50_000);
+        assert!(odd.iter().copied().eq((1..100_000).step_by(2)));
+        assert_eq!(xs.clone().into_par_iter().sum::<u64>(), xs.iter().sum::<u64>());
+    }
+
+    #[test]
+    fn test_par_tail() {
+        let v = (0..1000).collect::<Vec<u32>>();
+        cons!(v as first::rest @ par);
+        let doubled: List<u32> = rest.map(|x| x * 2).collect();
+        assert_eq!(first, 0);
+        assert_eq!(doubled.head(), Some(&2));
+        assert_eq!(doubled.len(), 999);
+        assert_eq!(list![1, 2].par_iter().count(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! Zero-copy archiving for [`List`], behind the `rkyv` feature.
+//!
+//! A list is archived as an [`ArchivedList`], which keeps the elements
+//! next to each other in the buffer rather than as linked nodes, so it
+//! can be read straight out of a file or shared memory without
+//! deserializing it first:
+//!
+//! ```rust
+//! use cons::{list, List};
+//! use cons::rkyv::ArchivedList;
+//! use rkyv::rancor::Error;
+//!
+//! let bytes = rkyv::to_bytes::<Error>(&list![1u32, 2, 3]).unwrap();
+//!
+//! let archived = rkyv::access::<ArchivedList<rkyv::Archived<u32>>, Error>(&bytes).unwrap();
+//! assert_eq!(archived.len(), 3);
+//! assert_eq!(archived.head().map(|x| x.to_native()), Some(1));
+//!
+//! let xs = rkyv::deserialize::<List<u32>, Error>(archived).unwrap();
+//! assert_eq!(xs, list![1, 2, 3]);
+//! ```
+
+use core::fmt;
+use core::slice;
+
+use rkyv::rancor::Fallible;
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Deserialize, Place, Portable, Serialize};
+
+use crate::list::{GenericList, PointerKind};
+#[cfg(doc)]
+use crate::List;
+
+/// An archived [`List`].
+///
+/// The elements are stored as a slice, so the length, indexing and
+/// iterating are all O(1) per element. Splitting it with
+/// [`cons!`](crate::cons) takes a reference, like a slice:
+///
+/// ```rust
+/// # use cons::{cons, list};
+/// # use cons::rkyv::ArchivedList;
+/// # use rkyv::rancor::Error;
+/// let bytes = rkyv::to_bytes::<Error>(&list![1u8, 2, 3]).unwrap();
+/// let archived = rkyv::access::<ArchivedList<u8>, Error>(&bytes).unwrap();
+///
+/// cons!(archived as x::rest @ iter);
+/// assert_eq!(*x, 1);
+/// assert_eq!(rest.as_slice(), [2, 3]);
+/// ```
+#[derive(Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[rkyv(crate = rkyv)]
+#[repr(transparent)]
+pub struct ArchivedList<T> {
+    elems: ArchivedVec<T>,
+}
+
+impl<T> ArchivedList<T> {
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.elems.len()
+    }
+
+    /// Returns `true` if the list is `nil`.
+    pub fn is_empty(&self) -> bool {
+        self.elems.is_empty()
+    }
+
+    /// Returns the first element, or `None` if the list is `nil`.
+    pub fn head(&self) -> Option<&T> {
+        self.elems.first()
+    }
+
+    /// Returns the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        self.elems.as_slice()
+    }
+
+    /// Iterates over the elements from the front.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArchivedList<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq<U>, U, P: PointerKind> PartialEq<GenericList<U, P>> for ArchivedList<T> {
+    fn eq(&self, other: &GenericList<U, P>) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(x, y)| x == y)
+    }
+}
+
+impl<T: Archive, P: PointerKind> Archive for GenericList<T, P> {
+    type Archived = ArchivedList<T::Archived>;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        rkyv::munge::munge!(let ArchivedList { elems } = out);
+        ArchivedVec::resolve_from_len(self.len(), resolver, elems);
+    }
+}
+
+impl<T, P, S> Serialize<S> for GenericList<T, P>
+where
+    T: Serialize<S>,
+    P: PointerKind,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::serialize_from_iter::<T, _, _>(self.iter(), serializer)
+    }
+}
+
+impl<T, P, D> Deserialize<GenericList<T, P>, D> for ArchivedList<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    P: PointerKind,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<GenericList<T, P>, D::Error> {
+        self.iter().map(|x| x.deserialize(deserializer)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use rkyv::rancor::Error;
+    use rkyv::string::ArchivedString;
+
+    use super::ArchivedList;
+    use crate::{list, List, SyncList};
+
+    #[test]
+    fn test_round_trip() {
+        let xs: List<String> = list![String::from("a"), String::from("bc")];
+        let bytes = rkyv::to_bytes::<Error>(&xs).unwrap();
+        let archived = rkyv::access::<ArchivedList<ArchivedString>, Error>(&bytes).unwrap();
+        assert_eq!(*archived, xs);
+        assert_eq!(format!("{archived:?}"), r#"["a", "bc"]"#);
+        assert_eq!(rkyv::deserialize::<List<String>, Error>(archived).unwrap(), xs);
+
+        let nil = rkyv::to_bytes::<Error>(&SyncList::<u8>::new()).unwrap();
+        let archived = rkyv::access::<ArchivedList<u8>, Error>(&nil).unwrap();
+        assert!(archived.is_empty() && archived.head().is_none());
+        assert!(rkyv::deserialize::<List<u8>, Error>(archived).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_nested() {
+        let xss: List<List<u8>> = list![list![1, 2], List::new(), list![3]];
+        let bytes = rkyv::to_bytes::<Error>(&xss).unwrap();
+        let archived = rkyv::access::<ArchivedList<ArchivedList<u8>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.iter().map(ArchivedList::len).collect::<Vec<_>>(), [2, 0, 1]);
+        assert_eq!(rkyv::deserialize::<List<List<u8>>, Error>(archived).unwrap(), xss);
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let bytes = rkyv::to_bytes::<Error>(&list![1u32, 2]).unwrap();
+        let short = &bytes[..bytes.len() - 1];
+        assert!(rkyv::access::<ArchivedList<rkyv::Archived<u32>>, Error>(short).is_err());
+    }
+}
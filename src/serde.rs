@@ -0,0 +1,98 @@
+//! `Serialize` and `Deserialize` for [`List`], behind the `serde`
+//! feature.
+
+use core::fmt;
+use core::iter;
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::list::{GenericList, PointerKind};
+#[cfg(doc)]
+use crate::List;
+
+impl<T: Serialize, P: PointerKind> Serialize for GenericList<T, P> {
+    /// Serializes the list as a sequence, such as a JSON array.
+    ///
+    /// ```rust
+    /// # use cons::list;
+    /// let json = serde_json::to_string(&list![1, 2, 3]).unwrap();
+    /// assert_eq!(json, "[1,2,3]");
+    /// ```
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, P: PointerKind> Deserialize<'de> for GenericList<T, P> {
+    /// Deserializes the list from a sequence, such as a JSON array.
+    ///
+    /// The nodes are built as the elements arrive, so there is no
+    /// intermediate `Vec`.
+    ///
+    /// ```rust
+    /// # use cons::{list, List};
+    /// let xs: List<i32> = serde_json::from_str("[1, 2, 3]").unwrap();
+    /// assert_eq!(xs, list![1, 2, 3]);
+    /// ```
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ListVisitor(PhantomData))
+    }
+}
+
+struct ListVisitor<T, P: PointerKind>(PhantomData<fn() -> GenericList<T, P>>);
+
+impl<'de, T: Deserialize<'de>, P: PointerKind> Visitor<'de> for ListVisitor<T, P> {
+    type Value = GenericList<T, P>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut error = None;
+        // `from_iter` links each node in as it is produced, so stopping
+        // early on an error just leaves a shorter list to throw away
+        let list = iter::from_fn(|| match seq.next_element() {
+            Ok(elem) => elem,
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        })
+        .collect();
+        match error {
+            Some(e) => Err(e),
+            None => Ok(list),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{list, List};
+
+    #[test]
+    fn test_round_trip() {
+        let xs = list![list![1, 2], list![], list![3]];
+        let json = serde_json::to_string(&xs).unwrap();
+        assert_eq!(json, "[[1,2],[],[3]]");
+        let ys: List<List<i32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(ys, xs);
+        assert_eq!(ys.iter().map(List::len).collect::<Vec<_>>(), [2, 0, 1]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(serde_json::from_str::<List<i32>>("[1, \"two\"]").is_err());
+        assert!(serde_json::from_str::<List<i32>>("{}").is_err());
+    }
+
+    #[test]
+    fn test_long_list() {
+        let xs = (0..1_000_000).collect::<List<u32>>();
+        let ys: List<u32> = serde_json::from_str(&serde_json::to_string(&xs).unwrap()).unwrap();
+        assert_eq!(ys, xs);
+    }
+}
@@ -0,0 +1,215 @@
+/// Like [`cons!`](crate::cons), but binds elements from the back of
+/// the iterator instead of the front. The name is `cons` backwards, as
+/// in SML's `snoc` lists.
+///
+/// The first segment of the pattern binds everything before the last
+/// few elements, and the rest bind the last few elements in order. The
+/// iterator has to be a [`DoubleEndedIterator`].
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// # use cons::snoc;
+/// let args = ["-v", "-n", "3", "input.txt"];
+/// snoc!(args as flags::file);
+/// assert_eq!(flags, vec!["-v", "-n", "3"]);
+/// assert_eq!(file, "input.txt");
+/// # }
+/// ```
+///
+/// Head patterns work the same as in `cons!`, including literals,
+/// destructuring, and guards. The front of the pattern can be `_` to
+/// drop the rest, `nil` to require that there is nothing else, or
+/// `@ iter` to keep the rest of the iterator:
+///
+/// ```rust
+/// # use cons::snoc;
+/// snoc!((1..=10) as init @ iter::(x if x % 3 == 0)::10);
+/// assert_eq!(x, 9);
+/// assert_eq!(init.next_back(), Some(8));
+///
+/// let pairs = [(1, 'a'), (2, 'b')];
+/// snoc!(pairs as nil::((n, c))::_);
+/// assert_eq!((n, c), (1, 'a'));
+/// ```
+///
+/// # Panics
+///
+/// Panics in the same cases as `cons!`: when there aren't enough
+/// elements, when the pattern starts with `nil` and there are too many,
+/// or when a head pattern or guard doesn't match. As with `cons!`, an
+/// `else` block can be given to run instead:
+///
+/// ```rust
+/// # use cons::snoc;
+/// fn last_two(v: Vec<i32>) -> Option<(i32, i32)> {
+///     snoc!(v as _::x::y else { return None });
+///     Some((x, y))
+/// }
+///
+/// assert_eq!(last_two(vec![1, 2, 3]), Some((2, 3)));
+/// assert_eq!(last_two(vec![1]), None);
+/// ```
+#[macro_export]
+macro_rules! snoc {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::snoc!(@__else $iter => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::snoc!(@__else $iter => [] $($rest)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+] else $else:block) => {
+        $crate::snoc!(@__ [else $else] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)+]) => {
+        $crate::snoc!(@__ [panic] $iter => $($pat)+);
+    };
+    (@__else $iter:expr => [$($pat:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::snoc!(@__else $iter => [$($pat)* $next] $($rest)*);
+    };
+    (@__ $mode:tt $iter:expr => $init:ident @ .. $(:: $hd:tt)+) => {
+        $crate::snoc!(@__ $mode $iter => $init @ iter $(:: $hd)+);
+    };
+    (@__ $mode:tt $iter:expr => $init:ident @ iter $(:: $hd:tt)+) => {
+        #[allow(unused_mut)]
+        let mut iter = $iter.into_iter();
+        $crate::snoc!(@__rev $mode iter [] $($hd)+);
+        #[allow(unused_mut)]
+        let mut $init = iter;
+    };
+    (@__ $mode:tt $iter:expr => $init:tt $(:: $hd:tt)+) => {
+        #[allow(unused_mut)]
+        let mut iter = $iter.into_iter();
+        $crate::snoc!(@__rev $mode iter [] $($hd)+);
+        $crate::snoc!(@__init $mode iter $init);
+    };
+    (@__rev $mode:tt $iter:ident [$($acc:tt)*] $hd:tt $($rest:tt)*) => {
+        $crate::snoc!(@__rev $mode $iter [$hd $($acc)*] $($rest)*);
+    };
+    (@__rev $mode:tt $iter:ident [$($hd:tt)+]) => {
+        $($crate::snoc!(@__back $mode $iter $hd);)+
+    };
+    (@__back $mode:tt $iter:ident ($hd:pat $(if $guard:expr)?)) => {
+        let ::core::option::Option::Some(hd) = $iter.next_back() else {
+            $crate::cons!(@__fail $mode exhausted $hd)
+        };
+        // the parentheses allow or-patterns such as `(1 | 2)`
+        #[allow(irrefutable_let_patterns, unused_parens)]
+        let ($hd) = hd else {
+            $crate::cons!(@__fail $mode mismatch [$hd])
+        };
+        $(let true = $guard else {
+            $crate::cons!(@__fail $mode mismatch [$hd if $guard])
+        };)?
+    };
+    (@__back $mode:tt $iter:ident $hd:ident) => {
+        $crate::snoc!(@__back $mode $iter ($hd));
+    };
+    (@__back $mode:tt $iter:ident $hd:literal) => {
+        $crate::snoc!(@__back $mode $iter ($hd));
+    };
+    (@__back $mode:tt $iter:ident _) => {
+        $crate::snoc!(@__back $mode $iter (_));
+    };
+    (@__init $mode:tt $iter:ident nil) => {
+        let rest = $iter.count();
+        let 0 = rest else {
+            $crate::cons!(@__fail $mode not_empty rest)
+        };
+    };
+    (@__init $mode:tt $iter:ident []) => {
+        $crate::snoc!(@__init $mode $iter nil);
+    };
+    (@__init $mode:tt $iter:ident _) => {
+        let _ = $iter;
+    };
+    (@__init $mode:tt $iter:ident $init:ident) => {
+        let $init = $crate::__collect_tail!($iter, $init);
+    };
+    (@__ $mode:tt $iter:expr => $($pat:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "expected a snoc pattern such as `init::x`, found `",
+            ::core::stringify!($($pat)*),
+            "`",
+        ));
+    };
+    (@__back $mode:tt $iter:ident $($pat:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "expected identifier, literal, or parenthesized pattern, found `",
+            ::core::stringify!($($pat)*),
+            "`",
+        ));
+    };
+    (@__init $mode:tt $iter:ident $($pat:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "the start of a snoc pattern must be an identifier, `_`, or `nil`, found `",
+            ::core::stringify!($($pat)*),
+            "`",
+        ));
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front snoc [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_last_elements() {
+        let v = vec![String::from("a"), String::from("b"), String::from("c")];
+        snoc!(v as init::y::z);
+        assert_eq!(init, vec!["a"]);
+        assert_eq!((y.as_str(), z.as_str()), ("b", "c"));
+    }
+
+    #[test]
+    fn test_exact() {
+        snoc!((1..=3) as nil::a::b::c);
+        assert_eq!((a, b, c), (1, 2, 3));
+        snoc!((1..=1) as []::x);
+        assert_eq!(x, 1);
+    }
+
+    #[test]
+    fn test_lazy_init() {
+        snoc!((1..=5) as init @ iter::_::last);
+        assert_eq!(last, 5);
+        assert_eq!(init.next(), Some(1));
+        assert_eq!(init.next_back(), Some(3));
+    }
+
+    #[test]
+    fn test_literals_and_guards() {
+        let cmd = ["cp", "-r", "src", "dst"];
+        snoc!(cmd as _::(src if src.len() == 3)::"dst");
+        assert_eq!(src, "src");
+    }
+
+    #[test]
+    #[should_panic(expected = "Iterator exhausted before reaching variable x")]
+    fn test_exhausted() {
+        let v = [1];
+        snoc!(v as _::x::y);
+        let _ = (x, y);
+    }
+
+    #[test]
+    #[should_panic(expected = "Found `nil` in cons but iterator is not empty (1 elements left)")]
+    fn test_too_long() {
+        let v = [1, 2, 3];
+        snoc!(v as nil::x::y);
+        let _ = (x, y);
+    }
+
+    #[test]
+    fn test_else() {
+        let mut lasts = vec![];
+        for v in [vec![1, 2], vec![], vec![3]] {
+            snoc!(v as _::x else { continue });
+            lasts.push(x);
+        }
+        assert_eq!(lasts, vec![2, 3]);
+    }
+}
@@ -0,0 +1,251 @@
+//! Cons lists that live in `static` and `const` items.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+/// Creates a [`StaticList`] containing the arguments, in order, with
+/// every node stored in the binary instead of on the heap.
+///
+/// The result is meant for `static` and `const` items, where the nodes
+/// are built at compile time and live for `'static`. Nothing is
+/// allocated, so this works without the `alloc` feature:
+///
+/// ```rust
+/// # use cons::{cons, static_list, StaticList};
+/// static PRIMES: StaticList<u32> = static_list![2, 3, 5, 7];
+///
+/// assert_eq!(PRIMES.len(), 4);
+/// assert_eq!(PRIMES.head(), Some(&2));
+/// assert_eq!(PRIMES.tail().unwrap().head(), Some(&3));
+///
+/// cons!(PRIMES as a::b::rest @ iter);
+/// assert_eq!((*a, *b, rest.sum::<u32>()), (2, 3, 12));
+/// ```
+///
+/// The elements can be any constant expressions, and a list can be
+/// built on the end of another one with `..`, which shares its nodes
+/// rather than copying them:
+///
+/// ```rust
+/// # use cons::{static_list, StaticList};
+/// const KEYWORDS: StaticList<(&str, u8)> = static_list![("fn", 1), ("let", 2)];
+/// const ALL: StaticList<(&str, u8)> = static_list![("if", 3), ..KEYWORDS];
+///
+/// assert_eq!(ALL.len(), 3);
+/// assert!(ALL.iter().any(|&(k, _)| k == "let"));
+/// ```
+#[macro_export]
+macro_rules! static_list {
+    () => {
+        $crate::StaticList::NIL
+    };
+    (.. $tail:expr) => {
+        $tail
+    };
+    ($head:expr $(, $($rest:tt)*)?) => {
+        $crate::StaticList {
+            head: ::core::option::Option::Some(&$crate::static_list::Node {
+                elem: $head,
+                next: $crate::static_list!($($($rest)*)?),
+            }),
+        }
+    };
+}
+
+/// A cons list whose nodes are `&'static` references, for lookup tables
+/// and other data known at compile time. Created with
+/// [`static_list!`](crate::static_list!).
+///
+/// It has the read-only parts of [`List`](crate::List)'s API, and since
+/// it is just a reference it is `Copy`.
+pub struct StaticList<T: 'static> {
+    #[doc(hidden)]
+    pub head: Option<&'static Node<T>>,
+}
+
+#[doc(hidden)]
+pub struct Node<T: 'static> {
+    pub elem: T,
+    pub next: StaticList<T>,
+}
+
+impl<T> StaticList<T> {
+    /// The empty list.
+    pub const NIL: Self = Self { head: None };
+
+    /// Returns `true` if the list has no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns the number of elements in the list. This walks the whole
+    /// list, so it is O(n), but it can be used in constants.
+    pub const fn len(&self) -> usize {
+        let mut n = 0;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            n += 1;
+            cur = node.next.head;
+        }
+        n
+    }
+
+    /// Returns a reference to the first element, or `None` if the list
+    /// is empty.
+    pub const fn head(&self) -> Option<&'static T> {
+        match self.head {
+            Some(node) => Some(&node.elem),
+            None => None,
+        }
+    }
+
+    /// Returns everything but the first element, or `None` if the list
+    /// is empty.
+    pub const fn tail(&self) -> Option<Self> {
+        match self.head {
+            Some(node) => Some(node.next),
+            None => None,
+        }
+    }
+
+    /// Splits the list into its head and tail, or returns `None` if it
+    /// is empty.
+    pub const fn uncons(&self) -> Option<(&'static T, Self)> {
+        match self.head {
+            Some(node) => Some((&node.elem, node.next)),
+            None => None,
+        }
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is
+    /// out of bounds.
+    ///
+    /// ```rust
+    /// # use cons::{static_list, StaticList};
+    /// const NAMES: StaticList<&str> = static_list!["zero", "one", "two"];
+    /// const ONE: &str = *NAMES.get(1).unwrap();
+    /// assert_eq!(ONE, "one");
+    /// assert_eq!(NAMES.get(3), None);
+    /// ```
+    pub const fn get(&self, index: usize) -> Option<&'static T> {
+        let mut i = 0;
+        let mut cur = self.head;
+        while let Some(node) = cur {
+            if i == index {
+                return Some(&node.elem);
+            }
+            i += 1;
+            cur = node.next.head;
+        }
+        None
+    }
+
+    /// Returns an iterator over references to the elements.
+    pub fn iter(&self) -> Iter<T> {
+        Iter { next: self.head }
+    }
+}
+
+impl<T> Clone for StaticList<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for StaticList<T> {}
+
+impl<T> Default for StaticList<T> {
+    fn default() -> Self {
+        Self::NIL
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StaticList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for StaticList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for StaticList<T> {}
+
+impl<T> IntoIterator for StaticList<T> {
+    type Item = &'static T;
+    type IntoIter = Iter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over references to the elements of a [`StaticList`].
+///
+/// Created by [`StaticList::iter`].
+pub struct Iter<T: 'static> {
+    next: Option<&'static Node<T>>,
+}
+
+impl<T> Iterator for Iter<T> {
+    type Item = &'static T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.head;
+            &node.elem
+        })
+    }
+}
+
+impl<T> FusedIterator for Iter<T> {}
+
+impl<T> Clone for Iter<T> {
+    fn clone(&self) -> Self {
+        Self { next: self.next }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Op {
+        name: &'static str,
+        arity: usize,
+    }
+
+    static OPS: StaticList<Op> = static_list![
+        Op { name: "neg", arity: 1 },
+        Op { name: "add", arity: 2 },
+    ];
+
+    const EMPTY: StaticList<Op> = static_list![];
+    const LEN: usize = OPS.len();
+
+    #[test]
+    fn test_lookup() {
+        let arity = |name| OPS.iter().find(|op| op.name == name).map(|op| op.arity);
+        assert_eq!((arity("add"), arity("mul")), (Some(2), None));
+        assert_eq!(LEN, 2);
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.uncons().map(|(op, _)| op), None);
+        assert_eq!(format!("{:?}", OPS.tail().unwrap()), r#"[Op { name: "add", arity: 2 }]"#);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_shared_tail() {
+        static BASE: StaticList<i32> = static_list![2, 3];
+        static XS: StaticList<i32> = static_list![1, ..BASE];
+        static YS: StaticList<i32> = static_list![1, 2, 3,];
+        assert_eq!(XS, YS);
+        assert!(core::ptr::eq(XS.get(1).unwrap(), BASE.head().unwrap()));
+        crate::cons!(XS as 1::rest);
+        assert_eq!(rest, [&2, &3]);
+    }
+}
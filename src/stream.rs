@@ -0,0 +1,406 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use core::cell::LazyCell;
+use core::iter::FusedIterator;
+
+type Node<T> = Option<(T, Stream<T>)>;
+type Thunk<T> = Box<dyn FnOnce() -> Node<T>>;
+
+/// A lazy cons list, in the style of SML's streams or Haskell's lists.
+///
+/// Each cell of the stream is a thunk that is only evaluated the first
+/// time it is looked at, and the result is remembered from then on.
+/// Because the tail isn't computed until it is needed, streams can be
+/// infinite:
+///
+/// ```rust
+/// # use cons::Stream;
+/// let naturals = Stream::iterate(0u64, |n| n + 1);
+/// let evens = naturals.filter(|n| n % 2 == 0).map(|n| n * 10);
+/// assert_eq!(evens.iter().take(3).copied().collect::<Vec<_>>(), vec![0, 20, 40]);
+/// ```
+///
+/// Streams can also be defined recursively with [`Stream::lazy`], which
+/// makes the classic sieve of Eratosthenes a few lines long:
+///
+/// ```rust
+/// # use cons::Stream;
+/// fn sieve(s: Stream<u64>) -> Stream<u64> {
+///     Stream::lazy(move || {
+///         let (&p, rest) = s.uncons()?;
+///         Some((p, sieve(rest.filter(move |n| n % p != 0))))
+///     })
+/// }
+///
+/// let primes = sieve(Stream::iterate(2, |n| n + 1));
+/// assert_eq!(primes.take(5).iter().copied().collect::<Vec<_>>(), vec![2, 3, 5, 7, 11]);
+/// ```
+///
+/// Like [`List`](crate::List), cloning a stream is O(1) and the clone
+/// shares both its cells and anything they have already computed.
+pub struct Stream<T> {
+    cell: Rc<LazyCell<Node<T>, Thunk<T>>>,
+}
+
+impl<T: 'static> Stream<T> {
+    /// Creates a stream whose first cell is computed by `f` the first
+    /// time it is needed. `f` returns `None` for the empty stream, or
+    /// the head and the (usually also lazy) tail.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// fn countdown(n: u32) -> Stream<u32> {
+    ///     Stream::lazy(move || (n > 0).then(|| (n, countdown(n - 1))))
+    /// }
+    ///
+    /// assert_eq!(countdown(3).iter().copied().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// ```
+    pub fn lazy<F>(f: F) -> Self
+    where
+        F: FnOnce() -> Option<(T, Self)> + 'static,
+    {
+        Self {
+            cell: Rc::new(LazyCell::new(Box::new(f))),
+        }
+    }
+
+    /// Creates an empty stream (SML's `nil`).
+    pub fn empty() -> Self {
+        Self::lazy(|| None)
+    }
+
+    /// Returns a new stream with `elem` at the front and `tail` after
+    /// it. This is SML's `elem :: tail`.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// let s = Stream::cons(1, Stream::cons(2, Stream::empty()));
+    /// assert_eq!(s.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn cons(elem: T, tail: Self) -> Self {
+        Self::lazy(move || Some((elem, tail)))
+    }
+
+    /// Builds a stream from a seed. `f` is called with the current
+    /// state each time another element is needed and returns the
+    /// element along with the next state, or `None` to end the stream.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// let fib = Stream::unfold((0u64, 1u64), |(a, b)| Some((a, (b, a + b))));
+    /// assert_eq!(
+    ///     fib.iter().take(8).copied().collect::<Vec<_>>(),
+    ///     vec![0, 1, 1, 2, 3, 5, 8, 13]
+    /// );
+    /// ```
+    pub fn unfold<S, F>(seed: S, f: F) -> Self
+    where
+        S: 'static,
+        F: Fn(S) -> Option<(T, S)> + 'static,
+    {
+        fn node<T: 'static, S: 'static, F>(seed: S, f: Rc<F>) -> Node<T>
+        where
+            F: Fn(S) -> Option<(T, S)> + 'static,
+        {
+            let (x, seed) = f(seed)?;
+            Some((x, Stream::lazy(move || node(seed, f))))
+        }
+
+        let f = Rc::new(f);
+        Self::lazy(move || node(seed, f))
+    }
+}
+
+impl<T: Clone + 'static> Stream<T> {
+    /// Creates the infinite stream `x, f(x), f(f(x)), ...`. Each
+    /// application of `f` happens only when that element is reached.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// let powers = Stream::iterate(1, |n| n * 2);
+    /// assert_eq!(powers.iter().nth(10), Some(&1024));
+    /// ```
+    pub fn iterate<F>(x: T, f: F) -> Self
+    where
+        F: Fn(&T) -> T + 'static,
+    {
+        fn node<T: Clone + 'static, F>(x: T, f: Rc<F>) -> Node<T>
+        where
+            F: Fn(&T) -> T + 'static,
+        {
+            let prev = x.clone();
+            Some((x, Stream::lazy(move || node(f(&prev), f))))
+        }
+
+        let f = Rc::new(f);
+        Self::lazy(move || node(x, f))
+    }
+
+    /// Creates the infinite stream `x, x, x, ...`.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// let ones = Stream::repeat(1);
+    /// assert_eq!(ones.take(3).iter().sum::<i32>(), 3);
+    /// ```
+    pub fn repeat(x: T) -> Self {
+        Self::iterate(x, T::clone)
+    }
+
+    /// Returns a stream of at most the first `n` elements of `self`.
+    pub fn take(&self, n: usize) -> Self {
+        let s = self.clone();
+        Self::lazy(move || {
+            if n == 0 {
+                return None;
+            }
+            let (x, tl) = s.uncons()?;
+            Some((x.clone(), tl.take(n - 1)))
+        })
+    }
+
+    /// Returns a stream of the elements of `self` for which `p`
+    /// returns `true`.
+    ///
+    /// Looking at a cell of the filtered stream forces `self` until a
+    /// matching element is found, so filtering an infinite stream that
+    /// has no more matches never returns.
+    pub fn filter<P>(&self, p: P) -> Self
+    where
+        P: Fn(&T) -> bool + 'static,
+    {
+        fn node<T: Clone + 'static, P>(mut s: Stream<T>, p: Rc<P>) -> Node<T>
+        where
+            P: Fn(&T) -> bool + 'static,
+        {
+            loop {
+                let (x, tl) = s.uncons()?;
+                if p(x) {
+                    let x = x.clone();
+                    return Some((x, Stream::lazy(move || node(tl, p))));
+                }
+                s = tl;
+            }
+        }
+
+        let s = self.clone();
+        let p = Rc::new(p);
+        Self::lazy(move || node(s, p))
+    }
+}
+
+impl<T> Stream<T> {
+    fn force(&self) -> Option<&(T, Self)> {
+        LazyCell::force(&self.cell).as_ref()
+    }
+
+    /// Returns the first element of the stream, forcing it if
+    /// necessary.
+    pub fn head(&self) -> Option<&T> {
+        self.force().map(|(x, _)| x)
+    }
+
+    /// Returns everything but the first element of the stream, or
+    /// `None` if it is empty. Only the first cell is forced.
+    pub fn tail(&self) -> Option<Self> {
+        self.force().map(|(_, tl)| tl.clone())
+    }
+
+    /// Splits the stream into its head and tail, or returns `None` if
+    /// it is empty.
+    ///
+    /// ```rust
+    /// # use cons::Stream;
+    /// let s = Stream::iterate(1, |n| n + 1);
+    /// let (x, tl) = s.uncons().unwrap();
+    /// assert_eq!(*x, 1);
+    /// assert_eq!(tl.head(), Some(&2));
+    /// ```
+    pub fn uncons(&self) -> Option<(&T, Self)> {
+        self.force().map(|(x, tl)| (x, tl.clone()))
+    }
+
+    /// Returns `true` if the stream has no elements. This forces the
+    /// first cell.
+    pub fn is_empty(&self) -> bool {
+        self.force().is_none()
+    }
+
+    /// Returns an iterator over references to the elements of the
+    /// stream, forcing each cell as it is reached.
+    ///
+    /// The iterator works with the cons macros too:
+    ///
+    /// ```rust
+    /// # use cons::{cons_ref, Stream};
+    /// let s = Stream::iterate(1, |n| n * 3);
+    /// cons_ref!(s as _::x::y::_);
+    /// assert_eq!((x, y), (&3, &9));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self }
+    }
+
+    /// Returns a stream of `f` applied to each element of `self`.
+    pub fn map<U, F>(&self, f: F) -> Stream<U>
+    where
+        T: 'static,
+        U: 'static,
+        F: Fn(&T) -> U + 'static,
+    {
+        fn node<T: 'static, U: 'static, F>(s: Stream<T>, f: Rc<F>) -> Node<U>
+        where
+            F: Fn(&T) -> U + 'static,
+        {
+            let (x, tl) = s.force()?;
+            let (x, tl) = (f(x), tl.clone());
+            Some((x, Stream::lazy(move || node(tl, f))))
+        }
+
+        let s = self.clone();
+        let f = Rc::new(f);
+        Stream::lazy(move || node(s, f))
+    }
+}
+
+impl<T> Clone for Stream<T> {
+    /// Returns a stream sharing all of its cells with `self`. This is
+    /// O(1).
+    fn clone(&self) -> Self {
+        Self {
+            cell: Rc::clone(&self.cell),
+        }
+    }
+}
+
+impl<T> Drop for Stream<T> {
+    /// Drops the forced cells one at a time rather than recursively, so
+    /// a long stream that has been walked can't overflow the stack.
+    /// Stops at the first cell that is shared or hasn't been forced.
+    fn drop(&mut self) {
+        let mut next = take_tail(&mut self.cell);
+        while let Some(mut s) = next {
+            next = take_tail(&mut s.cell);
+        }
+    }
+}
+
+/// Takes the tail out of a cell that nothing else refers to and that
+/// has already been forced.
+fn take_tail<T>(cell: &mut Rc<LazyCell<Node<T>, Thunk<T>>>) -> Option<Stream<T>> {
+    let (_, tail) = LazyCell::get_mut(Rc::get_mut(cell)?)?.take()?;
+    Some(tail)
+}
+
+impl<T: 'static> Default for Stream<T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stream<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over references to the elements of a [`Stream`].
+///
+/// Created by [`Stream::iter`].
+pub struct Iter<'a, T> {
+    next: &'a Stream<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, tl) = self.next.force()?;
+        self.next = tl;
+        Some(x)
+    }
+}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self { next: self.next }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stream;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_cells_are_memoized() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let s = Stream::unfold(0, move |n| {
+            counter.set(counter.get() + 1);
+            Some((n, n + 1))
+        });
+        assert_eq!(calls.get(), 0);
+        assert_eq!(s.iter().take(3).count(), 3);
+        assert_eq!(calls.get(), 3);
+        let t = s.clone();
+        assert_eq!(t.iter().take(3).copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_iterate_is_lazy() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let s = Stream::iterate(1, move |n| {
+            counter.set(counter.get() + 1);
+            n * 2
+        });
+        assert_eq!(s.head(), Some(&1));
+        assert_eq!(calls.get(), 0);
+        assert_eq!(s.tail().unwrap().head(), Some(&2));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_finite() {
+        let s = Stream::cons(1, Stream::cons(2, Stream::empty()));
+        assert!(!s.is_empty());
+        assert_eq!(s.map(|x| x * 10).iter().copied().collect::<Vec<_>>(), vec![10, 20]);
+        assert!(s.tail().unwrap().tail().unwrap().is_empty());
+        assert!(Stream::<i32>::empty().tail().is_none());
+    }
+
+    #[test]
+    fn test_take() {
+        let s = Stream::repeat('a').take(3);
+        assert_eq!(s.iter().collect::<String>(), "aaa");
+        assert!(Stream::repeat(0).take(0).is_empty());
+    }
+
+    #[test]
+    fn test_filter_skips_long_runs() {
+        let s = Stream::iterate(0u32, |n| n + 1).filter(|n| n % 100_000 == 0);
+        assert_eq!(s.iter().nth(2), Some(&200_000));
+    }
+
+    #[test]
+    fn test_long_stream_drop() {
+        let s = Stream::iterate(0u64, |x| x + 1);
+        assert_eq!(s.iter().nth(1_000_000), Some(&1_000_000));
+        drop(s);
+    }
+
+    #[test]
+    fn test_cons_on_stream() {
+        let s = Stream::unfold(1, |n| (n <= 3).then_some((n, n + 1)));
+        crate::cons_ref!(s as x::y::z::nil);
+        assert_eq!((x, y, z), (&1, &2, &3));
+    }
+}
@@ -0,0 +1,328 @@
+use core::cell::Cell;
+use core::iter::FusedIterator;
+
+/// Types that can be split into their first element and the rest.
+///
+/// This is what [`cons!`](crate::cons), [`try_cons!`](crate::try_cons),
+/// and [`cons_match!`](crate::cons_match) use to take values apart, so
+/// anything that implements it can be destructured with the same
+/// syntax. Everything that implements [`IntoIterator`] already does,
+/// with the iterator as the rest.
+///
+/// # Examples
+///
+/// A type that isn't a collection, such as a parser's input, can
+/// implement it directly:
+///
+/// ```rust
+/// use cons::{cons, Uncons};
+///
+/// struct Words<'a>(&'a str);
+///
+/// impl<'a> Uncons for Words<'a> {
+///     type Head = &'a str;
+///     type Rest = Words<'a>;
+///
+///     fn uncons(self) -> Option<(&'a str, Words<'a>)> {
+///         let s = self.0.trim_start();
+///         if s.is_empty() {
+///             return None;
+///         }
+///         let end = s.find(' ').unwrap_or(s.len());
+///         Some((&s[..end], Words(&s[end..])))
+///     }
+/// }
+///
+/// cons!((Words("  mv a.txt  b.txt ")) as "mv"::src::dst::nil);
+/// assert_eq!((src, dst), ("a.txt", "b.txt"));
+///
+/// cons!((Words("cd ~ /tmp")) as cmd::rest @ iter);
+/// assert_eq!(cmd, "cd");
+/// assert_eq!(rest.0, " ~ /tmp");
+/// ```
+///
+/// A struct with a single field, such as a newtype around a `Vec` or a
+/// slice, can derive the trait instead. The derived impl splits the
+/// field:
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// use cons::{cons, cons_match, Uncons};
+///
+/// #[derive(Uncons)]
+/// struct Path(Vec<&'static str>);
+///
+/// let path = Path(vec!["users", "7", "posts"]);
+/// let page = cons_match!(path {
+///     "users"::id::nil => format!("user {id}"),
+///     "users"::id::rest => format!("{} of user {id}", rest.join("/")),
+///     _ => String::from("not found"),
+/// });
+/// assert_eq!(page, "posts of user 7");
+///
+/// #[derive(Uncons)]
+/// struct Args<'a> {
+///     args: &'a [String],
+/// }
+///
+/// let argv = [String::from("cat"), String::from("a.txt")];
+/// cons!((Args { args: &argv }) as _::file::nil);
+/// assert_eq!(file, "a.txt");
+/// # }
+/// ```
+///
+/// With `@ iter`, the tail is bound as the `Rest` value itself. The
+/// other tails (`nil`, `_`, and binding the tail as a `Vec`) take the
+/// remaining elements one at a time with `uncons`.
+pub trait Uncons: Sized {
+    /// The type of the elements.
+    type Head;
+
+    /// What is left after taking the first element. Splitting it again
+    /// has to give the same types back.
+    type Rest: Uncons<Head = Self::Head, Rest = Self::Rest>;
+
+    /// Splits off the first element, or returns `None` if there are no
+    /// elements left.
+    fn uncons(self) -> Option<(Self::Head, Self::Rest)>;
+}
+
+impl<I: IntoIterator> Uncons for I {
+    type Head = I::Item;
+    type Rest = I::IntoIter;
+
+    fn uncons(self) -> Option<(I::Item, I::IntoIter)> {
+        let mut iter = self.into_iter();
+        let x = iter.next()?;
+        Some((x, iter))
+    }
+}
+
+/// Iterates over the elements of an [`Uncons`] value by splitting off
+/// one at a time.
+#[doc(hidden)]
+pub struct Elements<U: Uncons> {
+    first: Option<U>,
+    rest: Option<U::Rest>,
+}
+
+impl<U: Uncons> Elements<U> {
+    pub fn new(value: U) -> Self {
+        Self {
+            first: Some(value),
+            rest: None,
+        }
+    }
+}
+
+impl<U: Uncons> Iterator for Elements<U> {
+    type Item = U::Head;
+
+    fn next(&mut self) -> Option<U::Head> {
+        let (x, rest) = match self.first.take() {
+            Some(first) => first.uncons()?,
+            None => self.rest.take()?.uncons()?,
+        };
+        self.rest = Some(rest);
+        Some(x)
+    }
+}
+
+impl<U: Uncons> FusedIterator for Elements<U> {}
+
+/// Lets the macros treat a value as an iterator when it is one, and
+/// fall back to [`Uncons`] otherwise, without specialization.
+///
+/// The macros call methods on `&&Dispatch<T>`. Method resolution tries
+/// the impl on `&Dispatch<T>` first, which only applies when `T` is
+/// [`IntoIterator`], and then the one on `Dispatch<T>`. This only works
+/// because the macros are expanded where `T` is a concrete type; in
+/// generic code the fallback is always used.
+#[doc(hidden)]
+pub struct Dispatch<T>(Cell<Option<T>>);
+
+impl<T> Dispatch<T> {
+    pub fn new(value: T) -> Self {
+        Self(Cell::new(Some(value)))
+    }
+
+    fn take(&self) -> T {
+        self.0.take().expect("value already taken")
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaIntoIterator {
+    type Iter: Iterator;
+
+    /// The value to start matching a pattern against.
+    fn __cons_start(&self) -> Self::Iter;
+
+    /// An iterator over the elements.
+    fn __cons_iter(&self) -> Self::Iter;
+}
+
+impl<I: IntoIterator> ViaIntoIterator for &Dispatch<I> {
+    type Iter = I::IntoIter;
+
+    fn __cons_start(&self) -> I::IntoIter {
+        self.take().into_iter()
+    }
+
+    fn __cons_iter(&self) -> I::IntoIter {
+        self.take().into_iter()
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaUncons {
+    type Value: Uncons;
+
+    fn __cons_start(&self) -> Self::Value;
+
+    fn __cons_iter(&self) -> Elements<Self::Value>;
+}
+
+impl<U: Uncons> ViaUncons for Dispatch<U> {
+    type Value = U;
+
+    fn __cons_start(&self) -> U {
+        self.take()
+    }
+
+    fn __cons_iter(&self) -> Elements<U> {
+        Elements::new(self.take())
+    }
+}
+
+/// The number of elements left, if it is known up front, dispatched
+/// the same way as [`Dispatch`].
+///
+/// This goes by the iterator's size hint rather than requiring
+/// [`ExactSizeIterator`], which is what `len` uses anyway, and which
+/// would be ambiguous for ranges of integers whose type hasn't been
+/// inferred yet.
+#[doc(hidden)]
+pub struct Len<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait KnownLen {
+    fn __cons_len(&self) -> Option<usize>;
+}
+
+impl<I: Iterator> KnownLen for &Len<'_, I> {
+    fn __cons_len(&self) -> Option<usize> {
+        match self.0.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower),
+            _ => None,
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait UnknownLen {
+    fn __cons_len(&self) -> Option<usize>;
+}
+
+impl<T> UnknownLen for Len<'_, T> {
+    fn __cons_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use crate::cons_match;
+    use crate::{cons, try_cons, ConsError, Uncons};
+
+    /// A countdown that isn't an iterator.
+    struct Countdown(u32);
+
+    impl Uncons for Countdown {
+        type Head = u32;
+        type Rest = Countdown;
+
+        fn uncons(self) -> Option<(u32, Countdown)> {
+            self.0.checked_sub(1).map(|n| (self.0, Countdown(n)))
+        }
+    }
+
+    #[test]
+    fn test_cons_custom_type() {
+        cons!((Countdown(2)) as 2::1::nil);
+        cons!((Countdown(3)) as _::rest @ iter);
+        assert_eq!(rest.0, 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_cons_custom_type_tail() {
+        cons!((Countdown(4)) as x::y::rest);
+        assert_eq!((x, y), (4, 3));
+        assert_eq!(rest, vec![2, 1]);
+    }
+
+    fn take_three(n: u32) -> Result<Vec<u32>, ConsError> {
+        try_cons!((Countdown(n)) as a::b::c::_);
+        Ok(vec![a, b, c])
+    }
+
+    #[test]
+    fn test_try_cons_custom_type() {
+        assert_eq!(take_three(5), Ok(vec![5, 4, 3]));
+        assert_eq!(
+            take_three(2),
+            Err(ConsError::Exhausted { missing_binding: "c", got: 2 })
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_cons_match_custom_type() {
+        let describe = |n| {
+            cons_match!((Countdown(n)) {
+                nil => String::from("liftoff"),
+                x::nil => format!("last {x}"),
+                x::rest => format!("{x} then {}", rest.len()),
+            })
+        };
+        assert_eq!(describe(0), "liftoff");
+        assert_eq!(describe(1), "last 1");
+        assert_eq!(describe(3), "3 then 2");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[derive(Uncons)]
+    struct Stack<T, const N: usize = 4>
+    where
+        T: Copy,
+    {
+        items: [T; N],
+    }
+
+    #[derive(Uncons)]
+    pub(crate) struct Window<'a, T>(#[allow(dead_code)] pub &'a [T]);
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_derive() {
+        let stack = Stack { items: [1, 2, 3, 4] };
+        cons!(stack as a::b::rest);
+        assert_eq!((a, b, rest), (1, 2, vec![3, 4]));
+    }
+
+    #[test]
+    fn test_derive_tuple_struct() {
+        let items = [(1, 'a'), (2, 'b')];
+        cons!((Window(&items)) as ((n, c))::_);
+        assert_eq!((n, c), (&1, &'a'));
+    }
+
+    #[test]
+    fn test_into_iterator_rest_is_iterator() {
+        let (x, mut rest) = Uncons::uncons(vec![1, 2, 3]).unwrap();
+        assert_eq!(x, 1);
+        assert_eq!(rest.next_back(), Some(3));
+    }
+}
@@ -0,0 +1,250 @@
+//! A list stored in one contiguous buffer.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::slice;
+
+/// A list backed by a [`Vec`] and the index of its head, for code that
+/// needs the elements as a slice.
+///
+/// [`List`](crate::List) and [`ChunkedList`](crate::ChunkedList) spread
+/// their elements over many nodes, so they can't be handed to FFI or
+/// SIMD code as a `&[T]`. A `VecList` keeps them in one buffer, so
+/// [`as_slice`](VecList::as_slice) is free, and
+/// [`uncons`](VecList::uncons) is O(1) because it just moves the head
+/// forward:
+///
+/// ```rust
+/// # use cons::VecList;
+/// let mut xs = VecList::from_vec(vec![1, 2, 3, 4]);
+/// assert_eq!(xs.uncons(), Some(&1));
+/// assert_eq!(xs.as_slice(), [2, 3, 4]);
+/// assert_eq!(xs.head(), Some(&2));
+/// assert_eq!(xs.len(), 3);
+/// ```
+///
+/// The price is persistence: the list owns its buffer, so it changes
+/// in place instead of sharing nodes with other lists, and cloning it
+/// copies the elements.
+///
+/// Elements that are unconsed stay in the buffer until they are
+/// overwritten by [`cons`](VecList::cons) or the list is dropped or
+/// turned back into a `Vec`.
+pub struct VecList<T> {
+    elems: Vec<T>,
+    head: usize,
+}
+
+impl<T> VecList<T> {
+    /// Creates a new, empty list.
+    pub const fn new() -> Self {
+        Self {
+            elems: Vec::new(),
+            head: 0,
+        }
+    }
+
+    /// Creates a list with the elements of a vector, in order, reusing
+    /// its buffer.
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        Self {
+            elems: vec,
+            head: 0,
+        }
+    }
+
+    /// Adds `elem` to the front of the list.
+    ///
+    /// This is O(1) when it can reuse the slot of an element that was
+    /// unconsed, and O(n) otherwise, since the elements have to be
+    /// shifted along.
+    ///
+    /// ```rust
+    /// # use cons::VecList;
+    /// let mut xs = VecList::from_vec(vec![1, 2]);
+    /// xs.uncons();
+    /// xs.cons(10); // takes the slot `1` was in
+    /// xs.cons(20); // shifts the elements
+    /// assert_eq!(xs.as_slice(), [20, 10, 2]);
+    /// ```
+    pub fn cons(&mut self, elem: T) {
+        if self.head > 0 {
+            self.head -= 1;
+            self.elems[self.head] = elem;
+        } else {
+            self.elems.insert(0, elem);
+        }
+    }
+
+    /// Returns a reference to the first element, or `None` if the list
+    /// is empty.
+    pub fn head(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Returns everything but the first element, or `None` if the list
+    /// is empty.
+    pub fn tail(&self) -> Option<&[T]> {
+        self.as_slice().split_first().map(|(_, rest)| rest)
+    }
+
+    /// Removes the first element from the list and returns a reference
+    /// to it, or returns `None` if the list is empty. This is O(1).
+    pub fn uncons(&mut self) -> Option<&T> {
+        let head = self.elems.get(self.head)?;
+        self.head += 1;
+        Some(head)
+    }
+
+    /// Returns `true` if the list has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of elements in the list.
+    pub fn len(&self) -> usize {
+        self.elems.len() - self.head
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is
+    /// out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    /// Returns the elements as a slice. This is O(1).
+    pub fn as_slice(&self) -> &[T] {
+        &self.elems[self.head..]
+    }
+
+    /// Returns the elements as a mutable slice. This is O(1).
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.elems[self.head..]
+    }
+
+    /// Returns an iterator over references to the elements.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    /// Turns the list back into a vector, dropping any unconsed
+    /// elements that are still in the buffer.
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.elems.drain(..self.head);
+        self.elems
+    }
+}
+
+impl<T: Clone> Clone for VecList<T> {
+    /// Copies the elements of the list, leaving out any that were
+    /// unconsed.
+    fn clone(&self) -> Self {
+        Self::from_vec(self.as_slice().to_vec())
+    }
+}
+
+impl<T> Default for VecList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VecList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for VecList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq> Eq for VecList<T> {}
+
+impl<T: Hash> Hash for VecList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl<T> AsRef<[T]> for VecList<T> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T> AsMut<[T]> for VecList<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T> From<Vec<T>> for VecList<T> {
+    fn from(vec: Vec<T>) -> Self {
+        Self::from_vec(vec)
+    }
+}
+
+impl<T> From<VecList<T>> for Vec<T> {
+    fn from(list: VecList<T>) -> Self {
+        list.into_vec()
+    }
+}
+
+impl<T> FromIterator<T> for VecList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecList<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    #[test]
+    fn test_uncons_and_cons() {
+        let mut xs = (1..=4).collect::<VecList<i32>>();
+        let ptr = xs.as_slice().as_ptr();
+        while xs.len() > 1 {
+            xs.uncons();
+        }
+        assert_eq!((xs.head(), xs.tail()), (Some(&4), Some(&[][..])));
+        xs.cons(30);
+        assert_eq!(xs.as_slice(), [30, 4]);
+        // the slot is reused, so nothing moved
+        assert_eq!(xs.as_slice().as_ptr(), ptr.wrapping_add(2));
+        xs.as_mut_slice()[1] = 40;
+        assert_eq!(xs.clone().into_vec(), [30, 40]);
+        assert_eq!(format!("{xs:?}"), "[30, 40]");
+        assert_eq!(VecList::<i32>::new().uncons(), None);
+    }
+
+    #[test]
+    fn test_unconsed_elements_are_dropped() {
+        let s = Rc::new(());
+        let mut xs = VecList::from_vec(vec![Rc::clone(&s), Rc::clone(&s), Rc::clone(&s)]);
+        xs.uncons();
+        xs.uncons();
+        assert_eq!(Rc::strong_count(&s), 4);
+        xs.cons(Rc::clone(&s));
+        assert_eq!(Rc::strong_count(&s), 4);
+        let v = Vec::from(xs);
+        assert_eq!((v.len(), Rc::strong_count(&s)), (2, 3));
+        let ys = VecList::from(v);
+        crate::cons!((&ys) as a::rest);
+        assert!(Rc::ptr_eq(a, rest[0]));
+    }
+}
@@ -0,0 +1,305 @@
+//! A list whose length is part of its type.
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+use crate::cons_array::FixedLen;
+use crate::list::{self, List};
+
+/// Creates a [`Vect`] containing the arguments, in order. Its length
+/// is the number of arguments.
+///
+/// ```rust
+/// # use cons::vect;
+/// let v = vect![1, 2, 3];
+/// assert_eq!(v.len(), 3);
+/// assert_eq!(*v.head(), 1);
+/// ```
+#[macro_export]
+macro_rules! vect {
+    () => {
+        $crate::Vect::new()
+    };
+    ($($elem:expr),+ $(,)?) => {
+        $crate::vect!(@__rev [] $($elem),+)
+    };
+    (@__rev [$($rev:expr),*] $elem:expr $(, $rest:expr)*) => {
+        $crate::vect!(@__rev [$elem $(, $rev)*] $($rest),*)
+    };
+    (@__rev [$($rev:expr),*]) => {
+        $crate::Vect::new()$(.cons($rev))*
+    };
+}
+
+/// A type-level natural number, used as the length of a [`Vect`].
+///
+/// The numbers are [`Zero`], [`Succ<Zero>`], `Succ<Succ<Zero>>`, and
+/// so on. This trait is sealed and can't be implemented outside this
+/// crate.
+pub trait Nat: sealed::Sealed + 'static {
+    /// The number as a value.
+    const LEN: usize;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The type-level number 0.
+pub enum Zero {}
+
+/// The type-level number one more than `N`.
+pub struct Succ<N: Nat>(PhantomData<N>);
+
+impl sealed::Sealed for Zero {}
+
+impl Nat for Zero {
+    const LEN: usize = 0;
+}
+
+impl<N: Nat> sealed::Sealed for Succ<N> {}
+
+impl<N: Nat> Nat for Succ<N> {
+    const LEN: usize = N::LEN + 1;
+}
+
+/// A [`List`] whose length is tracked in its type, so that taking the
+/// head of an empty list is a type error rather than a `None`.
+///
+/// Consing onto a `Vect<T, N>` gives a `Vect<T, Succ<N>>`, and
+/// [`head`](Vect::head), [`tail`](Vect::tail), and
+/// [`uncons`](Vect::uncons) only exist when the length is a
+/// [`Succ`], so they don't need to return an `Option`:
+///
+/// ```rust
+/// # use cons::vect;
+/// let v = vect![1, 2];
+/// let (x, rest) = v.uncons();
+/// let (y, rest) = rest.uncons();
+/// assert_eq!((*x, *y), (1, 2));
+/// assert!(rest.is_empty());
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::vect;
+/// let (_, rest) = vect![1].uncons();
+/// rest.uncons(); // `rest` is a `Vect<i32, Zero>`
+/// ```
+///
+/// The length is a type-level number rather than a const generic,
+/// since stable Rust can't compute `N + 1` in a type.
+///
+/// With [`cons_array!`](crate::cons_array), a cons pattern is checked
+/// against the length at compile time, just like for arrays:
+///
+/// ```rust
+/// # use cons::{cons_array, vect};
+/// cons_array!((vect![1, 2, 3]) as x::rest);
+/// assert_eq!((x, rest), (1, vec![2, 3]));
+/// ```
+///
+/// ```rust,compile_fail
+/// # use cons::{cons_array, vect};
+/// cons_array!((vect![1, 2]) as x::y::z::nil);
+/// ```
+///
+/// Like a [`List`], the nodes are shared, so cloning and consing are
+/// O(1).
+pub struct Vect<T, N: Nat> {
+    list: List<T>,
+    len: PhantomData<N>,
+}
+
+impl<T> Vect<T, Zero> {
+    /// Creates a new, empty `Vect`.
+    pub const fn new() -> Self {
+        Self::from_list(List::new())
+    }
+}
+
+impl<T, N: Nat> Vect<T, N> {
+    const fn from_list(list: List<T>) -> Self {
+        Self {
+            list,
+            len: PhantomData,
+        }
+    }
+
+    /// Returns a new `Vect` with `elem` at the front and `self` as the
+    /// tail, one element longer.
+    ///
+    /// ```rust
+    /// # use cons::Vect;
+    /// let v = Vect::new().cons(2).cons(1);
+    /// assert_eq!(*v.head(), 1);
+    /// assert_eq!(v.len(), 2);
+    /// ```
+    #[must_use]
+    pub fn cons(&self, elem: T) -> Vect<T, Succ<N>> {
+        Vect::from_list(self.list.cons(elem))
+    }
+
+    /// Returns the number of elements, which is `N::LEN`.
+    pub const fn len(&self) -> usize {
+        N::LEN
+    }
+
+    /// Returns `true` if the length is [`Zero`].
+    pub const fn is_empty(&self) -> bool {
+        N::LEN == 0
+    }
+
+    /// Returns an iterator over references to the elements.
+    pub fn iter(&self) -> list::Iter<'_, T> {
+        self.list.iter()
+    }
+
+    /// Returns the elements as an ordinary [`List`], sharing its nodes.
+    pub fn as_list(&self) -> &List<T> {
+        &self.list
+    }
+}
+
+impl<T, N: Nat> Vect<T, Succ<N>> {
+    /// Returns a reference to the first element.
+    pub fn head(&self) -> &T {
+        self.list.head().unwrap()
+    }
+
+    /// Returns everything but the first element, one element shorter.
+    pub fn tail(&self) -> Vect<T, N> {
+        Vect::from_list(self.list.tail().unwrap())
+    }
+
+    /// Splits the `Vect` into its head and tail.
+    pub fn uncons(&self) -> (&T, Vect<T, N>) {
+        let (x, xs) = self.list.uncons().unwrap();
+        (x, Vect::from_list(xs))
+    }
+}
+
+impl<T, N: Nat> FixedLen for Vect<T, N> {
+    const LEN: usize = N::LEN;
+}
+
+impl<T, N: Nat> Clone for Vect<T, N> {
+    fn clone(&self) -> Self {
+        Self::from_list(self.list.clone())
+    }
+}
+
+impl<T> Default for Vect<T, Zero> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug, N: Nat> fmt::Debug for Vect<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.list, f)
+    }
+}
+
+impl<T: PartialEq, N: Nat> PartialEq for Vect<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.list == other.list
+    }
+}
+
+impl<T: Eq, N: Nat> Eq for Vect<T, N> {}
+
+impl<T: Hash, N: Nat> Hash for Vect<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.list.hash(state);
+    }
+}
+
+impl<T, N: Nat> From<Vect<T, N>> for List<T> {
+    fn from(v: Vect<T, N>) -> List<T> {
+        v.list
+    }
+}
+
+/// Checks that the list has exactly `N::LEN` elements, and gives it
+/// back if it doesn't.
+impl<T, N: Nat> TryFrom<List<T>> for Vect<T, N> {
+    type Error = List<T>;
+
+    fn try_from(list: List<T>) -> Result<Self, List<T>> {
+        if list.len() == N::LEN {
+            Ok(Self::from_list(list))
+        } else {
+            Err(list)
+        }
+    }
+}
+
+impl<'a, T, N: Nat> IntoIterator for &'a Vect<T, N> {
+    type Item = &'a T;
+    type IntoIter = list::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+impl<T: Clone, N: Nat> IntoIterator for Vect<T, N> {
+    type Item = T;
+    type IntoIter = list::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cons_array, list};
+
+    fn sum<N: Nat>(v: &Vect<i32, N>) -> i32 {
+        v.iter().sum()
+    }
+
+    fn second<T: Clone, N: Nat>(v: &Vect<T, Succ<Succ<N>>>) -> T {
+        v.tail().head().clone()
+    }
+
+    #[test]
+    fn test_lengths() {
+        let empty: Vect<i32, Zero> = vect![];
+        assert!(empty.is_empty());
+        let v = empty.cons(3).cons(2).cons(1);
+        assert_eq!(v, vect![1, 2, 3]);
+        assert_eq!((v.len(), v.tail().len()), (3, 2));
+        assert_eq!(sum(&v), 6);
+        assert_eq!(second(&v), 2);
+        assert_eq!(format!("{v:?}"), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn test_shares_nodes() {
+        let v = vect![String::from("a"), String::from("b")];
+        let w = v.cons(String::from("z"));
+        assert!(std::ptr::eq(v.head(), w.tail().head()));
+    }
+
+    #[test]
+    fn test_list_conversions() {
+        let v: Vect<_, Succ<Succ<Zero>>> = Vect::try_from(list![1, 2]).unwrap();
+        assert_eq!(List::from(v), list![1, 2]);
+        assert_eq!(Vect::<_, Succ<Zero>>::try_from(list![1, 2]), Err(list![1, 2]));
+    }
+
+    #[test]
+    fn test_cons_array() {
+        cons_array!((vect![1, 2]) as x::y::nil);
+        assert_eq!((x, y), (1, 2));
+
+        let v = vect!['a', 'b', 'c'];
+        cons_array!((v.tail()) as b::rest);
+        assert_eq!((b, rest), ('b', vec!['c']));
+        assert_eq!(v.len(), 3);
+    }
+}
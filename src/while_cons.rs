@@ -0,0 +1,116 @@
+/// Repeatedly takes the heads of a cons pattern from an iterator and
+/// runs a block with them, like `while let` does for ordinary
+/// patterns.
+///
+/// The tail of the pattern is bound to the rest of the iterator (as a
+/// mutable reference), so the block can take more elements itself
+/// before the next round:
+///
+/// ```rust
+/// # use cons::while_cons;
+/// let mut pairs = vec![];
+/// while_cons!((1..=5) as k::v::_ {
+///     pairs.push((k, v));
+/// });
+/// assert_eq!(pairs, [(1, 2), (3, 4)]);
+///
+/// let mut args = vec![];
+/// while_cons!((["-o", "out", "-v", "in"]) as arg::rest {
+///     if arg == "-o" {
+///         // skip the option's value
+///         rest.next();
+///     } else {
+///         args.push(arg);
+///     }
+/// });
+/// assert_eq!(args, ["-v", "in"]);
+/// ```
+///
+/// The heads use the same syntax as [`cons!`](crate::cons), including
+/// literals and guards. The loop ends as soon as the pattern doesn't
+/// match, either because there aren't enough elements left or because
+/// a head doesn't fit, and any elements taken in that round are
+/// dropped. `break` and `continue` work as in any other loop.
+#[macro_export]
+macro_rules! while_cons {
+    ($iter:ident as $($rest:tt)+) => {
+        $crate::while_cons!(@__split $iter => [] $($rest)+);
+    };
+    (($iter:expr) as $($rest:tt)+) => {
+        $crate::while_cons!(@__split $iter => [] $($rest)+);
+    };
+    (@__split $iter:expr => [$($pat:tt)*] $tl:ident $body:block) => {
+        $crate::while_cons!(@__ $iter => [$($pat)* $tl @ iter] $body);
+    };
+    (@__split $iter:expr => [$($pat:tt)*] _ $body:block) => {
+        $crate::while_cons!(@__ $iter => [$($pat)* _] $body);
+    };
+    (@__split $iter:expr => [$($pat:tt)*] $alias:ident ! () :: $($rest:tt)+) => {
+        $crate::while_cons!(@__split $iter => [$($pat)* $alias!() ::] $($rest)+);
+    };
+    (@__split $iter:expr => [$($pat:tt)*] $hd:tt :: $($rest:tt)+) => {
+        $crate::while_cons!(@__split $iter => [$($pat)* $hd ::] $($rest)+);
+    };
+    (@__split $iter:expr => [$($pat:tt)*] $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "expected the pattern to end in a tail name or `_` followed by a block, found `",
+            ::core::stringify!($($rest)*),
+            "`",
+        ));
+    };
+    (@__ $iter:expr => [$($pat:tt)+] $body:block) => {
+        #[allow(unused_mut)]
+        let mut iter = $crate::cons!(@__dispatch __cons_iter $iter);
+        loop {
+            $crate::cons!(@__ [else { break }] (&mut iter) => $($pat)+);
+            $body
+        }
+    };
+    ($($invocation:tt)*) => {
+        $crate::cons!(@__front while_cons [] $($invocation)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_stops_on_mismatch() {
+        let mut seen = vec![];
+        let v = [2, 4, 5, 6];
+        while_cons!(v as (x if x % 2 == 0)::_ {
+            seen.push(x);
+        });
+        assert_eq!(seen, [2, 4]);
+    }
+
+    #[test]
+    fn test_rest_is_left() {
+        let mut iter = "a=1;b=2;c".split(';');
+        let mut pairs = vec![];
+        while_cons!((iter.by_ref()) as pair::_ {
+            let Some((k, v)) = pair.split_once('=') else { break };
+            pairs.push((k, v));
+        });
+        assert_eq!(pairs, [("a", "1"), ("b", "2")]);
+        assert_eq!(iter.next(), None);
+
+        let mut tokens = vec![];
+        while_cons!((vec!["(", "a", ")", "b"]) as tok::rest {
+            if tok == "(" {
+                tokens.push(rest.take_while(|&t| t != ")").collect::<Vec<_>>().concat());
+                continue;
+            }
+            tokens.push(String::from(tok));
+        });
+        assert_eq!(tokens, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_literal_heads() {
+        let mut count = 0;
+        while_cons!(([0, 1, 0, 1, 1]) as 0::1::_ {
+            count += 1;
+        });
+        assert_eq!(count, 2);
+    }
+}